@@ -0,0 +1,57 @@
+//! Standard base64 encoding used for inline binary goldens.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as padded standard base64.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode standard base64, ignoring whitespace. Padding is optional, but a
+/// final character must not leave unused bits set, and a lone one is an error.
+pub(crate) fn decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = vec![];
+    let mut n = 0u32;
+    let mut bits = 0;
+    let mut digits = 0;
+    for c in text
+        .trim_end_matches(|c: char| c == '=' || c.is_whitespace())
+        .chars()
+    {
+        if c.is_whitespace() {
+            continue;
+        }
+        let digit = ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .ok_or_else(|| format!("invalid base64 character {:?}", c))?;
+        n = n << 6 | digit as u32;
+        bits += 6;
+        digits += 1;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((n >> bits) as u8);
+        }
+    }
+    if digits % 4 == 1 {
+        return Err("truncated base64: a lone final character".to_string());
+    }
+    if n & ((1 << bits) - 1) != 0 {
+        return Err("invalid base64: the final character has unused bits set".to_string());
+    }
+    Ok(out)
+}
@@ -0,0 +1,90 @@
+//! Decoding and encoding text by its byte order mark.
+
+/// A text encoding identified by its byte order mark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    /// UTF-8 without a byte order mark.
+    Utf8,
+    /// UTF-8 with a byte order mark.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    const UTF8_BOM: &'static [u8] = &[0xEF, 0xBB, 0xBF];
+    const UTF16_LE_BOM: &'static [u8] = &[0xFF, 0xFE];
+    const UTF16_BE_BOM: &'static [u8] = &[0xFE, 0xFF];
+
+    /// The encoding `bytes` start with the byte order mark of, or UTF-8 if
+    /// they have none.
+    pub(crate) fn detect(bytes: &[u8]) -> Encoding {
+        if bytes.starts_with(Self::UTF8_BOM) {
+            Encoding::Utf8Bom
+        } else if bytes.starts_with(Self::UTF16_LE_BOM) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(Self::UTF16_BE_BOM) {
+            Encoding::Utf16Be
+        } else {
+            Encoding::Utf8
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[],
+            Encoding::Utf8Bom => Self::UTF8_BOM,
+            Encoding::Utf16Le => Self::UTF16_LE_BOM,
+            Encoding::Utf16Be => Self::UTF16_BE_BOM,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    /// Encode `text`, starting with this encoding's byte order mark.
+    pub(crate) fn encode(self, text: &str) -> Vec<u8> {
+        let mut bytes = self.bom().to_vec();
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => bytes.extend_from_slice(text.as_bytes()),
+            Encoding::Utf16Le => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+            Encoding::Utf16Be => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+        }
+        bytes
+    }
+}
+
+/// Decode `bytes` by their byte order mark, returning the encoding and text
+/// without the mark.
+pub(crate) fn decode(bytes: &[u8]) -> Result<(Encoding, String), String> {
+    let encoding = Encoding::detect(bytes);
+    let body = &bytes[encoding.bom().len()..];
+    let invalid = || format!("invalid {}", encoding.name());
+    let text = match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => {
+            String::from_utf8(body.to_vec()).map_err(|_| invalid())?
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            if !body.len().is_multiple_of(2) {
+                return Err(invalid());
+            }
+            let units = body.chunks_exact(2).map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if encoding == Encoding::Utf16Le {
+                    u16::from_le_bytes(pair)
+                } else {
+                    u16::from_be_bytes(pair)
+                }
+            });
+            char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .map_err(|_| invalid())?
+        }
+    };
+    Ok((encoding, text))
+}
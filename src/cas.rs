@@ -0,0 +1,47 @@
+//! The blob directory and index behind [`CasStore`](crate::CasStore).
+//!
+//! Blobs are named by the 128-bit FNV-1a hash of their contents, in hex, under
+//! `.golden-cas/blobs`. The index, `.golden-cas/index`, has a `<hash> <path>`
+//! line for each golden, sorted by path, with `/` separating path components.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The directory, under a store's root, holding the blobs and index.
+pub(crate) const DIR: &str = ".golden-cas";
+
+/// The name of the blob holding `bytes`.
+pub(crate) fn hash(bytes: &[u8]) -> String {
+    let hash = bytes
+        .iter()
+        .fold(0x6c62272e07bb014262b821756295c58d_u128, |hash, &byte| {
+            (hash ^ u128::from(byte)).wrapping_mul(0x0000000001000000000000000000013b)
+        });
+    format!("{:032x}", hash)
+}
+
+/// The index key for a golden's path.
+pub(crate) fn key(path: &Path) -> String {
+    let components: Vec<_> = path.iter().map(|c| c.to_string_lossy()).collect();
+    components.join("/")
+}
+
+/// Parse an index into a map from paths to blob hashes.
+pub(crate) fn parse_index(text: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut index = BTreeMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let (hash, path) = line
+            .split_once(' ')
+            .filter(|(hash, path)| hash.len() == 32 && !path.is_empty())
+            .ok_or_else(|| format!("malformed index line {}: {:?}", i + 1, line))?;
+        index.insert(path.to_string(), hash.to_string());
+    }
+    Ok(index)
+}
+
+pub(crate) fn format_index(index: &BTreeMap<String, String>) -> String {
+    index
+        .iter()
+        .map(|(path, hash)| format!("{} {}\n", hash, path))
+        .collect()
+}
@@ -0,0 +1,91 @@
+//! A minimal CSV parser used by the CSV differs.
+
+/// A parsed record and the line it starts on, counting from 1.
+pub(crate) struct Record {
+    pub(crate) line: usize,
+    pub(crate) fields: Vec<String>,
+}
+
+/// Parse comma-separated records as described by RFC 4180.
+///
+/// Fields may be quoted with `"`, with `""` for a literal quote, and quoted
+/// fields may span lines. Records end with LF or CRLF, and a final line break
+/// doesn't start another record. Every record must have as many fields as the
+/// first.
+pub(crate) fn parse(text: &str) -> Result<Vec<Record>, String> {
+    let mut records: Vec<Record> = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.chars().peekable();
+    let mut at_record_start = true;
+    while let Some(c) = chars.next() {
+        at_record_start = false;
+        match c {
+            '"' if field.is_empty() => {
+                let quoted_from = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => {
+                            return Err(format!(
+                                "unterminated quoted field starting on line {}",
+                                quoted_from
+                            ))
+                        }
+                    }
+                }
+                if !matches!(chars.peek(), None | Some(',' | '\r' | '\n')) {
+                    return Err(format!(
+                        "unexpected text after quoted field on line {}",
+                        line
+                    ));
+                }
+            }
+            '"' => return Err(format!("unexpected quote on line {}", line)),
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                records.push(Record {
+                    line: start,
+                    fields: std::mem::take(&mut fields),
+                });
+                line += 1;
+                start = line;
+                at_record_start = true;
+            }
+            c => field.push(c),
+        }
+    }
+    if !at_record_start {
+        fields.push(field);
+        records.push(Record {
+            line: start,
+            fields,
+        });
+    }
+    if let Some(first) = records.first() {
+        let width = first.fields.len();
+        if let Some(ragged) = records.iter().find(|r| r.fields.len() != width) {
+            return Err(format!(
+                "line {} has {} fields, but the header has {}",
+                ragged.line,
+                ragged.fields.len(),
+                width
+            ));
+        }
+    }
+    Ok(records)
+}
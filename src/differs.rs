@@ -1,60 +1,3231 @@
 //! Functions for comparing files.
 
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::ops::Range;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use similar::{capture_diff_slices_deadline, Algorithm, ChangeTag, DiffOp, TextDiff};
 use similar_asserts;
 
+use crate::bom;
+use crate::csv;
+#[cfg(feature = "dot")]
+use crate::dot;
+#[cfg(feature = "html")]
+use crate::html;
+use crate::jq;
+use crate::json;
+use crate::lockfile;
+#[cfg(feature = "markdown")]
+use crate::markdown;
+#[cfg(feature = "json-schema")]
+use crate::schema;
+use crate::timestamp;
+#[cfg(feature = "vt100")]
+use crate::vt100;
+#[cfg(feature = "wav")]
+use crate::wav;
+#[cfg(feature = "xml")]
+use crate::xml;
+
 /// A function that displays a diff and panics if two files to not match.
 pub type Differ = Box<dyn Fn(&Path, &Path)>;
 
+/// An IO error encountered by a built-in differ.
+///
+/// The built-in differs return it as a [`DiffOutcome::Error`], so
+/// [`Mint::try_check_goldenfiles`](crate::Mint::try_check_goldenfiles) can
+/// report it as an error rather than a mismatch, and their `_diff` forms panic
+/// with its message. Custom differs can report one by raising it with
+/// [`std::panic::panic_any`]. A golden that does not exist
+/// yet is treated as empty by the text differs and is not an error, but one
+/// that isn't valid UTF-8 is.
+#[derive(Debug)]
+pub struct DiffIoError {
+    /// The file that could not be read.
+    pub path: PathBuf,
+    /// The underlying error.
+    pub error: io::Error,
+}
+
+impl fmt::Display for DiffIoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error reading {:?}: {}", self.path, self.error)
+    }
+}
+
+impl error::Error for DiffIoError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The result of comparing two files without panicking.
+///
+/// Every built-in differ has a form returning one, named with `_outcome` in
+/// place of `_diff`, such as [`text_outcome`] for [`text_diff`] and
+/// [`json_outcome_ignoring_keys`] for [`json_diff_ignoring_keys`]. The Mint
+/// calls these directly, so only custom differs are run under
+/// [`std::panic::catch_unwind`].
+#[derive(Debug)]
+pub enum DiffOutcome {
+    /// The files match.
+    Match,
+    /// The files differ, as described by the message.
+    Mismatch(String),
+    /// A file could not be read.
+    Error(DiffIoError),
+}
+
+impl From<DiffIoError> for DiffOutcome {
+    fn from(error: DiffIoError) -> Self {
+        DiffOutcome::Error(error)
+    }
+}
+
+/// A function that compares two files and returns the outcome instead of
+/// panicking. See [`panic_on_mismatch`] to use one as a [`Differ`].
+pub type OutcomeDiffer = Box<dyn Fn(&Path, &Path) -> DiffOutcome>;
+
+/// Adapt a function returning a [`DiffOutcome`] into a [`Differ`].
+///
+/// A mismatch or an error panics with its message, as the built-in differs'
+/// `_diff` forms do.
+pub fn panic_on_mismatch<F>(differ: F) -> Differ
+where
+    F: Fn(&Path, &Path) -> DiffOutcome + 'static,
+{
+    Box::new(move |old, new| raise(differ(old, new)))
+}
+
+fn raise(outcome: DiffOutcome) {
+    match outcome {
+        DiffOutcome::Match => {}
+        DiffOutcome::Mismatch(message) => panic!("{}", message),
+        DiffOutcome::Error(error) => panic!("{}", error),
+    }
+}
+
+/// Return a [`DiffOutcome::Mismatch`] with a formatted message from a
+/// comparison run by [`compare`].
+macro_rules! mismatch {
+    ($($arg:tt)*) => {
+        return Err(DiffOutcome::Mismatch(format!($($arg)*)))
+    };
+}
+
+/// Run a comparison that returns early with its mismatch or error, and
+/// otherwise matches.
+fn compare(f: impl FnOnce() -> Result<(), DiffOutcome>) -> DiffOutcome {
+    f().err().unwrap_or(DiffOutcome::Match)
+}
+
+/// Compare unicode text files like [`text_diff`], returning the outcome
+/// instead of panicking.
+///
+/// A mismatch's message holds the diff [`text_diff`] prints, under a
+/// `<path>: files differ` heading.
+pub fn text_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| text_eq(old, &read_text_file(old)?, &read_text_file(new)?))
+}
+
+/// Compare binary files like [`binary_diff`], returning the outcome instead
+/// of panicking.
+pub fn binary_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let old_len = check_io(fs::metadata(old), old)?.len();
+        let new_len = check_io(fs::metadata(new), new)?.len();
+        if old_len != new_len {
+            mismatch!(
+                "File sizes differ: Old file is {} bytes, new file is {} bytes",
+                old_len,
+                new_len
+            );
+        }
+
+        let old_bytes = BufReader::new(check_io(fs::File::open(old), old)?).bytes();
+        let new_bytes = BufReader::new(check_io(fs::File::open(new), new)?).bytes();
+        for (position, (old_byte, new_byte)) in old_bytes.zip(new_bytes).enumerate() {
+            if check_io(old_byte, old)? != check_io(new_byte, new)? {
+                mismatch!(
+                    "{}: Files differ at byte {}: old is {} bytes, new is {} bytes",
+                    old.display(),
+                    position + 1,
+                    old_len,
+                    new_len
+                );
+            }
+        }
+        Ok(())
+    })
+}
+
 /// Compare unicode text files. Print a colored diff and panic on failure.
+///
+/// A golden that isn't valid UTF-8 fails to read, rather than being compared
+/// as empty.
+///
+/// Setting `GOLDENFILE_ELIDE_UNCHANGED` to a number bounds how many unchanged
+/// lines text diffs show between changes, replacing the rest of longer runs
+/// with a `... (k identical lines) ...` marker.
 pub fn text_diff(old: &Path, new: &Path) {
-    similar_asserts::assert_eq!(
-        &fs::read_to_string(old).unwrap_or("".to_string()),
-        &fs::read_to_string(new).unwrap_or("".to_string()),
-        "{}",
-        old.display(),
-    );
+    raise(text_outcome(old, new));
+}
+
+/// Compare unicode text files, printing a side-by-side diff `width` columns
+/// wide on failure instead of a unified one.
+///
+/// Changed lines are colored unless `NO_COLOR` is set. If `width` is too narrow
+/// for two readable columns, a unified diff is printed instead. Setting
+/// `GOLDENFILE_DIFF_STYLE=side-by-side` renders every text diff this way.
+pub fn side_by_side_text_diff(width: usize) -> Differ {
+    panic_on_mismatch(side_by_side_text_outcome(width))
+}
+
+/// Compare unicode text files like [`side_by_side_text_diff`], returning the
+/// outcome instead of panicking.
+pub fn side_by_side_text_outcome(width: usize) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            match side_by_side_mismatch(old, &old_text, &new_text, width) {
+                Some(message) => Err(DiffOutcome::Mismatch(message)),
+                None => Ok(()),
+            }
+        })
+    })
+}
+
+/// Compare text files that may be UTF-8 or UTF-16, decoding each by its byte
+/// order mark. Print a colored diff of the decoded text and panic on failure.
+///
+/// Files without a byte order mark are read as UTF-8, so a UTF-8 golden
+/// matches UTF-16 output with the same text. Use
+/// [`Mint::new_goldenfile_bom_aware`](crate::Mint::new_goldenfile_bom_aware)
+/// to also keep the golden's encoding when updating.
+pub fn bom_aware_text_diff(old: &Path, new: &Path) {
+    raise(bom_aware_text_outcome(old, new));
+}
+
+/// Compare text files like [`bom_aware_text_diff`], returning the outcome
+/// instead of panicking.
+pub fn bom_aware_text_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let decode = |path: &Path| match bom::decode(&read_file(path)?) {
+            Ok((_, text)) => Ok(text),
+            Err(err) => mismatch!("{}: {}", path.display(), err),
+        };
+        text_eq(old, &decode(old)?, &decode(new)?)
+    })
+}
+
+/// Compare unicode text files like [`text_diff`], but when they only differ in
+/// line endings, panic naming the endings each file uses instead of printing
+/// a diff.
+///
+/// This catches goldenfiles accidentally committed with CRLF line endings, or
+/// with a mix of CRLF and LF.
+pub fn strict_line_ending_diff(old: &Path, new: &Path) {
+    raise(strict_line_ending_outcome(old, new));
+}
+
+/// Compare unicode text files like [`strict_line_ending_diff`], returning the
+/// outcome instead of panicking.
+pub fn strict_line_ending_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+        let normalize = |text: &str| text.replace("\r\n", "\n");
+        if old_text != new_text && normalize(&old_text) == normalize(&new_text) {
+            mismatch!(
+                "{}: Line endings differ: old uses {}, new uses {}",
+                old.display(),
+                describe_line_endings(&old_text),
+                describe_line_endings(&new_text)
+            );
+        }
+        text_eq(old, &old_text, &new_text)
+    })
+}
+
+fn describe_line_endings(text: &str) -> String {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, 0) => "no line endings".to_string(),
+        (_, 0) => "CRLF".to_string(),
+        (0, _) => "LF".to_string(),
+        _ => format!("mixed line endings ({} CRLF, {} LF)", crlf, lf),
+    }
+}
+
+/// Normalizations applied by [`text_diff_opts`]. The default applies none,
+/// matching [`text_diff`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextDiffOptions {
+    /// Ignore whitespace at the end of each line, including `\r`.
+    pub trim_trailing_ws: bool,
+    /// Expand tabs to spaces, with tab stops this many columns apart.
+    pub tab_width: Option<usize>,
+    /// Ignore whether the file ends with a newline, LF or CRLF. Only one
+    /// final line terminator is ignored, so an added or removed empty last
+    /// line is still a difference.
+    pub ignore_final_newline: bool,
+    /// Ignore lines that are empty or only whitespace.
+    pub ignore_blank_lines: bool,
+}
+
+/// Compare unicode text files after normalizing both as configured by
+/// `options`. Print a colored diff of the normalized text and panic on
+/// failure.
+///
+/// Tabs are expanded before trailing whitespace is trimmed, and a line is
+/// blank if it is after both.
+pub fn text_diff_opts(options: TextDiffOptions) -> Differ {
+    panic_on_mismatch(text_outcome_opts(options))
+}
+
+/// Compare unicode text files like [`text_diff_opts`], returning the outcome
+/// instead of panicking.
+pub fn text_outcome_opts(options: TextDiffOptions) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            text_eq(
+                old,
+                &normalize_text(&read_text_file(old)?, &options),
+                &normalize_text(&read_text_file(new)?, &options),
+            )
+        })
+    })
+}
+
+fn normalize_text(text: &str, options: &TextDiffOptions) -> String {
+    let (body, final_newline) = match text.strip_suffix('\n') {
+        Some(body) => (body, true),
+        None => (text, false),
+    };
+    let mut out = String::new();
+    for line in body.split('\n').filter(|_| !text.is_empty()) {
+        let mut line = match options.tab_width {
+            Some(width) => expand_tabs(line, width),
+            None => line.to_string(),
+        };
+        if options.trim_trailing_ws {
+            line.truncate(line.trim_end().len());
+        }
+        if options.ignore_blank_lines && line.trim().is_empty() {
+            continue;
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if (!final_newline || options.ignore_final_newline) && out.ends_with('\n') {
+        out.pop();
+        // A final CRLF is a terminator too, not a line ending in `\r`.
+        if final_newline && text.ends_with("\r\n") && out.ends_with('\r') {
+            out.pop();
+        }
+    }
+    out
+}
+
+fn expand_tabs(line: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = String::new();
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = width - column % width;
+            out.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            out.push(c);
+            column += 1;
+        }
+    }
+    out
+}
+
+/// Compare text files, tolerating a fraction of changed lines. Print a colored
+/// diff and panic if more than `max_frac` of lines changed.
+///
+/// Lines are diffed with the Myers algorithm. The fraction changed is the
+/// number of lines deleted from the old file plus the number inserted into the
+/// new one, divided by the total number of lines in both files, so it ranges
+/// from 0 (identical) to 1 (nothing in common). Modifying one line of a
+/// 100-line file counts as one deletion and one insertion out of 200 lines,
+/// a fraction of 0.01. Two empty files are identical.
+pub fn fuzzy_line_diff(max_frac: f64) -> Differ {
+    panic_on_mismatch(fuzzy_line_outcome(max_frac))
+}
+
+/// Compare text files like [`fuzzy_line_diff`], returning the outcome instead
+/// of panicking.
+pub fn fuzzy_line_outcome(max_frac: f64) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let diff = TextDiff::from_lines(&old_text, &new_text);
+            let (mut changed, mut total) = (0, 0);
+            for change in diff.iter_all_changes() {
+                total += match change.tag() {
+                    ChangeTag::Equal => 2,
+                    ChangeTag::Delete | ChangeTag::Insert => {
+                        changed += 1;
+                        1
+                    }
+                };
+            }
+            let frac = if total == 0 {
+                0.0
+            } else {
+                changed as f64 / total as f64
+            };
+            if frac > max_frac {
+                mismatch!(
+                    "{}: {:.4} of lines changed, more than the {} allowed\n{}",
+                    old.display(),
+                    frac,
+                    max_frac,
+                    similar_asserts::SimpleDiff::from_str(&old_text, &new_text, "old", "new")
+                );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare text files by their overall similarity. Print the ratio and a
+/// colored diff, and panic if the ratio is below `min_ratio`.
+///
+/// The ratio is computed like Python's `difflib.SequenceMatcher.ratio`, over
+/// characters rather than lines: twice the number of characters the files have
+/// in common, as found by a Myers diff, divided by the total number of
+/// characters in both. It ranges from 0 (nothing in common) to 1 (identical).
+/// Changing 1 character of a 100-character file gives a ratio of 0.99, however
+/// many lines the file has. Two empty files are identical.
+pub fn similarity_ratio_diff(min_ratio: f64) -> Differ {
+    panic_on_mismatch(similarity_ratio_outcome(min_ratio))
+}
+
+/// Compare text files like [`similarity_ratio_diff`], returning the outcome
+/// instead of panicking.
+pub fn similarity_ratio_outcome(min_ratio: f64) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let ratio = f64::from(TextDiff::from_chars(&old_text, &new_text).ratio());
+            if ratio < min_ratio {
+                mismatch!(
+                    "{}: similarity ratio {:.4} is below the {} required\n{}",
+                    old.display(),
+                    ratio,
+                    min_ratio,
+                    similar_asserts::SimpleDiff::from_str(&old_text, &new_text, "old", "new")
+                );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Comment syntaxes understood by [`code_comment_stripping_diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommentLanguage {
+    /// `// line` and `/* block */` comments, as in Rust, C, C++, Java, Go and
+    /// JavaScript. Block comments do not nest.
+    CStyle,
+    /// `# line` comments, as in Python, shell, TOML and YAML.
+    Hash,
+}
+
+/// Compare source files after stripping comments from both. Print a colored
+/// diff of the stripped code and panic on failure.
+///
+/// Comment markers inside double-quoted string literals, and single-quoted
+/// ones for [`CommentLanguage::Hash`], are left alone. Lines that only
+/// contained a comment are dropped entirely, so adding or removing a comment
+/// line does not count as a change.
+pub fn code_comment_stripping_diff(language: CommentLanguage) -> Differ {
+    panic_on_mismatch(code_comment_stripping_outcome(language))
+}
+
+/// Compare source files like [`code_comment_stripping_diff`], returning the
+/// outcome instead of panicking.
+pub fn code_comment_stripping_outcome(language: CommentLanguage) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            text_eq(
+                old,
+                &strip_comments(&read_text_file(old)?, language),
+                &strip_comments(&read_text_file(new)?, language),
+            )
+        })
+    })
+}
+
+/// Compare text files after replacing the contents of every double-quoted
+/// string in both with `...`. Print a colored diff of the masked text and
+/// panic on failure.
+///
+/// A backslash escapes the character after it, so `\"` doesn't end a string.
+/// A string left open at the end of a line ends there.
+pub fn ignore_quoted_strings_diff(old: &Path, new: &Path) {
+    raise(ignore_quoted_strings_outcome(old, new));
+}
+
+/// Compare text files like [`ignore_quoted_strings_diff`], returning the outcome instead of
+/// panicking.
+pub fn ignore_quoted_strings_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        text_eq(
+            old,
+            &mask_quoted_strings(&read_text_file(old)?),
+            &mask_quoted_strings(&read_text_file(new)?),
+        )
+    })
+}
+
+/// Compare text files after replacing memory addresses in both with
+/// `0x<ADDR>`. Print a colored diff of the masked text and panic on failure.
+///
+/// An address is `0x` followed by 8 to 16 lowercase hex digits, as printed by
+/// `{:p}` and by `Debug` for pointers, not touching other letters, digits or
+/// `_`. Shorter values like `0xff` and uppercase hex are left alone, since they
+/// are more likely to be data.
+pub fn address_normalizing_diff(old: &Path, new: &Path) {
+    raise(address_normalizing_outcome(old, new));
+}
+
+/// Compare text files like [`address_normalizing_diff`], returning the outcome instead of
+/// panicking.
+pub fn address_normalizing_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        text_eq(
+            old,
+            &mask_addresses(&read_text_file(old)?),
+            &mask_addresses(&read_text_file(new)?),
+        )
+    })
+}
+
+fn mask_addresses(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut out = String::new();
+    let mut copied = 0;
+    let mut pos = 0;
+    while let Some(found) = text[pos..].find("0x") {
+        let start = pos + found;
+        let digits = bytes[start + 2..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(b))
+            .count();
+        let end = start + 2 + digits;
+        if (8..=16).contains(&digits)
+            && (start == 0 || !is_word(bytes[start - 1]))
+            && bytes.get(end).is_none_or(|&b| !is_word(b))
+        {
+            out.push_str(&text[copied..start]);
+            out.push_str("0x<ADDR>");
+            copied = end;
+        }
+        pos = end;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Compare text files after converting Windows path separators in path-like
+/// tokens to `/` in both. Print a colored diff of the normalized text and
+/// panic on failure.
+///
+/// Tokens are separated by whitespace, quotes and punctuation like `,` and
+/// `(`. To avoid mangling escapes like `\n` and `\d`, a token only counts as a
+/// path if it starts with a drive like `C:\` or a UNC `\\`, or if backslashes
+/// join two or more non-empty components and it starts with `.` or `..` or
+/// ends in a file name with an extension. A trailing `:line:column:` is
+/// allowed, and consistently doubled backslashes, as in escaped JSON strings,
+/// count as one separator.
+pub fn path_sep_normalizing_diff(old: &Path, new: &Path) {
+    raise(path_sep_normalizing_outcome(old, new));
+}
+
+/// Compare text files like [`path_sep_normalizing_diff`], returning the outcome instead of
+/// panicking.
+pub fn path_sep_normalizing_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        text_eq(
+            old,
+            &normalize_path_separators(&read_text_file(old)?),
+            &normalize_path_separators(&read_text_file(new)?),
+        )
+    })
+}
+
+fn normalize_path_separators(text: &str) -> String {
+    let is_boundary = |c: char| c.is_whitespace() || "\"'`,;()[]{}<>=|".contains(c);
+    let mut out = String::with_capacity(text.len());
+    let mut token = String::new();
+    for c in text.chars() {
+        if is_boundary(c) {
+            out.push_str(&normalize_path_token(&token).unwrap_or_else(|| token.clone()));
+            token.clear();
+            out.push(c);
+        } else {
+            token.push(c);
+        }
+    }
+    out.push_str(&normalize_path_token(&token).unwrap_or(token));
+    out
+}
+
+/// `token` with `/` separators, or `None` if it doesn't look like a Windows
+/// path.
+fn normalize_path_token(token: &str) -> Option<String> {
+    if !token.contains('\\') {
+        return None;
+    }
+    let mut end = token.strip_suffix(':').map_or(token.len(), str::len);
+    while let Some(colon) = token[..end].rfind(':') {
+        let digits = &token[colon + 1..end];
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            break;
+        }
+        end = colon;
+    }
+    let (path, location) = token.split_at(end);
+
+    let bytes = path.as_bytes();
+    let (prefix, rest, anchored) = if let Some(rest) = path.strip_prefix("\\\\") {
+        ("//".to_string(), rest, true)
+    } else if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && &path[1..3] == ":\\" {
+        let rest = &path[3..];
+        (
+            format!("{}/", &path[..2]),
+            rest.strip_prefix('\\').unwrap_or(rest),
+            true,
+        )
+    } else {
+        (String::new(), path, false)
+    };
+    if rest.contains("\\\\\\") {
+        return None;
+    }
+    let separator = if rest.contains("\\\\") { "\\\\" } else { "\\" };
+    let parts: Vec<&str> = rest.split(separator).collect();
+    let is_component = |part: &&str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_alphanumeric() || "._-~$@+/".contains(c))
+    };
+    let (last, init) = parts.split_last().unwrap();
+    if !init.iter().all(is_component) || !(is_component(last) || anchored && last.is_empty()) {
+        return None;
+    }
+    if !anchored && (parts.len() < 2 || !(matches!(parts[0], "." | "..") || last.contains('.'))) {
+        return None;
+    }
+    Some(format!("{}{}{}", prefix, parts.join("/"), location))
+}
+
+/// Compare text files after writing numbers in both without thousands
+/// separators and with `.` as the decimal mark, so `1,234.5`, `1.234,5` and
+/// `1 234,5` are all `1234.5`. Print a colored diff of the normalized text and
+/// panic on failure.
+///
+/// Thousands separators are `,`, `.`, `'`, `’` and the no-break, narrow
+/// no-break and thin spaces, but not plain spaces, which more often separate
+/// numbers. Groups after the first must have three digits. When a number has
+/// two kinds of separator, the last is the decimal mark. With only one kind, a
+/// repeated separator groups thousands, and a lone `,` groups thousands if
+/// three digits follow and is a decimal comma otherwise. A lone `.` is always a
+/// decimal point, so `1.234` is read as in English, not German. Anything else,
+/// like versions and addresses such as `1.2.3`, is left alone.
+pub fn locale_numeric_diff(old: &Path, new: &Path) {
+    raise(locale_numeric_outcome(old, new));
+}
+
+/// Compare text files like [`locale_numeric_diff`], returning the outcome instead of
+/// panicking.
+pub fn locale_numeric_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        text_eq(
+            old,
+            &normalize_locale_numbers(&read_text_file(old)?),
+            &normalize_locale_numbers(&read_text_file(new)?),
+        )
+    })
+}
+
+const GROUP_SEPARATORS: [char; 7] = [',', '.', '\'', '\u{2019}', '\u{a0}', '\u{202f}', '\u{2009}'];
+
+fn normalize_locale_numbers(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() || (i > 0 && (is_word(chars[i - 1]) || chars[i - 1] == '.')) {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        let mut groups = vec![String::new()];
+        let mut separators = vec![];
+        let mut j = i;
+        while j < chars.len() {
+            let c = chars[j];
+            if c.is_ascii_digit() {
+                groups.last_mut().unwrap().push(c);
+            } else if GROUP_SEPARATORS.contains(&c)
+                && chars.get(j + 1).is_some_and(char::is_ascii_digit)
+            {
+                separators.push(c);
+                groups.push(String::new());
+            } else {
+                break;
+            }
+            j += 1;
+        }
+        match canonical_number(&groups, &separators) {
+            Some(number) => out.push_str(&number),
+            None => out.extend(&chars[i..j]),
+        }
+        i = j;
+    }
+    out
+}
+
+/// The canonical form of a number made of digit `groups` joined by
+/// `separators`, or `None` to leave it as written.
+fn canonical_number(groups: &[String], separators: &[char]) -> Option<String> {
+    let is_grouped = |groups: &[String]| {
+        (1..=3).contains(&groups[0].len()) && groups[1..].iter().all(|g| g.len() == 3)
+    };
+    let (&last, init) = separators.split_last()?;
+    if init.iter().all(|&c| c == last) {
+        if separators.len() == 1 && last == '.' {
+            return None;
+        }
+        if is_grouped(groups) {
+            return Some(groups.concat());
+        }
+        if separators.len() == 1 && last == ',' {
+            return Some(format!("{}.{}", groups[0], groups[1]));
+        }
+        return None;
+    }
+    let group = init[0];
+    let decimal_mark = matches!(last, ',' | '.');
+    let (fraction, whole) = groups.split_last().unwrap();
+    if decimal_mark && group != last && init.iter().all(|&c| c == group) && is_grouped(whole) {
+        Some(format!("{}.{}", whole.concat(), fraction))
+    } else {
+        None
+    }
+}
+
+fn mask_quoted_strings(text: &str) -> String {
+    let mut out = String::new();
+    let mut in_string = false;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, in_string) {
+            ('"', false) => {
+                out.push_str("\"...");
+                in_string = true;
+            }
+            ('"', true) => {
+                out.push('"');
+                in_string = false;
+            }
+            ('\n', _) => {
+                out.push('\n');
+                in_string = false;
+            }
+            ('\\', true) => {
+                // Stop at an escaped line ending so the line still ends.
+                if chars.next() == Some('\n') {
+                    out.push('\n');
+                    in_string = false;
+                }
+            }
+            (_, true) => {}
+            (c, false) => out.push(c),
+        }
+    }
+    out
+}
+
+/// Compare Markdown files by their block structure, ignoring insignificant
+/// whitespace and line wrapping. Panic naming the first differing block.
+///
+/// Headings, paragraphs, list items, block quotes, code blocks and thematic
+/// breaks are compared in order. Inline text has runs of whitespace collapsed
+/// and `_x_` emphasis matches `*x*`, while code block contents must match
+/// exactly.
+///
+/// Requires the `markdown` feature.
+#[cfg(feature = "markdown")]
+pub fn markdown_diff(old: &Path, new: &Path) {
+    raise(markdown_outcome(old, new));
+}
+
+/// Compare Markdown files like [`markdown_diff`], returning the outcome
+/// instead of panicking.
+///
+/// Requires the `markdown` feature.
+#[cfg(feature = "markdown")]
+pub fn markdown_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let old_blocks = markdown::parse(&read_text_file(old)?);
+        let new_blocks = markdown::parse(&read_text_file(new)?);
+        let len = old_blocks.len().max(new_blocks.len());
+        if let Some(i) = (0..len).find(|&i| old_blocks.get(i) != new_blocks.get(i)) {
+            let describe = |block: Option<&markdown::Block>| {
+                block.map_or("(none)".to_string(), |block| format!("{:?}", block))
+            };
+            mismatch!(
+                "{}: Markdown differs at block {}:\n  old: {}\n  new: {}",
+                old.display(),
+                i + 1,
+                describe(old_blocks.get(i)),
+                describe(new_blocks.get(i)),
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Compare text files as unordered collections of blocks. Panic listing the
+/// blocks found in only one file.
+///
+/// Both files are split on `separator` (use `"\n\n"` for paragraphs separated
+/// by blank lines), leading and trailing newlines are trimmed from each block,
+/// and empty blocks are dropped. The files match if they contain the same
+/// blocks the same number of times, in any order.
+pub fn blocks_unordered_diff(separator: &str) -> Differ {
+    panic_on_mismatch(blocks_unordered_outcome(separator))
+}
+
+/// Compare text files like [`blocks_unordered_diff`], returning the outcome
+/// instead of panicking.
+pub fn blocks_unordered_outcome(separator: &str) -> OutcomeDiffer {
+    let separator = separator.to_string();
+    Box::new(move |old, new| {
+        compare(|| {
+            let old_text = read_text_file(old)?;
+            let new_text = read_text_file(new)?;
+            let mut counts: HashMap<&str, isize> = HashMap::new();
+            for block in split_blocks(&old_text, &separator) {
+                *counts.entry(block).or_default() += 1;
+            }
+            for block in split_blocks(&new_text, &separator) {
+                *counts.entry(block).or_default() -= 1;
+            }
+
+            let mut only_old = vec![];
+            let mut only_new = vec![];
+            for block in
+                split_blocks(&old_text, &separator).chain(split_blocks(&new_text, &separator))
+            {
+                let count = counts.get_mut(block).unwrap();
+                if *count > 0 {
+                    only_old.push(block);
+                    *count -= 1;
+                } else if *count < 0 {
+                    only_new.push(block);
+                    *count += 1;
+                }
+            }
+            if !only_old.is_empty() || !only_new.is_empty() {
+                let mut message = format!("{}: Blocks differ", old.display());
+                for (label, blocks) in [("old", only_old), ("new", only_new)] {
+                    for block in blocks {
+                        message.push_str(&format!("\n--- only in {} ---\n{}", label, block));
+                    }
+                }
+                mismatch!("{}", message);
+            }
+            Ok(())
+        })
+    })
+}
+
+fn split_blocks<'a>(text: &'a str, separator: &'a str) -> impl Iterator<Item = &'a str> {
+    text.split(separator)
+        .map(|block| block.trim_matches(|c| c == '\n' || c == '\r'))
+        .filter(|block| !block.is_empty())
+}
+
+/// Compare text files containing timestamps, allowing each timestamp to differ
+/// from its golden counterpart by up to `tolerance`. Panic with a diff if the
+/// surrounding text differs, or naming the first pair of timestamps that are
+/// too far apart.
+///
+/// `format` is strftime-like and supports `%Y` (4-digit year), `%m`, `%d`,
+/// `%H`, `%M`, `%S` (2 digits each), `%f` (1 to 9 fractional-second digits)
+/// and `%%`. For example, `"%Y-%m-%dT%H:%M:%SZ"` matches RFC 3339 UTC
+/// timestamps. Timestamps are shown as `<TIMESTAMP>` in text diffs.
+pub fn timestamp_tolerant_diff(format: &str, tolerance: Duration) -> Differ {
+    panic_on_mismatch(timestamp_tolerant_outcome(format, tolerance))
+}
+
+/// Compare text files like [`timestamp_tolerant_diff`], returning the outcome
+/// instead of panicking.
+pub fn timestamp_tolerant_outcome(format: &str, tolerance: Duration) -> OutcomeDiffer {
+    let format = timestamp::Format::parse(format)
+        .unwrap_or_else(|err| panic!("Invalid timestamp format {:?}: {}", format, err));
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let (old_skeleton, old_times) = timestamp::extract(&format, &old_text, "<TIMESTAMP>");
+            let (new_skeleton, new_times) = timestamp::extract(&format, &new_text, "<TIMESTAMP>");
+            text_eq(old, &old_skeleton, &new_skeleton)?;
+
+            for (i, ((old_time, old_str), (new_time, new_str))) in
+                old_times.into_iter().zip(new_times).enumerate()
+            {
+                let delta = (new_time - old_time).abs();
+                if delta > tolerance.as_secs_f64() {
+                    mismatch!(
+                    "{}: Timestamp {} differs by {}s, more than the {}s tolerance: old {}, new {}",
+                    old.display(),
+                    i + 1,
+                    delta,
+                    tolerance.as_secs_f64(),
+                    old_str,
+                    new_str
+                );
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare log files after stripping a leading timestamp from each line,
+/// keeping the level and message. Print a colored diff of the stripped lines
+/// and panic on failure.
+///
+/// `format` is as for [`timestamp_tolerant_diff`], e.g. `"%Y-%m-%dT%H:%M:%SZ"`
+/// for lines like `2024-01-02T03:04:05Z INFO message`. Whitespace after the
+/// timestamp is stripped with it. Lines that don't start with a timestamp,
+/// like continuation lines of a message, are compared as they are.
+pub fn log_line_diff(format: &str) -> Differ {
+    panic_on_mismatch(log_line_outcome(format))
+}
+
+/// Compare log files like [`log_line_diff`], returning the outcome instead of
+/// panicking.
+pub fn log_line_outcome(format: &str) -> OutcomeDiffer {
+    let format = timestamp::Format::parse(format)
+        .unwrap_or_else(|err| panic!("Invalid timestamp format {:?}: {}", format, err));
+    Box::new(move |old, new| {
+        compare(|| {
+            let strip = |path: &Path| {
+                let text = read_text_file(path)?;
+                let lines = text
+                    .split_inclusive('\n')
+                    .map(|line| match format.match_at(line) {
+                        Some((_, len)) => line[len..].trim_start_matches([' ', '\t']),
+                        None => line,
+                    });
+                Ok::<_, DiffIoError>(lines.collect::<String>())
+            };
+            text_eq(old, &strip(old)?, &strip(new)?)
+        })
+    })
+}
+
+/// Compare text files, allowing numbers to differ by up to `epsilon`. Panic
+/// with a diff if the surrounding text differs, or naming the first pair of
+/// numbers that are too far apart.
+///
+/// A number is an optional `-`, digits with an optional fraction, and an
+/// optional exponent, not directly after a letter, digit or `_`. Numbers are
+/// shown as `<NUMBER>` in text diffs.
+pub fn numeric_tolerant_diff(epsilon: f64) -> Differ {
+    panic_on_mismatch(numeric_tolerant_outcome(epsilon))
+}
+
+/// Compare text files like [`numeric_tolerant_diff`], returning the outcome
+/// instead of panicking.
+pub fn numeric_tolerant_outcome(epsilon: f64) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let (old_skeleton, old_numbers) = extract_numbers(&old_text);
+            let (new_skeleton, new_numbers) = extract_numbers(&new_text);
+            text_eq(old, &old_skeleton, &new_skeleton)?;
+
+            for (i, (old_number, new_number)) in
+                old_numbers.into_iter().zip(new_numbers).enumerate()
+            {
+                let (a, b): (f64, f64) = (old_number.parse().unwrap(), new_number.parse().unwrap());
+                if (a - b).abs() > epsilon {
+                    mismatch!(
+                        "{}: Number {} differs by more than {}: old {}, new {}",
+                        old.display(),
+                        i + 1,
+                        epsilon,
+                        old_number,
+                        new_number
+                    );
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare text files holding grids of floating-point numbers, allowing each
+/// element to differ by up to `epsilon`. Panic if the grids' shapes differ, or
+/// naming the row and column, numbered from 1, of the first pair of elements
+/// that are too far apart.
+///
+/// Each non-blank line is a row of whitespace-separated numbers, and rows may
+/// have different lengths. `NaN` matches `NaN` and infinities match
+/// themselves. If either file isn't such a grid, they are compared like
+/// [`text_diff`] instead.
+pub fn matrix_diff(epsilon: f64) -> Differ {
+    panic_on_mismatch(matrix_outcome(epsilon))
+}
+
+/// Compare text files like [`matrix_diff`], returning the outcome instead of
+/// panicking.
+pub fn matrix_outcome(epsilon: f64) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let (Some(old_rows), Some(new_rows)) =
+                (parse_matrix(&old_text), parse_matrix(&new_text))
+            else {
+                return text_eq(old, &old_text, &new_text);
+            };
+            let shape = |rows: &[Vec<f64>]| rows.iter().map(Vec::len).collect::<Vec<_>>();
+            if shape(&old_rows) != shape(&new_rows) {
+                let describe = |rows: &[Vec<f64>]| match rows.first() {
+                    Some(row) if rows.iter().all(|r| r.len() == row.len()) => {
+                        format!("{}x{}", rows.len(), row.len())
+                    }
+                    _ => format!("{} ragged rows", rows.len()),
+                };
+                mismatch!(
+                    "{}: Matrix shapes differ: old is {}, new is {}",
+                    old.display(),
+                    describe(&old_rows),
+                    describe(&new_rows)
+                );
+            }
+            for (row, (old_row, new_row)) in old_rows.iter().zip(&new_rows).enumerate() {
+                for (col, (&a, &b)) in old_row.iter().zip(new_row).enumerate() {
+                    let close = a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= epsilon;
+                    if !close {
+                        mismatch!(
+                            "{}: Element ({}, {}) differs by more than {}: old {}, new {}",
+                            old.display(),
+                            row + 1,
+                            col + 1,
+                            epsilon,
+                            a,
+                            b
+                        );
+                    }
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare CSV files as sets of rows keyed by the `key_column` named in their
+/// header, ignoring row order. Panic listing the removed, added and changed
+/// rows by key, with the columns that changed.
+///
+/// The headers must match, and keys must be unique within each file. Files
+/// that aren't valid CSV, or have rows with a different number of fields
+/// than the header, are compared as text. At most 10 rows of each kind are
+/// listed.
+pub fn csv_keyed_diff(key_column: &str) -> Differ {
+    panic_on_mismatch(csv_keyed_outcome(key_column))
+}
+
+/// Compare CSV files like [`csv_keyed_diff`], returning the outcome instead of
+/// panicking.
+pub fn csv_keyed_outcome(key_column: &str) -> OutcomeDiffer {
+    let key_column = key_column.to_string();
+    Box::new(move |old, new| {
+        const MAX_LISTED: usize = 10;
+
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let (Ok(old_records), Ok(new_records)) = (csv::parse(&old_text), csv::parse(&new_text))
+            else {
+                return text_eq(old, &old_text, &new_text);
+            };
+            let header = |records: &[csv::Record]| {
+                records
+                    .first()
+                    .map_or(vec![], |record| record.fields.clone())
+            };
+            let (old_header, new_header) = (header(&old_records), header(&new_records));
+            if old_header != new_header {
+                mismatch!(
+                    "{}: CSV headers differ: old {}, new {}",
+                    old.display(),
+                    old_header.join(","),
+                    new_header.join(",")
+                );
+            }
+            if old_records.is_empty() {
+                return Ok(());
+            }
+            let Some(key) = old_header.iter().position(|column| *column == key_column) else {
+                mismatch!(
+                    "{}: no key column {:?} in the header",
+                    old.display(),
+                    key_column
+                );
+            };
+            let (old_rows, new_rows) = (
+                index_csv_rows(old, &old_records, key)?,
+                index_csv_rows(new, &new_records, key)?,
+            );
+
+            let removed: Vec<String> = old_rows
+                .iter()
+                .filter(|(row_key, _)| !new_rows.contains_key(*row_key))
+                .map(|(row_key, record)| {
+                    format!("removed {}: {}", row_key, record.fields.join(","))
+                })
+                .collect();
+            let added: Vec<String> = new_rows
+                .iter()
+                .filter(|(row_key, _)| !old_rows.contains_key(*row_key))
+                .map(|(row_key, record)| format!("added {}: {}", row_key, record.fields.join(",")))
+                .collect();
+            let changed: Vec<String> = old_rows
+                .iter()
+                .filter_map(|(row_key, old_record)| {
+                    let new_record = new_rows.get(row_key)?;
+                    let columns: Vec<String> = old_header
+                        .iter()
+                        .zip(old_record.fields.iter().zip(&new_record.fields))
+                        .filter(|(_, (a, b))| a != b)
+                        .map(|(column, (a, b))| format!("{}: {} -> {}", column, a, b))
+                        .collect();
+                    (!columns.is_empty())
+                        .then(|| format!("changed {}: {}", row_key, columns.join("; ")))
+                })
+                .collect();
+            if !removed.is_empty() || !added.is_empty() || !changed.is_empty() {
+                let mut message = format!("{}: CSV rows differ", old.display());
+                for lines in [removed, added, changed] {
+                    for line in lines.iter().take(MAX_LISTED) {
+                        message.push_str(&format!("\n  {}", line));
+                    }
+                    if lines.len() > MAX_LISTED {
+                        message.push_str(&format!("\n  ... and {} more", lines.len() - MAX_LISTED));
+                    }
+                }
+                mismatch!("{}", message);
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Index the rows after the header by the field in column `key`, failing if a
+/// key is repeated.
+fn index_csv_rows<'a>(
+    path: &Path,
+    records: &'a [csv::Record],
+    key: usize,
+) -> Result<BTreeMap<&'a str, &'a csv::Record>, DiffOutcome> {
+    let mut rows = BTreeMap::new();
+    for record in &records[1..] {
+        let row_key = record.fields[key].as_str();
+        if rows.insert(row_key, record).is_some() {
+            mismatch!(
+                "{}: duplicate key {:?} on line {}",
+                path.display(),
+                row_key,
+                record.line
+            );
+        }
+    }
+    Ok(rows)
+}
+
+/// Parse the non-blank lines of `text` as rows of numbers, or return `None`
+/// if any token isn't a number.
+fn parse_matrix(text: &str) -> Option<Vec<Vec<f64>>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.split_whitespace().map(|t| t.parse().ok()).collect())
+        .collect()
+}
+
+/// Replace every number in `text` with `<NUMBER>`, returning the rewritten
+/// text and the numbers in order.
+fn extract_numbers(text: &str) -> (String, Vec<&str>) {
+    let bytes = text.as_bytes();
+    let digits = |mut pos: usize| {
+        while bytes.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        pos
+    };
+    let mut out = String::new();
+    let mut numbers = vec![];
+    let mut pos = 0;
+    while pos < text.len() {
+        let after_word =
+            pos > 0 && (bytes[pos - 1].is_ascii_alphanumeric() || bytes[pos - 1] == b'_');
+        let start = pos;
+        let mut end = if bytes[pos] == b'-' { pos + 1 } else { pos };
+        let int_end = digits(end);
+        if after_word || int_end == end {
+            let c = text[pos..].chars().next().unwrap();
+            out.push(c);
+            pos += c.len_utf8();
+            continue;
+        }
+        end = int_end;
+        if bytes.get(end) == Some(&b'.') && digits(end + 1) > end + 1 {
+            end = digits(end + 1);
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+            let exponent_end = digits(end + 1 + sign);
+            if exponent_end > end + 1 + sign {
+                end = exponent_end;
+            }
+        }
+        out.push_str("<NUMBER>");
+        numbers.push(&text[start..end]);
+        pos = end;
+    }
+    (out, numbers)
+}
+
+/// Compare text files after replacing volatile IDs with numbered placeholders,
+/// so only the pattern of which IDs repeat must match. Print a colored diff
+/// and panic on failure.
+///
+/// Words are maximal runs of alphanumeric characters, `_` and `-`, and each
+/// word for which `is_id` returns true is an ID. Distinct IDs in each file are
+/// replaced by `<ID:1>`, `<ID:2>` and so on in order of first appearance, so
+/// two things that shared an ID must still share one. For example,
+/// `|word| word.len() == 36 && word.matches('-').count() == 4` matches UUIDs.
+pub fn sequential_id_diff<F: Fn(&str) -> bool + 'static>(is_id: F) -> Differ {
+    panic_on_mismatch(sequential_id_outcome(is_id))
+}
+
+/// Compare text files like [`sequential_id_diff`], returning the outcome
+/// instead of panicking.
+pub fn sequential_id_outcome<F: Fn(&str) -> bool + 'static>(is_id: F) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            text_eq(
+                old,
+                &number_ids(&read_text_file(old)?, &is_id),
+                &number_ids(&read_text_file(new)?, &is_id),
+            )
+        })
+    })
+}
+
+fn number_ids(text: &str, is_id: &dyn Fn(&str) -> bool) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut out = String::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+        let word = &rest[..end];
+        if word.is_empty() {
+            let c = rest.chars().next().unwrap();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if is_id(word) {
+            let next = ids.len() + 1;
+            out.push_str(&format!("<ID:{}>", ids.entry(word).or_insert(next)));
+        } else {
+            out.push_str(word);
+        }
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// Remove the last `n` lines of both files before comparing them with
+/// `inner`, ignoring a footer of known length like a timing summary.
+///
+/// A file with `n` or fewer lines becomes empty. `inner` sees the stripped
+/// contents, so its messages don't mention the footer. Files that aren't valid
+/// UTF-8 are passed to `inner` unchanged.
+pub fn strip_trailing_lines_diff(n: usize, inner: Differ) -> Differ {
+    transformed_diff(Arc::new(move |text| strip_trailing_lines(text, n)), inner)
+}
+
+/// Remove the last `n` lines of both files before comparing them with `inner`,
+/// like [`strip_trailing_lines_diff`], returning the outcome instead of
+/// panicking.
+pub fn strip_trailing_lines_outcome(n: usize, inner: OutcomeDiffer) -> OutcomeDiffer {
+    transformed_outcome(Arc::new(move |text| strip_trailing_lines(text, n)), inner)
+}
+
+fn strip_trailing_lines(text: &str, n: usize) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    lines[..lines.len().saturating_sub(n)].concat()
+}
+
+/// Replace every occurrence of each key of `map` in both files with its value
+/// before comparing them with `inner`, scrubbing known machine-specific
+/// strings like hostnames and build paths.
+///
+/// Text is scanned once from the start, replacing the longest key found at
+/// each position, so a key that contains another wins and placeholders are
+/// never replaced again. Empty keys are ignored. Files that aren't valid UTF-8
+/// are passed to `inner` unchanged.
+pub fn scrubbed_with_map_diff(map: HashMap<String, String>, inner: Differ) -> Differ {
+    transformed_diff(scrub_with_map(map), inner)
+}
+
+/// Scrub both files with `map` before comparing them with `inner`, like
+/// [`scrubbed_with_map_diff`], returning the outcome instead of panicking.
+pub fn scrubbed_with_map_outcome(
+    map: HashMap<String, String>,
+    inner: OutcomeDiffer,
+) -> OutcomeDiffer {
+    transformed_outcome(scrub_with_map(map), inner)
+}
+
+fn scrub_with_map(map: HashMap<String, String>) -> TextTransform {
+    let mut pairs: Vec<(String, String)> = map.into_iter().filter(|(k, _)| !k.is_empty()).collect();
+    pairs.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    Arc::new(move |text| scrub_with_pairs(text, &pairs))
+}
+
+fn scrub_with_pairs(text: &str, pairs: &[(String, String)]) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match pairs.iter().find(|(key, _)| rest.starts_with(key.as_str())) {
+            Some((key, value)) => {
+                out.push_str(value);
+                rest = &rest[key.len()..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+/// Apply `f` to each line of both files before comparing them with `inner`.
+/// A line is replaced with what `f` returns, or dropped if it returns `None`.
+///
+/// `f` sees each line without its line ending, which is kept after the
+/// replacement. `inner` sees the mapped contents, so its diff shows the
+/// rewritten lines. Files that aren't valid UTF-8 are passed to `inner`
+/// unchanged.
+pub fn line_map_diff<F>(f: F, inner: Differ) -> Differ
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    transformed_diff(Arc::new(move |text| map_lines(text, &f)), inner)
+}
+
+/// Apply `f` to each line of both files before comparing them with `inner`,
+/// like [`line_map_diff`], returning the outcome instead of panicking.
+pub fn line_map_outcome<F>(f: F, inner: OutcomeDiffer) -> OutcomeDiffer
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    transformed_outcome(Arc::new(move |text| map_lines(text, &f)), inner)
+}
+
+fn map_lines(text: &str, f: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        if let Some(mapped) = f(body) {
+            out.push_str(&mapped);
+            out.push_str(&line[body.len()..]);
+        }
+    }
+    out
+}
+
+/// Replace canonical UUIDs in both files with a placeholder before comparing
+/// them with `inner`.
+///
+/// A UUID is 32 hex digits in groups of 8, 4, 4, 4 and 12 separated by `-`,
+/// in either case, not touching other letters or digits. If `numbered`, each
+/// distinct UUID in a file becomes `<UUID:1>`, `<UUID:2>` and so on in order of
+/// first appearance, so two places that shared a UUID must still share one.
+/// Otherwise every UUID becomes `<UUID>`. Files that aren't valid UTF-8 are
+/// passed to `inner` unchanged.
+pub fn uuid_scrub_diff(inner: Differ, numbered: bool) -> Differ {
+    transformed_diff(Arc::new(move |text| scrub_uuids(text, numbered)), inner)
+}
+
+/// Replace UUIDs in both files before comparing them with `inner`, like
+/// [`uuid_scrub_diff`], returning the outcome instead of panicking.
+pub fn uuid_scrub_outcome(inner: OutcomeDiffer, numbered: bool) -> OutcomeDiffer {
+    transformed_outcome(Arc::new(move |text| scrub_uuids(text, numbered)), inner)
+}
+
+fn scrub_uuids(text: &str, numbered: bool) -> String {
+    const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+    let bytes = text.as_bytes();
+    let is_uuid_at = |start: usize| {
+        let mut pos = start;
+        for (i, len) in GROUPS.iter().enumerate() {
+            if i > 0 {
+                if bytes.get(pos) != Some(&b'-') {
+                    return false;
+                }
+                pos += 1;
+            }
+            let group = match bytes.get(pos..pos + len) {
+                Some(group) => group,
+                None => return false,
+            };
+            if !group.iter().all(u8::is_ascii_hexdigit) {
+                return false;
+            }
+            pos += len;
+        }
+        !bytes.get(pos).is_some_and(u8::is_ascii_alphanumeric)
+    };
+
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut out = String::new();
+    let mut pos = 0;
+    while pos < text.len() {
+        let at_boundary = pos == 0 || !bytes[pos - 1].is_ascii_alphanumeric();
+        if at_boundary && is_uuid_at(pos) {
+            let uuid = text[pos..pos + 36].to_ascii_lowercase();
+            if numbered {
+                let next = ids.len() + 1;
+                out.push_str(&format!("<UUID:{}>", ids.entry(uuid).or_insert(next)));
+            } else {
+                out.push_str("<UUID>");
+            }
+            pos += 36;
+        } else {
+            let c = text[pos..].chars().next().unwrap();
+            out.push(c);
+            pos += c.len_utf8();
+        }
+    }
+    out
+}
+
+/// Compare JSON files semantically. Panic naming the JSON path of the first
+/// difference.
+///
+/// Object key order and whitespace are ignored. Numbers are compared by value:
+/// integers exactly, and anything with a fraction or exponent as `f64`, so `1`,
+/// `1.0` and `1e0` are equal.
+pub fn json_diff(old: &Path, new: &Path) {
+    raise(json_outcome(old, new));
+}
+
+/// Compare JSON files like [`json_diff`], returning the outcome instead of
+/// panicking.
+pub fn json_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| json_eq(old, &read_json(old)?, &read_json(new)?))
+}
+
+/// Compare JSON files like [`json_diff`], ignoring object entries whose key is
+/// in `keys` wherever they appear.
+///
+/// This suits volatile fields like `id` or `created_at` that appear throughout
+/// a document.
+pub fn json_diff_ignoring_keys(keys: HashSet<String>) -> Differ {
+    panic_on_mismatch(json_outcome_ignoring_keys(keys))
+}
+
+/// Compare JSON files like [`json_diff_ignoring_keys`], returning the outcome
+/// instead of panicking.
+pub fn json_outcome_ignoring_keys(keys: HashSet<String>) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (mut old_value, mut new_value) = (read_json(old)?, read_json(new)?);
+            old_value.remove_keys(&keys);
+            new_value.remove_keys(&keys);
+            json_eq(old, &old_value, &new_value)
+        })
+    })
+}
+
+/// Compare JSON files like [`json_diff`], ignoring the entries that `paths`
+/// select.
+///
+/// Paths start at `$` and select object keys with `.key` or `["key"]`, array
+/// elements with `[0]`, and every key or element with `.*` or `[*]`, e.g.
+/// `$.meta.generated_at` or `$.items[*].id`. Ignored object entries may be
+/// missing from either file. Ignored array elements are compared as `null`, so
+/// the arrays must still be the same length. Invalid paths panic here.
+pub fn json_diff_ignoring_paths(paths: Vec<String>) -> Differ {
+    panic_on_mismatch(json_outcome_ignoring_paths(paths))
+}
+
+/// Compare JSON files like [`json_diff_ignoring_paths`], returning the outcome
+/// instead of panicking.
+pub fn json_outcome_ignoring_paths(paths: Vec<String>) -> OutcomeDiffer {
+    let paths: Vec<Vec<json::PathStep>> = paths
+        .iter()
+        .map(|path| {
+            json::parse_path(path)
+                .unwrap_or_else(|err| panic!("Invalid JSON path {:?}: {}", path, err))
+        })
+        .collect();
+    Box::new(move |old, new| {
+        compare(|| {
+            let (mut old_value, mut new_value) = (read_json(old)?, read_json(new)?);
+            for path in &paths {
+                old_value.ignore_path(path);
+                new_value.ignore_path(path);
+            }
+            json_eq(old, &old_value, &new_value)
+        })
+    })
+}
+
+/// Compare JSON files after running both through a jq filter, as by
+/// [`json_diff`]. Panic naming the first difference in the filter's output.
+///
+/// Only a subset of jq is supported: `.`, `.key`, `."key"`, `.[index]`,
+/// `.["key"]`, `.[]`, `|`, `,`, parentheses, `keys` and `length`. The outputs
+/// are compared as an array, so `$[0]` in a message is the first output.
+/// An invalid filter panics here, and a filter that fails on a file, like
+/// iterating over a number, panics with a "jq filter failed" message rather
+/// than naming a difference.
+pub fn json_jq_diff(filter: &str) -> Differ {
+    panic_on_mismatch(json_jq_outcome(filter))
+}
+
+/// Compare JSON files like [`json_jq_diff`], returning the outcome instead of
+/// panicking.
+pub fn json_jq_outcome(filter: &str) -> OutcomeDiffer {
+    let filter = jq::Filter::parse(filter)
+        .unwrap_or_else(|err| panic!("Invalid jq filter {:?}: {}", filter, err));
+    Box::new(move |old, new| {
+        compare(|| {
+            let run = |path: &Path| match filter.run(&read_json(path)?) {
+                Ok(outputs) => Ok(json::Value::Array(outputs)),
+                Err(err) => mismatch!("{}: jq filter failed: {}", path.display(), err),
+            };
+            json_eq(old, &run(old)?, &run(new)?)
+        })
+    })
+}
+
+fn json_eq(old_path: &Path, old: &json::Value, new: &json::Value) -> Result<(), DiffOutcome> {
+    if let Some((path, expected, found)) = json::first_difference(old, new, "$") {
+        mismatch!(
+            "{}: JSON differs at {}: expected {}, found {}",
+            old_path.display(),
+            path,
+            expected,
+            found
+        );
+    }
+    Ok(())
+}
+
+/// Compare JSON Lines (ndjson) files record by record. Panic naming the line
+/// and JSON path of the first differing record.
+///
+/// Each line is parsed as a JSON value, and records are compared in order as
+/// by [`json_diff`]. Lines that aren't valid JSON in either file are
+/// compared as text, and if they differ the whole files are diffed as text.
+pub fn jsonl_diff(old: &Path, new: &Path) {
+    raise(jsonl_outcome(old, new));
+}
+
+/// Compare JSON Lines files like [`jsonl_diff`], returning the outcome instead
+/// of panicking.
+pub fn jsonl_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+        let old_lines: Vec<&str> = old_text.lines().collect();
+        let new_lines: Vec<&str> = new_text.lines().collect();
+        for i in 0..old_lines.len().max(new_lines.len()) {
+            let (old_line, new_line) = (old_lines.get(i), new_lines.get(i));
+            let (Some(old_line), Some(new_line)) = (old_line, new_line) else {
+                mismatch!(
+                    "{}: line {}: expected {}, found {}",
+                    old.display(),
+                    i + 1,
+                    old_line.unwrap_or(&"(nothing)"),
+                    new_line.unwrap_or(&"(nothing)")
+                );
+            };
+            match (json::parse(old_line), json::parse(new_line)) {
+                (Ok(old_record), Ok(new_record)) => {
+                    if let Some((path, expected, found)) =
+                        json::first_difference(&old_record, &new_record, "$")
+                    {
+                        mismatch!(
+                            "{}: line {}: record differs at {}: expected {}, found {}",
+                            old.display(),
+                            i + 1,
+                            path,
+                            expected,
+                            found
+                        );
+                    }
+                }
+                _ if old_line == new_line => {}
+                _ => text_eq(old, &old_text, &new_text)?,
+            }
+        }
+        Ok(())
+    })
+}
+
+/// How [`json_subset_diff`] matches arrays in the golden against arrays in the
+/// new file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonArrayMatch {
+    /// Golden elements must match the new array's leading elements, in order.
+    Prefix,
+    /// Every golden element must match some element of the new array, in any
+    /// order.
+    Contains,
+}
+
+/// Compare JSON files where the golden is a partial specification. Panic
+/// naming the first golden path the new file does not satisfy.
+///
+/// Objects in the new file must contain every key in the golden, but may have
+/// extra keys. Nested values are matched the same way, and scalars must be
+/// equal.
+pub fn json_subset_diff(arrays: JsonArrayMatch) -> Differ {
+    panic_on_mismatch(json_subset_outcome(arrays))
+}
+
+/// Compare JSON files like [`json_subset_diff`], returning the outcome instead
+/// of panicking.
+pub fn json_subset_outcome(arrays: JsonArrayMatch) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let golden = read_json(old)?;
+            let actual = read_json(new)?;
+            if let Err((path, message)) = json_contains(&actual, &golden, "$", arrays) {
+                mismatch!(
+                    "{}: JSON subset not satisfied at {}: {}",
+                    old.display(),
+                    path,
+                    message
+                );
+            }
+            Ok(())
+        })
+    })
+}
+
+fn json_contains(
+    actual: &json::Value,
+    expected: &json::Value,
+    path: &str,
+    arrays: JsonArrayMatch,
+) -> std::result::Result<(), (String, String)> {
+    match (actual, expected) {
+        (json::Value::Object(_), json::Value::Object(entries)) => {
+            for (key, value) in entries {
+                let path = json::key_path(path, key);
+                match actual.get(key) {
+                    Some(found) => json_contains(found, value, &path, arrays)?,
+                    None => return Err((path, "missing".to_string())),
+                }
+            }
+            Ok(())
+        }
+        (json::Value::Array(found), json::Value::Array(items)) => match arrays {
+            JsonArrayMatch::Prefix => {
+                for (i, item) in items.iter().enumerate() {
+                    let path = json::index_path(path, i);
+                    match found.get(i) {
+                        Some(found) => json_contains(found, item, &path, arrays)?,
+                        None => return Err((path, "missing".to_string())),
+                    }
+                }
+                Ok(())
+            }
+            JsonArrayMatch::Contains => {
+                for (i, item) in items.iter().enumerate() {
+                    let matched = found
+                        .iter()
+                        .any(|found| json_contains(found, item, path, arrays).is_ok());
+                    if !matched {
+                        let path = json::index_path(path, i);
+                        return Err((path, format!("no element matches {}", item)));
+                    }
+                }
+                Ok(())
+            }
+        },
+        _ if actual == expected => Ok(()),
+        _ => Err((
+            path.to_string(),
+            format!("expected {}, found {}", expected, actual),
+        )),
+    }
+}
+
+/// Compare raw HTTP messages, ignoring header order and the case of header
+/// names. Headers named in `dropped`, like `Date` or `Set-Cookie`, are removed
+/// first. Panic saying whether the start line and headers or the body differ.
+///
+/// A message is a start line, header lines and a body after the first blank
+/// line, with `\n` or `\r\n` line endings. Repeated headers keep their
+/// relative order, and continuation lines are joined to the header above.
+pub fn http_message_diff(dropped: HashSet<String>) -> Differ {
+    panic_on_mismatch(http_message_outcome(dropped))
+}
+
+/// Compare raw HTTP messages like [`http_message_diff`], returning the outcome
+/// instead of panicking.
+pub fn http_message_outcome(dropped: HashSet<String>) -> OutcomeDiffer {
+    let dropped: HashSet<String> = dropped.iter().map(|name| name.to_lowercase()).collect();
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_text, new_text) = (read_text_file(old)?, read_text_file(new)?);
+            let (old_head, old_body) = parse_http_message(&old_text, &dropped);
+            let (new_head, new_body) = parse_http_message(&new_text, &dropped);
+            let parts = [
+                ("HTTP headers differ", old_head.as_str(), new_head.as_str()),
+                ("HTTP body differs", old_body, new_body),
+            ];
+            for (heading, old_part, new_part) in parts {
+                if old_part != new_part {
+                    mismatch!(
+                        "{}: {}\n{}",
+                        old.display(),
+                        heading,
+                        similar_asserts::SimpleDiff::from_str(old_part, new_part, "old", "new")
+                    );
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Split an HTTP message into its start line and sorted headers, one per line,
+/// and its body.
+fn parse_http_message<'a>(text: &'a str, dropped: &HashSet<String>) -> (String, &'a str) {
+    let mut lines = vec![];
+    let mut body = "";
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+        rest = next;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() {
+            body = rest;
+            break;
+        }
+        lines.push(line);
+    }
+
+    let mut start = String::new();
+    let mut headers: Vec<(String, String)> = vec![];
+    for (i, line) in lines.into_iter().enumerate() {
+        if i == 0 {
+            start = line.to_string();
+        } else if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else {
+            let (name, value) = line.split_once(':').unwrap_or((line, ""));
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers.retain(|(name, _)| !dropped.contains(name));
+    headers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut head = start;
+    for (name, value) in headers {
+        head.push_str(&format!("\n{}: {}", name, value));
+    }
+    (head, body)
+}
+
+fn read_json(path: &Path) -> Result<json::Value, DiffOutcome> {
+    match json::parse(&read_text_file(path)?) {
+        Ok(value) => Ok(value),
+        Err(err) => mismatch!("{}: invalid JSON: {}", path.display(), err),
+    }
+}
+
+/// Validate the new file as JSON against the JSON Schema at `schema_path`,
+/// ignoring the old file. Panic listing every validation error with its JSON
+/// path.
+///
+/// Supports the keywords contract tests commonly use: `type`, `enum`,
+/// `const`, `properties`, `required`, `additionalProperties`,
+/// `minProperties`, `maxProperties`, `items`, `minItems`, `maxItems`,
+/// `uniqueItems`, `minLength`, `maxLength`, `minimum`, `maximum`,
+/// `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`, `allOf`, `anyOf`,
+/// `oneOf`, `not` and `$ref`s within the schema. A schema using other
+/// keywords, apart from annotations like `title` and `format`, panics rather
+/// than passing. To keep the schema itself as the goldenfile, use
+/// [`Mint::register_schema_goldenfile`](crate::Mint::register_schema_goldenfile).
+///
+/// Requires the `json-schema` feature.
+#[cfg(feature = "json-schema")]
+pub fn json_schema_diff<P: AsRef<Path>>(schema_path: P) -> Differ {
+    panic_on_mismatch(json_schema_outcome(schema_path))
+}
+
+/// Validate the new file like [`json_schema_diff`], returning the outcome
+/// instead of panicking.
+///
+/// Requires the `json-schema` feature.
+#[cfg(feature = "json-schema")]
+pub fn json_schema_outcome<P: AsRef<Path>>(schema_path: P) -> OutcomeDiffer {
+    let schema_path = schema_path.as_ref().to_path_buf();
+    Box::new(move |old, new| {
+        compare(|| {
+            let errors = schema_errors(&schema_path, new)?;
+            if !errors.is_empty() {
+                mismatch!(
+                    "{}: JSON doesn't match the schema {}:\n  {}",
+                    old.display(),
+                    schema_path.display(),
+                    errors.join("\n  ")
+                );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Validate the new file against the old file as a JSON Schema, as for
+/// [`json_schema_diff`].
+#[cfg(feature = "json-schema")]
+pub(crate) fn schema_golden_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let errors = schema_errors(old, new)?;
+        if !errors.is_empty() {
+            mismatch!(
+                "{}: JSON doesn't match the schema:\n  {}",
+                old.display(),
+                errors.join("\n  ")
+            );
+        }
+        Ok(())
+    })
+}
+
+#[cfg(feature = "json-schema")]
+fn schema_errors(schema_path: &Path, path: &Path) -> Result<Vec<String>, DiffOutcome> {
+    match schema::validate(&read_json(schema_path)?, &read_json(path)?) {
+        Ok(errors) => Ok(errors),
+        Err(err) => mismatch!("{}: invalid schema: {}", schema_path.display(), err),
+    }
+}
+
+/// Compare XML files by element structure, ignoring the nodes and attributes
+/// that `paths` select. Panic naming the path of the first remaining
+/// difference.
+///
+/// Paths are a subset of XPath: absolute steps separated by `/`, or by `//` to
+/// match at any depth, made of element names, `*`, `text()`, or a final
+/// `@name` or `@*` for attributes, with positions like `[2]` on element and
+/// text steps. For example, `//meta/@generatedAt` ignores the `generatedAt`
+/// attribute of every `meta` element, and `/config/build[1]` ignores the first
+/// `build` element under `config`. Attribute order, comments, processing
+/// instructions and whitespace around text are ignored too. Invalid paths
+/// panic here.
+///
+/// Requires the `xml` feature.
+#[cfg(feature = "xml")]
+pub fn xml_diff_ignoring_paths(paths: Vec<String>) -> Differ {
+    panic_on_mismatch(xml_outcome_ignoring_paths(paths))
+}
+
+/// Compare XML files like [`xml_diff_ignoring_paths`], returning the outcome
+/// instead of panicking.
+///
+/// Requires the `xml` feature.
+#[cfg(feature = "xml")]
+pub fn xml_outcome_ignoring_paths(paths: Vec<String>) -> OutcomeDiffer {
+    let paths: Vec<xml::Path> = paths
+        .iter()
+        .map(|path| {
+            xml::Path::parse(path)
+                .unwrap_or_else(|err| panic!("Invalid XML path {:?}: {}", path, err))
+        })
+        .collect();
+    Box::new(move |old, new| {
+        compare(|| {
+            let parse = |path: &Path| {
+                let mut nodes = match xml::parse(&read_text_file(path)?) {
+                    Ok(nodes) => nodes,
+                    Err(err) => mismatch!("{}: invalid XML: {}", path.display(), err),
+                };
+                for ignored in &paths {
+                    ignored.remove_from(&mut nodes);
+                }
+                Ok(nodes)
+            };
+            if let Some((path, expected, found)) =
+                xml::first_difference(&parse(old)?, &parse(new)?, "")
+            {
+                mismatch!(
+                    "{}: XML differs at {}: expected {}, found {}",
+                    old.display(),
+                    path,
+                    expected,
+                    found
+                );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare Cargo.lock-style lockfiles by their `[[package]]` entries, ignoring
+/// the order of packages and of the keys within them. Panic listing the
+/// packages that were added, removed, updated to another version or changed,
+/// and the keys that changed outside of packages.
+///
+/// Only the subset of TOML that Cargo writes to lockfiles is understood.
+pub fn lockfile_diff(old: &Path, new: &Path) {
+    raise(lockfile_outcome(old, new));
+}
+
+/// Compare lockfiles like [`lockfile_diff`], returning the outcome instead of
+/// panicking.
+pub fn lockfile_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let parse = |path: &Path| match lockfile::parse(&read_text_file(path)?) {
+            Ok(lockfile) => Ok(lockfile),
+            Err(err) => mismatch!("{}: invalid lockfile: {}", path.display(), err),
+        };
+        let lines = lockfile::differences(&parse(old)?, &parse(new)?);
+        if !lines.is_empty() {
+            mismatch!(
+                "{}: Lockfiles differ\n  {}",
+                old.display(),
+                lines.join("\n  ")
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Compare Rust source files after formatting both with `rustfmt`, so
+/// formatting differences in generated code are ignored.
+///
+/// `rustfmt` must be on the `PATH`, and runs with edition 2021 and its default
+/// configuration. If it can't run or rejects a file, panic with its error
+/// rather than a diff.
+///
+/// Requires the `rustfmt` feature.
+#[cfg(feature = "rustfmt")]
+pub fn rustfmt_diff(old: &Path, new: &Path) {
+    raise(rustfmt_outcome(old, new));
+}
+
+/// Compare Rust source files like [`rustfmt_diff`], returning the outcome
+/// instead of panicking.
+///
+/// Requires the `rustfmt` feature.
+#[cfg(feature = "rustfmt")]
+pub fn rustfmt_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| text_eq(old, &rustfmt(old)?, &rustfmt(new)?))
+}
+
+#[cfg(feature = "rustfmt")]
+fn rustfmt(path: &Path) -> Result<String, DiffOutcome> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let source = read_text_file(path)?;
+    let spawned = Command::new("rustfmt")
+        .args(["--edition", "2021", "--emit", "stdout"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => mismatch!("Failed to run rustfmt: {}", err),
+    };
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(source.as_bytes()));
+    let output = match child.wait_with_output() {
+        Ok(output) => output,
+        Err(err) => mismatch!("Failed to run rustfmt: {}", err),
+    };
+    writer.join().unwrap().ok();
+    if !output.status.success() {
+        mismatch!(
+            "{}: rustfmt failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+    match String::from_utf8(output.stdout) {
+        Ok(text) => Ok(text),
+        Err(err) => mismatch!("rustfmt produced invalid UTF-8: {}", err),
+    }
+}
+
+/// Compare WAV files by their decoded samples, allowing each to differ by up
+/// to `sample_tolerance`, so differences in header bytes and metadata chunks
+/// are ignored.
+///
+/// Integer samples are scaled to `-1.0..=1.0` before comparing, so the
+/// tolerance doesn't depend on the bit depth. The channel count, sample rate
+/// and sample encoding must match, as must the length. Panic naming the first
+/// sample outside the tolerance and the RMS difference over all samples.
+///
+/// Requires the `wav` feature.
+#[cfg(feature = "wav")]
+pub fn wav_diff(sample_tolerance: f64) -> Differ {
+    panic_on_mismatch(wav_outcome(sample_tolerance))
+}
+
+/// Compare WAV files like [`wav_diff`], returning the outcome instead of
+/// panicking.
+///
+/// Requires the `wav` feature.
+#[cfg(feature = "wav")]
+pub fn wav_outcome(sample_tolerance: f64) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let decode = |path: &Path| match wav::parse(&read_file(path)?) {
+                Ok(wav) => Ok(wav),
+                Err(err) => mismatch!("{}: invalid WAV: {}", path.display(), err),
+            };
+            let (old_wav, new_wav) = (decode(old)?, decode(new)?);
+            if old_wav.format != new_wav.format {
+                mismatch!(
+                    "{}: WAV formats differ: old is {}, new is {}",
+                    old.display(),
+                    old_wav.format,
+                    new_wav.format
+                );
+            }
+            let channels = old_wav.channels();
+            if old_wav.samples.len() != new_wav.samples.len() {
+                mismatch!(
+                    "{}: WAV lengths differ: old has {} frames, new has {}",
+                    old.display(),
+                    old_wav.samples.len() / channels,
+                    new_wav.samples.len() / channels
+                );
+            }
+
+            let deltas: Vec<f64> = old_wav
+                .samples
+                .iter()
+                .zip(&new_wav.samples)
+                .map(|(a, b)| (a - b).abs())
+                .collect();
+            if let Some(i) = deltas.iter().position(|&delta| delta > sample_tolerance) {
+                let rms = (deltas.iter().map(|d| d * d).sum::<f64>() / deltas.len() as f64).sqrt();
+                mismatch!(
+                "{}: Sample {} (frame {}, channel {}) differs by {}, more than {}: old {}, new {}; RMS difference {}",
+                old.display(),
+                i,
+                i / channels,
+                i % channels,
+                deltas[i],
+                sample_tolerance,
+                old_wav.samples[i],
+                new_wav.samples[i],
+                rms
+            );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare Graphviz DOT files as sets of nodes and edges, ignoring declaration
+/// order and attribute order. Panic listing the nodes, edges and attribute
+/// statements only present in one graph.
+///
+/// A node's attributes are merged across its declarations, and nodes that only
+/// appear in edges are included without attributes. Undirected edges match
+/// regardless of endpoint order. Subgraphs only group their contents, so
+/// moving a node between subgraphs is not a difference.
+///
+/// Requires the `dot` feature.
+#[cfg(feature = "dot")]
+pub fn dot_diff(old: &Path, new: &Path) {
+    raise(dot_outcome(old, new));
+}
+
+/// Compare Graphviz DOT files like [`dot_diff`], returning the outcome instead
+/// of panicking.
+///
+/// Requires the `dot` feature.
+#[cfg(feature = "dot")]
+pub fn dot_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let parse = |path: &Path| match dot::parse(&read_text_file(path)?) {
+            Ok(graph) => Ok(graph.describe()),
+            Err(err) => mismatch!("{}: invalid DOT: {}", path.display(), err),
+        };
+        let (old_lines, new_lines) = (parse(old)?, parse(new)?);
+        let only_old: Vec<&String> = old_lines
+            .iter()
+            .filter(|l| !new_lines.contains(l))
+            .collect();
+        let only_new: Vec<&String> = new_lines
+            .iter()
+            .filter(|l| !old_lines.contains(l))
+            .collect();
+        if !only_old.is_empty() || !only_new.is_empty() {
+            let mut message = format!("{}: Graphs differ", old.display());
+            for (label, lines) in [("old", only_old), ("new", only_new)] {
+                for line in lines {
+                    message.push_str(&format!("\n  only in {}: {}", label, line));
+                }
+            }
+            mismatch!("{}", message);
+        }
+        Ok(())
+    })
+}
+
+/// Compare HTML files by their normalized DOM. Panic naming the path of the
+/// first differing node.
+///
+/// Tag and attribute names are case-insensitive, attribute order does not
+/// matter, character references are decoded, and whitespace in text is
+/// collapsed, except inside `<pre>` and `<textarea>`. Comments and doctypes
+/// are ignored.
+///
+/// Requires the `html` feature.
+#[cfg(feature = "html")]
+pub fn html_diff(old: &Path, new: &Path) {
+    raise(html_outcome(old, new));
+}
+
+/// Compare HTML files like [`html_diff`], returning the outcome instead of
+/// panicking.
+///
+/// Requires the `html` feature.
+#[cfg(feature = "html")]
+pub fn html_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        let old_nodes = html::parse(&read_text_file(old)?);
+        let new_nodes = html::parse(&read_text_file(new)?);
+        if let Some((path, old_node, new_node)) = html::first_difference(&old_nodes, &new_nodes, "")
+        {
+            mismatch!(
+                "{}: HTML differs at {}:\n  old: {}\n  new: {}",
+                old.display(),
+                path,
+                old_node,
+                new_node
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Compare terminal output by the screen it renders to on a `cols` by `rows`
+/// terminal, so different escape sequences that draw the same characters
+/// with the same attributes are not a difference. Panic naming the first
+/// differing cell, with 1-based coordinates.
+///
+/// Cursor movement, erasing, scrolling and SGR attributes are interpreted;
+/// other escape sequences are ignored. Since the screen size isn't part of
+/// the file, register this with
+/// [`Mint::new_goldenfile_with_differ`](crate::Mint::new_goldenfile_with_differ).
+///
+/// Requires the `vt100` feature.
+#[cfg(feature = "vt100")]
+pub fn vt100_diff(cols: usize, rows: usize) -> Differ {
+    panic_on_mismatch(vt100_outcome(cols, rows))
+}
+
+/// Compare terminal output like [`vt100_diff`], returning the outcome instead
+/// of panicking.
+///
+/// Requires the `vt100` feature.
+#[cfg(feature = "vt100")]
+pub fn vt100_outcome(cols: usize, rows: usize) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let render = |path: &Path| {
+                let output = read_file(path)?;
+                Ok::<_, DiffIoError>(vt100::render(&String::from_utf8_lossy(&output), cols, rows))
+            };
+            let (old_screen, new_screen) = (render(old)?, render(new)?);
+            for row in 0..rows.max(1) {
+                for col in 0..cols.max(1) {
+                    let (old_cell, new_cell) =
+                        (old_screen.cell(row, col), new_screen.cell(row, col));
+                    if old_cell != new_cell {
+                        mismatch!(
+                        "{}: Screens differ at row {}, column {}: old {}, new {}\n  old row: {:?}\n  new row: {:?}",
+                        old.display(),
+                        row + 1,
+                        col + 1,
+                        old_cell,
+                        new_cell,
+                        old_screen.row_text(row),
+                        new_screen.row_text(row)
+                    );
+                    }
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+fn strip_comments(text: &str, language: CommentLanguage) -> String {
+    let mut out = String::new();
+    let mut line = String::new();
+    let mut line_had_comment = false;
+    let mut in_block = false;
+    let mut in_line_comment = false;
+    let mut in_string = None;
+
+    let mut flush = |line: &mut String, had_comment: bool, newline: bool| {
+        if had_comment {
+            let trimmed = line.trim_end();
+            if !trimmed.is_empty() {
+                out.push_str(trimmed);
+                if newline {
+                    out.push('\n');
+                }
+            }
+        } else {
+            out.push_str(line);
+            if newline {
+                out.push('\n');
+            }
+        }
+        line.clear();
+    };
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            flush(&mut line, line_had_comment, true);
+            line_had_comment = in_block;
+            in_line_comment = false;
+            in_string = None;
+            continue;
+        }
+        if in_line_comment {
+            continue;
+        }
+        if in_block {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                in_block = false;
+            }
+            continue;
+        }
+        if let Some(quote) = in_string {
+            line.push(c);
+            if c == '\\' && chars.peek().is_some_and(|&n| n != '\n') {
+                line.push(chars.next().unwrap());
+            } else if c == quote {
+                in_string = None;
+            }
+            continue;
+        }
+        match (language, c, chars.peek()) {
+            (_, '"', _) | (CommentLanguage::Hash, '\'', _) => {
+                in_string = Some(c);
+                line.push(c);
+            }
+            (CommentLanguage::CStyle, '\'', _) => {
+                // A char literal like '"' or '\'' is quoted, but a lifetime
+                // like 'a has no closing quote and is plain code.
+                let mut ahead = chars.clone();
+                let literal = match ahead.next() {
+                    Some('\\') => true,
+                    Some('\n') | None => false,
+                    Some(_) => ahead.next() == Some('\''),
+                };
+                if literal {
+                    in_string = Some(c);
+                }
+                line.push(c);
+            }
+            (CommentLanguage::CStyle, '/', Some('/')) | (CommentLanguage::Hash, '#', _) => {
+                in_line_comment = true;
+                line_had_comment = true;
+            }
+            (CommentLanguage::CStyle, '/', Some('*')) => {
+                chars.next();
+                in_block = true;
+                line_had_comment = true;
+            }
+            _ => line.push(c),
+        }
+    }
+    if !line.is_empty() || line_had_comment {
+        flush(&mut line, line_had_comment, false);
+    }
+    out
 }
 
 /// Panic if binary files differ with some basic information about where they
-/// differ.
+/// differ, including the size of each file.
 pub fn binary_diff(old: &Path, new: &Path) {
-    let old_len = file_len(old);
-    let new_len = file_len(new);
-    if old_len != new_len {
-        panic!(
-            "File sizes differ: Old file is {} bytes, new file is {} bytes",
-            old_len, new_len
+    raise(binary_outcome(old, new));
+}
+
+/// Compare binary files of the same size, allowing up to `max_changed` bytes
+/// to differ. Panic with the number of differing bytes, the first one and the
+/// size of each file.
+pub fn changed_bytes_diff(max_changed: usize) -> Differ {
+    panic_on_mismatch(changed_bytes_outcome(max_changed))
+}
+
+/// Compare binary files like [`changed_bytes_diff`], returning the outcome
+/// instead of panicking.
+pub fn changed_bytes_outcome(max_changed: usize) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_bytes, new_bytes) = same_size_bytes(old, new)?;
+            let changed: Vec<usize> = old_bytes
+                .iter()
+                .zip(&new_bytes)
+                .enumerate()
+                .filter(|(_, (a, b))| a != b)
+                .map(|(i, _)| i)
+                .collect();
+            if changed.len() > max_changed {
+                mismatch!(
+                "{}: {} bytes differ, more than {}, starting at byte {}: old is {} bytes, new is {} bytes",
+                old.display(),
+                changed.len(),
+                max_changed,
+                changed[0] + 1,
+                old_bytes.len(),
+                new_bytes.len()
+            );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare binary files of the same size, such as raw images, allowing the
+/// mean absolute difference between their bytes to be up to `max_mean`. Panic
+/// with the mean difference and the size of each file.
+pub fn mean_byte_diff(max_mean: f64) -> Differ {
+    panic_on_mismatch(mean_byte_outcome(max_mean))
+}
+
+/// Compare binary files like [`mean_byte_diff`], returning the outcome instead
+/// of panicking.
+pub fn mean_byte_outcome(max_mean: f64) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_bytes, new_bytes) = same_size_bytes(old, new)?;
+            let total: u64 = old_bytes
+                .iter()
+                .zip(&new_bytes)
+                .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+                .sum();
+            let mean = total as f64 / old_bytes.len().max(1) as f64;
+            if mean > max_mean {
+                mismatch!(
+                "{}: mean byte difference {:.3} is more than {}: old is {} bytes, new is {} bytes",
+                old.display(),
+                mean,
+                max_mean,
+                old_bytes.len(),
+                new_bytes.len()
+            );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Read two binary files, failing with a mismatch like [`binary_diff`] if
+/// their sizes differ.
+fn same_size_bytes(old: &Path, new: &Path) -> Result<(Vec<u8>, Vec<u8>), DiffOutcome> {
+    let (old_bytes, new_bytes) = (check_io(fs::read(old), old)?, check_io(fs::read(new), new)?);
+    if old_bytes.len() != new_bytes.len() {
+        mismatch!(
+            "{}: File sizes differ: old is {} bytes, new is {} bytes",
+            old.display(),
+            old_bytes.len(),
+            new_bytes.len()
+        );
+    }
+    Ok((old_bytes, new_bytes))
+}
+
+/// Where the meaningful contents of a padded binary file end, for
+/// [`binary_diff_ignoring_trailing_pad`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogicalLength {
+    /// The first this many bytes of every file are meaningful.
+    Bytes(u64),
+    /// Each file's meaningful contents end after the first occurrence of this
+    /// byte sequence.
+    EndMarker(Vec<u8>),
+}
+
+/// Compare binary files padded to a multiple of `block` bytes, ignoring the
+/// padding. Panic with the first differing byte of the meaningful contents.
+///
+/// The padding is the rest of the final block after the logical length given
+/// by `logical_len`, and may hold arbitrary bytes. Files whose logical lengths
+/// differ, or that run longer than the block holding their logical end, don't
+/// match.
+pub fn binary_diff_ignoring_trailing_pad(block: usize, logical_len: LogicalLength) -> Differ {
+    panic_on_mismatch(binary_outcome_ignoring_trailing_pad(block, logical_len))
+}
+
+/// Compare padded binary files like [`binary_diff_ignoring_trailing_pad`],
+/// returning the outcome instead of panicking.
+pub fn binary_outcome_ignoring_trailing_pad(
+    block: usize,
+    logical_len: LogicalLength,
+) -> OutcomeDiffer {
+    assert!(block > 0, "block size must be positive");
+    if let LogicalLength::EndMarker(marker) = &logical_len {
+        assert!(!marker.is_empty(), "end marker must not be empty");
+    }
+    Box::new(move |old, new| {
+        compare(|| {
+            let meaningful = |path: &Path| {
+                let mut contents = read_file(path)?;
+                let len = match &logical_len {
+                    LogicalLength::Bytes(len) => usize::try_from(*len).unwrap_or(usize::MAX),
+                    LogicalLength::EndMarker(marker) => match contents
+                        .windows(marker.len())
+                        .position(|window| window == &marker[..])
+                    {
+                        Some(start) => start + marker.len(),
+                        None => mismatch!("{}: end marker not found", path.display()),
+                    },
+                };
+                let padded = len.div_ceil(block).saturating_mul(block);
+                if contents.len() < len || contents.len() > padded {
+                    mismatch!(
+                        "{}: {} bytes is not {} bytes padded to a multiple of {}",
+                        path.display(),
+                        contents.len(),
+                        len,
+                        block
+                    );
+                }
+                let file_len = contents.len();
+                contents.truncate(len);
+                Ok((contents, file_len))
+            };
+            let ((old_contents, old_len), (new_contents, new_len)) =
+                (meaningful(old)?, meaningful(new)?);
+            if old_contents.len() != new_contents.len() {
+                mismatch!(
+                "{}: Logical lengths differ: old is {} bytes, new is {} bytes, padded to {} and {} bytes",
+                old.display(),
+                old_contents.len(),
+                new_contents.len(),
+                old_len,
+                new_len
+            );
+            }
+            let first_difference = old_contents
+                .iter()
+                .zip(&new_contents)
+                .position(|(old_byte, new_byte)| old_byte != new_byte);
+            if let Some(position) = first_difference {
+                mismatch!(
+                    "{}: Files differ at byte {}: old is {} bytes, new is {} bytes",
+                    old.display(),
+                    position + 1,
+                    old_len,
+                    new_len
+                );
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Panic if binary files differ with a compact summary of the differing byte
+/// ranges, suitable for large files.
+///
+/// Ranges are half-open byte offsets, starting from 0. Bytes past the end of
+/// the shorter file count as differing. At most 10 ranges are listed.
+pub fn binary_regions_diff(old: &Path, new: &Path) {
+    raise(binary_regions_outcome(old, new));
+}
+
+/// Compare binary files like [`binary_regions_diff`], returning the outcome
+/// instead of panicking.
+pub fn binary_regions_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        const MAX_LISTED: usize = 10;
+
+        let old_len = file_len(old)?;
+        let new_len = file_len(new)?;
+        let mut regions: Vec<(u64, u64)> = vec![];
+        let mut offset = 0;
+        let mut old_bytes = file_byte_iter(old)?;
+        let mut new_bytes = file_byte_iter(new)?;
+        loop {
+            let (old_byte, new_byte) = (
+                next_byte(&mut old_bytes, old)?,
+                next_byte(&mut new_bytes, new)?,
+            );
+            if old_byte.is_none() && new_byte.is_none() {
+                break;
+            }
+            if old_byte != new_byte {
+                match regions.last_mut() {
+                    Some((_, end)) if *end == offset => *end += 1,
+                    _ => regions.push((offset, offset + 1)),
+                }
+            }
+            offset += 1;
+        }
+
+        if !regions.is_empty() {
+            let total: u64 = regions.iter().map(|(start, end)| end - start).sum();
+            let mut message = format!(
+            "{}: Files differ in {} region(s), {} byte(s) total: old is {} bytes, new is {} bytes",
+            old.display(),
+            regions.len(),
+            total,
+            old_len,
+            new_len
+        );
+            for (start, end) in regions.iter().take(MAX_LISTED) {
+                message.push_str(&format!("\n  {}..{} ({} bytes)", start, end, end - start));
+            }
+            if regions.len() > MAX_LISTED {
+                message.push_str(&format!("\n  ... and {} more", regions.len() - MAX_LISTED));
+            }
+            mismatch!("{}", message);
+        }
+        Ok(())
+    })
+}
+
+/// Panic if binary files differ, listing the bytes inserted, deleted and
+/// replaced to turn the old file into the new one.
+///
+/// Unlike [`binary_regions_diff`], bytes inserted or removed part way through
+/// only show up as that edit, not as every later byte differing. Ranges are
+/// half-open byte offsets, starting from 0, and each edit shows the bytes
+/// involved in hex, up to 16 from each file. At most 10 edits are listed.
+///
+/// Diffing gives up on finding the fewest edits after a second, so large,
+/// very different files may list more or bigger edits than they need.
+pub fn binary_edit_diff(old: &Path, new: &Path) {
+    raise(binary_edit_outcome(old, new));
+}
+
+/// Compare binary files like [`binary_edit_diff`], returning the outcome
+/// instead of panicking.
+pub fn binary_edit_outcome(old: &Path, new: &Path) -> DiffOutcome {
+    compare(|| {
+        const MAX_LISTED: usize = 10;
+        const MAX_SHOWN: usize = 16;
+        const TIMEOUT: Duration = Duration::from_secs(1);
+
+        let (old_bytes, new_bytes) = (read_file(old)?, read_file(new)?);
+        let deadline = Instant::now() + TIMEOUT;
+        let edits: Vec<DiffOp> =
+            capture_diff_slices_deadline(Algorithm::Myers, &old_bytes, &new_bytes, Some(deadline))
+                .into_iter()
+                .filter(|op| !matches!(op, DiffOp::Equal { .. }))
+                .collect();
+        if edits.is_empty() {
+            return Ok(());
+        }
+
+        let hex = |bytes: &[u8]| {
+            let mut shown: String = bytes
+                .iter()
+                .take(MAX_SHOWN)
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            if bytes.len() > MAX_SHOWN {
+                shown.push_str("...");
+            }
+            shown
+        };
+        let mut message = format!(
+            "{}: Files differ by {} edit(s): old is {} bytes, new is {} bytes",
+            old.display(),
+            edits.len(),
+            old_bytes.len(),
+            new_bytes.len()
         );
+        for op in edits.iter().take(MAX_LISTED) {
+            let (old_range, new_range) = (op.old_range(), op.new_range());
+            message.push_str(&match op {
+                DiffOp::Insert { .. } => format!(
+                    "\n  inserted at old {} (new {}..{}): {}",
+                    old_range.start,
+                    new_range.start,
+                    new_range.end,
+                    hex(&new_bytes[new_range.clone()])
+                ),
+                DiffOp::Delete { .. } => format!(
+                    "\n  deleted old {}..{} (at new {}): {}",
+                    old_range.start,
+                    old_range.end,
+                    new_range.start,
+                    hex(&old_bytes[old_range.clone()])
+                ),
+                _ => format!(
+                    "\n  replaced old {}..{} with new {}..{}: {} -> {}",
+                    old_range.start,
+                    old_range.end,
+                    new_range.start,
+                    new_range.end,
+                    hex(&old_bytes[old_range.clone()]),
+                    hex(&new_bytes[new_range.clone()])
+                ),
+            });
+        }
+        if edits.len() > MAX_LISTED {
+            message.push_str(&format!("\n  ... and {} more", edits.len() - MAX_LISTED));
+        }
+        mismatch!("{}", message);
+    })
+}
+
+/// Compare binary files as multisets of `record_size`-byte records, ignoring
+/// their order. Panic listing the records, in hex, that occur more often in
+/// one file than the other.
+///
+/// A record that occurs twice in one file and once in the other is listed
+/// once. At most 10 records are listed per file. Panics if a file's length is
+/// not a multiple of `record_size`, which must be positive.
+pub fn record_multiset_diff(record_size: usize) -> Differ {
+    panic_on_mismatch(record_multiset_outcome(record_size))
+}
+
+/// Compare binary files like [`record_multiset_diff`], returning the outcome
+/// instead of panicking.
+pub fn record_multiset_outcome(record_size: usize) -> OutcomeDiffer {
+    assert!(record_size > 0, "record size must be positive");
+    Box::new(move |old, new| {
+        compare(|| {
+            const MAX_LISTED: usize = 10;
+
+            let (old_bytes, new_bytes) = (read_file(old)?, read_file(new)?);
+            for (path, bytes) in [(old, &old_bytes), (new, &new_bytes)] {
+                if !bytes.len().is_multiple_of(record_size) {
+                    mismatch!(
+                        "{}: length {} is not a multiple of the record size {}",
+                        path.display(),
+                        bytes.len(),
+                        record_size
+                    );
+                }
+            }
+            let mut counts: BTreeMap<&[u8], isize> = Default::default();
+            for record in old_bytes.chunks_exact(record_size) {
+                *counts.entry(record).or_default() += 1;
+            }
+            for record in new_bytes.chunks_exact(record_size) {
+                *counts.entry(record).or_default() -= 1;
+            }
+            let only_old: Vec<&[u8]> = counts
+                .iter()
+                .filter(|(_, n)| **n > 0)
+                .map(|r| *r.0)
+                .collect();
+            let only_new: Vec<&[u8]> = counts
+                .iter()
+                .filter(|(_, n)| **n < 0)
+                .map(|r| *r.0)
+                .collect();
+            if !only_old.is_empty() || !only_new.is_empty() {
+                let mut message = format!(
+                    "{}: Records differ: old is {} bytes, new is {} bytes",
+                    old.display(),
+                    old_bytes.len(),
+                    new_bytes.len()
+                );
+                for (label, records) in [("old", only_old), ("new", only_new)] {
+                    for record in records.iter().take(MAX_LISTED) {
+                        let hex: String = record.iter().map(|b| format!("{:02x}", b)).collect();
+                        message.push_str(&format!("\n  only in {}: {}", label, hex));
+                    }
+                    if records.len() > MAX_LISTED {
+                        message
+                            .push_str(&format!("\n  ... and {} more", records.len() - MAX_LISTED));
+                    }
+                }
+                mismatch!("{}", message);
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Compare only the named byte `fields` of binary files with a fixed layout,
+/// such as a header, ignoring all other bytes. Panic listing each differing
+/// field by name with its old and new bytes in hex.
+///
+/// Fields are half-open byte ranges and may overlap. A field that extends past
+/// the end of a file differs from one that doesn't.
+pub fn struct_fields_binary_diff(fields: Vec<(String, Range<usize>)>) -> Differ {
+    panic_on_mismatch(struct_fields_binary_outcome(fields))
+}
+
+/// Compare fields of binary files like [`struct_fields_binary_diff`],
+/// returning the outcome instead of panicking.
+pub fn struct_fields_binary_outcome(fields: Vec<(String, Range<usize>)>) -> OutcomeDiffer {
+    Box::new(move |old, new| {
+        compare(|| {
+            let (old_bytes, new_bytes) = (read_file(old)?, read_file(new)?);
+            let describe = |bytes: &[u8], range: &Range<usize>| match bytes.get(range.clone()) {
+                Some(field) => field.iter().map(|b| format!("{:02x}", b)).collect(),
+                None => format!("past the end ({} bytes)", bytes.len()),
+            };
+            let mut message = format!(
+                "{}: Fields differ: old is {} bytes, new is {} bytes",
+                old.display(),
+                old_bytes.len(),
+                new_bytes.len()
+            );
+            let mut differ = false;
+            for (name, range) in &fields {
+                if old_bytes.get(range.clone()) != new_bytes.get(range.clone()) {
+                    differ = true;
+                    message.push_str(&format!(
+                        "\n  {} (bytes {}..{}): old {}, new {}",
+                        name,
+                        range.start,
+                        range.end,
+                        describe(&old_bytes, range),
+                        describe(&new_bytes, range)
+                    ));
+                }
+            }
+            if differ {
+                mismatch!("{}", message);
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Apply `transform` to the text of both files, then compare them with `inner`.
+///
+/// Files that aren't valid UTF-8 are passed to `inner` unchanged, and missing
+/// files stay missing. `inner` runs on transformed copies, so the diff it
+/// prints may name them, though the panic raised again names the originals.
+pub fn transformed_diff(
+    transform: Arc<dyn Fn(&str) -> String + Send + Sync>,
+    inner: Differ,
+) -> Differ {
+    transformed_bytes_diff(transform_text(transform), inner)
+}
+
+/// Apply `transform` to the text of both files, then compare them with
+/// `inner`, like [`transformed_diff`], returning the outcome instead of
+/// panicking.
+pub fn transformed_outcome(
+    transform: Arc<dyn Fn(&str) -> String + Send + Sync>,
+    inner: OutcomeDiffer,
+) -> OutcomeDiffer {
+    transformed_bytes_outcome(transform_text(transform), inner)
+}
+
+/// Apply `transform` to the contents of both files, then compare them with
+/// `inner`. Like [`transformed_diff`], but for binary files.
+pub fn transformed_bytes_diff(
+    transform: Arc<dyn Fn(Vec<u8>) -> Vec<u8> + Send + Sync>,
+    inner: Differ,
+) -> Differ {
+    let inner = AnyDiffer::Custom(inner);
+    Box::new(move |old, new| {
+        if let Err(failure) = diff_transformed(old, new, &inner, &*transform, &*transform) {
+            failure.raise();
+        }
+    })
+}
+
+/// Apply `transform` to the contents of both files, then compare them with
+/// `inner`, like [`transformed_bytes_diff`], returning the outcome instead of
+/// panicking.
+pub fn transformed_bytes_outcome(
+    transform: Arc<dyn Fn(Vec<u8>) -> Vec<u8> + Send + Sync>,
+    inner: OutcomeDiffer,
+) -> OutcomeDiffer {
+    let inner = AnyDiffer::Outcome(inner);
+    Box::new(move |old, new| {
+        into_outcome(diff_transformed(old, new, &inner, &*transform, &*transform))
+    })
+}
+
+/// A transform of text, as taken by [`transformed_diff`].
+type TextTransform = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Apply `transform` to contents that are valid UTF-8, leaving others as they
+/// are.
+fn transform_text(transform: TextTransform) -> Arc<dyn Fn(Vec<u8>) -> Vec<u8> + Send + Sync> {
+    Arc::new(move |contents| match String::from_utf8(contents) {
+        Ok(text) => transform(&text).into_bytes(),
+        Err(err) => err.into_bytes(),
+    })
+}
+
+/// Read a whole file, treating a missing file as empty.
+fn read_file(path: &Path) -> Result<Vec<u8>, DiffIoError> {
+    match fs::read(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        result => check_io(result, path),
+    }
+}
+
+/// Read a whole UTF-8 file, treating a missing file as empty.
+fn read_text_file(path: &Path) -> Result<String, DiffIoError> {
+    let text = String::from_utf8(read_file(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err));
+    check_io(text, path)
+}
+
+/// Fail with the diff between two texts, if they differ.
+fn text_eq(old_path: &Path, old: &str, new: &str) -> Result<(), DiffOutcome> {
+    match text_mismatch(old_path, old, new) {
+        Some(message) => Err(DiffOutcome::Mismatch(message)),
+        None => Ok(()),
+    }
+}
+
+/// The message for a mismatch between two texts, or `None` if they're equal.
+pub(crate) fn text_mismatch(old_path: &Path, old: &str, new: &str) -> Option<String> {
+    match side_by_side_env_width() {
+        Some(width) => side_by_side_mismatch(old_path, old, new, width),
+        None => (old != new).then(|| unified_mismatch(old_path, old, new)),
+    }
+}
+
+/// The message for a mismatch between two texts rendered side by side at
+/// `width`, or as a unified diff if that is too narrow.
+fn side_by_side_mismatch(old_path: &Path, old: &str, new: &str, width: usize) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    Some(match render_side_by_side(old, new, width) {
+        Some(table) => format!("{}: files differ\n{}", old_path.display(), table),
+        None => unified_mismatch(old_path, old, new),
+    })
+}
+
+fn unified_mismatch(old_path: &Path, old: &str, new: &str) -> String {
+    let diff = match elide_unchanged_env() {
+        Some(max_unchanged) => render_elided(old, new, max_unchanged),
+        None => similar_asserts::SimpleDiff::from_str(old, new, "old", "new").to_string(),
+    };
+    match changed_columns(old, new) {
+        Some(pointer) => format!(
+            "{}: files differ\n{}\n{}",
+            old_path.display(),
+            pointer,
+            diff
+        ),
+        None => format!("{}: files differ\n{}", old_path.display(), diff),
+    }
+}
+
+/// The most unchanged lines to show between changes, if
+/// `GOLDENFILE_ELIDE_UNCHANGED` is set to a number.
+fn elide_unchanged_env() -> Option<usize> {
+    std::env::var("GOLDENFILE_ELIDE_UNCHANGED")
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Render a line diff with `-` and `+` markers, showing at most
+/// `max_unchanged` lines of each run of unchanged lines. The lines nearest the
+/// changes are kept, and the rest replaced with a `... (k identical lines) ...`
+/// marker.
+fn render_elided(old: &str, new: &str, max_unchanged: usize) -> String {
+    let color = std::env::var_os("NO_COLOR").is_none() && yansi::is_enabled();
+    let diff = TextDiff::from_lines(old, new);
+    let changes: Vec<_> = diff.iter_all_changes().collect();
+    let mut lines = vec![];
+    let mut i = 0;
+    while i < changes.len() {
+        let run = changes[i..]
+            .iter()
+            .take_while(|change| change.tag() == ChangeTag::Equal)
+            .count();
+        if run == 0 {
+            let change = &changes[i];
+            let line = change.value().trim_end_matches(['\r', '\n']);
+            lines.push(match (change.tag(), color) {
+                (ChangeTag::Delete, true) => {
+                    format!("{}", yansi::Paint::red(&format!("-{}", line)))
+                }
+                (ChangeTag::Delete, false) => format!("-{}", line),
+                (_, true) => format!("{}", yansi::Paint::green(&format!("+{}", line))),
+                (_, false) => format!("+{}", line),
+            });
+            i += 1;
+            continue;
+        }
+        let (before, after) = (i > 0, i + run < changes.len());
+        let (head, tail) = match (before, after) {
+            _ if run <= max_unchanged => (run, 0),
+            (true, true) => (max_unchanged.div_ceil(2), max_unchanged / 2),
+            (true, false) => (max_unchanged, 0),
+            _ => (0, max_unchanged),
+        };
+        let equal = |change: &similar::Change<&str>| {
+            format!(" {}", change.value().trim_end_matches(['\r', '\n']))
+        };
+        lines.extend(changes[i..i + head].iter().map(equal));
+        if head + tail < run {
+            lines.push(format!("... ({} identical lines) ...", run - head - tail));
+        }
+        lines.extend(changes[i + run - tail..i + run].iter().map(equal));
+        i += run;
+    }
+    lines.join("\n")
+}
+
+/// The width to render text diffs side by side at, if
+/// `GOLDENFILE_DIFF_STYLE=side-by-side`. Uses `COLUMNS` if set, and
+/// otherwise 160.
+fn side_by_side_env_width() -> Option<usize> {
+    if std::env::var("GOLDENFILE_DIFF_STYLE").ok()? != "side-by-side" {
+        return None;
+    }
+    let columns = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok());
+    Some(columns.unwrap_or(160))
+}
+
+/// Render a diff as two columns fitting in `width`, with a marker between
+/// them: `|` for a changed line, `<` for a deleted one and `>` for an
+/// inserted one. Lines too long for their column are cut off with `…`.
+/// Returns `None` if `width` is too narrow to be readable.
+fn render_side_by_side(old: &str, new: &str, width: usize) -> Option<String> {
+    let column = width.checked_sub(3)? / 2;
+    if column < 8 {
+        return None;
+    }
+    let color = std::env::var_os("NO_COLOR").is_none() && yansi::is_enabled();
+    // Cells are split into text and padding so only the text is colored.
+    let cell = |line: Option<&str>| {
+        let line = line.unwrap_or("").trim_end_matches(['\r', '\n']);
+        let mut chars: Vec<char> = line.chars().collect();
+        if chars.len() > column {
+            chars.truncate(column - 1);
+            chars.push('…');
+        }
+        let padding = " ".repeat(column - chars.len());
+        (chars.into_iter().collect::<String>(), padding)
+    };
+
+    let diff = TextDiff::from_lines(old, new);
+    let (old_lines, new_lines) = (diff.old_slices(), diff.new_slices());
+    let mut rows = vec![];
+    for op in diff.ops() {
+        let (old_range, new_range) = (op.old_range(), op.new_range());
+        let equal = op.tag() == similar::DiffTag::Equal;
+        for i in 0..old_range.len().max(new_range.len()) {
+            let old_line = (i < old_range.len()).then(|| old_lines[old_range.start + i]);
+            let new_line = (i < new_range.len()).then(|| new_lines[new_range.start + i]);
+            let marker = match (old_line, new_line) {
+                _ if equal => ' ',
+                (Some(_), Some(_)) => '|',
+                (Some(_), None) => '<',
+                _ => '>',
+            };
+            let ((old_text, old_padding), (new_text, new_padding)) =
+                (cell(old_line), cell(new_line));
+            let row = if color && !equal {
+                use yansi::Paint;
+                format!(
+                    "{}{} {} {}{}",
+                    old_text.red(),
+                    old_padding,
+                    marker,
+                    new_text.green(),
+                    new_padding
+                )
+            } else {
+                format!(
+                    "{}{} {} {}{}",
+                    old_text, old_padding, marker, new_text, new_padding
+                )
+            };
+            rows.push(row.trim_end().to_string());
+        }
+    }
+    Some(rows.join("\n"))
+}
+
+/// If exactly one line changed, show it before and after with carets under
+/// the columns that differ.
+fn changed_columns(old: &str, new: &str) -> Option<String> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut changes = diff
+        .iter_all_changes()
+        .filter(|change| change.tag() != ChangeTag::Equal);
+    let (old_line, new_line) = match (changes.next(), changes.next(), changes.next()) {
+        (Some(deleted), Some(inserted), None)
+            if deleted.tag() == ChangeTag::Delete && inserted.tag() == ChangeTag::Insert =>
+        {
+            (
+                deleted.value().trim_end_matches(['\r', '\n']),
+                inserted.value().trim_end_matches(['\r', '\n']),
+            )
+        }
+        _ => return None,
+    };
+    if old_line == new_line {
+        return None;
+    }
+
+    let old_chars: Vec<char> = old_line.chars().collect();
+    let new_chars: Vec<char> = new_line.chars().collect();
+    let prefix = old_chars
+        .iter()
+        .zip(&new_chars)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_chars[prefix..]
+        .iter()
+        .rev()
+        .zip(new_chars[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let width = (old_chars.len().max(new_chars.len()) - prefix - suffix).max(1);
+    // Keep tabs in the padding so the carets line up however tabs render.
+    let padding: String = new_chars[..prefix]
+        .iter()
+        .map(|&c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    Some(format!(
+        "  old: {}\n  new: {}\n       {}{}",
+        old_line,
+        new_line,
+        padding,
+        "^".repeat(width)
+    ))
+}
+
+fn file_byte_iter(path: &Path) -> Result<io::Bytes<BufReader<fs::File>>, DiffIoError> {
+    Ok(BufReader::new(check_io(fs::File::open(path), path)?).bytes())
+}
+
+fn next_byte(
+    bytes: &mut io::Bytes<BufReader<fs::File>>,
+    path: &Path,
+) -> Result<Option<u8>, DiffIoError> {
+    bytes.next().transpose().map_err(|error| DiffIoError {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+fn file_len(path: &Path) -> Result<u64, DiffIoError> {
+    Ok(check_io(fs::metadata(path), path)?.len())
+}
+
+/// A differ the Mint can run: a built-in one returning its outcome, or a
+/// custom one that panics on a mismatch.
+pub(crate) enum AnyDiffer {
+    Outcome(OutcomeDiffer),
+    Custom(Differ),
+}
+
+impl AnyDiffer {
+    /// Compare two files, catching a panic only from a custom differ.
+    pub(crate) fn run(&self, old: &Path, new: &Path) -> Result<(), Failure> {
+        match self {
+            AnyDiffer::Outcome(differ) => match differ(old, new) {
+                DiffOutcome::Match => Ok(()),
+                DiffOutcome::Mismatch(message) => Err(Failure::Changed(message)),
+                DiffOutcome::Error(error) => Err(Failure::Io(error)),
+            },
+            AnyDiffer::Custom(differ) => {
+                panic::catch_unwind(panic::AssertUnwindSafe(|| differ(old, new)))
+                    .map_err(Failure::from_payload)
+            }
+        }
     }
+}
 
-    let first_difference = file_byte_iter(old)
-        .zip(file_byte_iter(new))
-        .position(|(old_byte, new_byte)| old_byte != new_byte);
+/// Why two files failed to compare equal.
+pub(crate) enum Failure {
+    /// The files differ, as described by the message.
+    Changed(String),
+    /// A file could not be read.
+    Io(DiffIoError),
+    /// A custom differ panicked, already printing its message.
+    Panic(Box<dyn Any + Send>),
+}
 
-    if let Some(position) = first_difference {
-        panic!("{}: Files differ at byte {}", old.display(), position + 1);
+impl Failure {
+    /// The failure a custom differ panicked with: an IO error if it raised a
+    /// [`DiffIoError`], and otherwise the panic.
+    fn from_payload(payload: Box<dyn Any + Send>) -> Failure {
+        match payload.downcast::<DiffIoError>() {
+            Ok(error) => Failure::Io(*error),
+            Err(payload) => Failure::Panic(payload),
+        }
     }
+
+    /// The failure's message, as it would be panicked with.
+    pub(crate) fn message(&self) -> String {
+        match self {
+            Failure::Changed(message) => message.clone(),
+            Failure::Io(error) => error.to_string(),
+            Failure::Panic(payload) => panic_message(&**payload),
+        }
+    }
+
+    /// Print the failure's message, as the panic hook already has for a
+    /// custom differ's panic.
+    pub(crate) fn print(&self) {
+        if !matches!(self, Failure::Panic(_)) {
+            eprintln!("{}", self.message());
+        }
+    }
+
+    /// Panic with the failure, resuming a custom differ's panic as it was.
+    pub(crate) fn raise(self) -> ! {
+        match self {
+            Failure::Changed(message) => panic!("{}", message),
+            Failure::Io(error) => panic!("{}", error),
+            Failure::Panic(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+/// The outcome of a comparison, resuming a custom differ's panic.
+pub(crate) fn into_outcome(result: Result<(), Failure>) -> DiffOutcome {
+    match result {
+        Ok(()) => DiffOutcome::Match,
+        Err(Failure::Changed(message)) => DiffOutcome::Mismatch(message),
+        Err(Failure::Io(error)) => DiffOutcome::Error(error),
+        Err(Failure::Panic(payload)) => panic::resume_unwind(payload),
+    }
+}
+
+/// Extract the message from a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    panic_text(payload).unwrap_or_else(|| "differ panicked".to_string())
 }
 
-fn open_file(path: &Path) -> fs::File {
-    check_io(fs::File::open(path), "opening file", path)
+/// The message of a panic payload that is a string.
+fn panic_text(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        Some(message.to_string())
+    } else {
+        payload.downcast_ref::<String>().cloned()
+    }
 }
 
-fn file_byte_iter(path: &Path) -> impl Iterator<Item = u8> + '_ {
-    BufReader::new(open_file(path))
-        .bytes()
-        .map(move |b| check_io(b, "reading file", path))
+/// Run `differ` on copies of both files with the given transforms applied to
+/// their contents. Missing files stay missing, and failures name the original
+/// files rather than the copies.
+pub(crate) fn diff_transformed(
+    old: &Path,
+    new: &Path,
+    differ: &AnyDiffer,
+    transform_old: &dyn Fn(Vec<u8>) -> Vec<u8>,
+    transform_new: &dyn Fn(Vec<u8>) -> Vec<u8>,
+) -> Result<(), Failure> {
+    let dir = check_io(tempfile::tempdir(), old).map_err(Failure::Io)?;
+    let name = old.file_name().unwrap_or_default();
+    let copy = |side: &str, path: &Path, transform: &dyn Fn(Vec<u8>) -> Vec<u8>| {
+        let copy = dir.path().join(side).join(name);
+        check_io(fs::create_dir_all(copy.parent().unwrap()), &copy)?;
+        if path.exists() {
+            let contents = read_file(path)?;
+            check_io(fs::write(&copy, transform(contents)), &copy)?;
+        }
+        Ok(copy)
+    };
+    let (old_copy, new_copy) = (
+        copy("old", old, transform_old).map_err(Failure::Io)?,
+        copy("new", new, transform_new).map_err(Failure::Io)?,
+    );
+    diff_renamed(&old_copy, &new_copy, old, new, differ)
 }
 
-fn file_len(path: &Path) -> u64 {
-    check_io(fs::metadata(path), "getting file length", path).len()
+/// Run `differ` on `old_copy` and `new_copy`, naming `old` and `new` instead
+/// in any failure. A custom differ's panic is only renamed in the payload it is
+/// resumed with, since the panic hook has already printed it.
+pub(crate) fn diff_renamed(
+    old_copy: &Path,
+    new_copy: &Path,
+    old: &Path,
+    new: &Path,
+    differ: &AnyDiffer,
+) -> Result<(), Failure> {
+    let original = |path: PathBuf| match path {
+        path if path == old_copy => old.to_path_buf(),
+        path if path == new_copy => new.to_path_buf(),
+        path => path,
+    };
+    let rename = |message: &str| {
+        message
+            .replace(&old_copy.display().to_string(), &old.display().to_string())
+            .replace(&new_copy.display().to_string(), &new.display().to_string())
+    };
+    let Err(failure) = differ.run(old_copy, new_copy) else {
+        return Ok(());
+    };
+    Err(match failure {
+        Failure::Changed(message) => Failure::Changed(rename(&message)),
+        Failure::Io(error) => Failure::Io(DiffIoError {
+            path: original(error.path),
+            error: error.error,
+        }),
+        Failure::Panic(payload) => match panic_text(&*payload) {
+            Some(message) => Failure::Panic(Box::new(rename(&message))),
+            None => Failure::Panic(payload),
+        },
+    })
 }
 
-fn check_io<T>(x: Result<T, io::Error>, message: &str, path: &Path) -> T {
-    x.unwrap_or_else(|err| panic!("Error {} {:?}: {:?}", message, path, err))
+/// Attach `path` to an IO error.
+fn check_io<T>(x: Result<T, io::Error>, path: &Path) -> Result<T, DiffIoError> {
+    x.map_err(|error| DiffIoError {
+        path: path.to_path_buf(),
+        error,
+    })
 }
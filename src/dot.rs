@@ -0,0 +1,357 @@
+//! A small Graphviz DOT parser used to compare graphs by structure.
+
+use std::collections::BTreeMap;
+
+type Attrs = BTreeMap<String, String>;
+
+/// A parsed graph, with declarations merged and ordered.
+///
+/// Attribute statements (`graph [..]`, `node [..]`, `edge [..]` and `key=value`)
+/// are recorded as written rather than applied to the nodes and edges they
+/// affect. Subgraphs only group their contents.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Graph {
+    /// `graph` or `digraph`, with `strict` if given.
+    kind: String,
+    /// Attribute statements, keyed by `graph`, `node` or `edge` and name.
+    attributes: BTreeMap<(String, String), String>,
+    nodes: BTreeMap<String, Attrs>,
+    edges: Vec<(String, String, Attrs)>,
+}
+
+impl Graph {
+    /// One line per kind, attribute, node and edge, so graphs can be compared
+    /// as sets of lines.
+    pub(crate) fn describe(&self) -> Vec<String> {
+        let op = if self.kind.ends_with("digraph") {
+            "->"
+        } else {
+            "--"
+        };
+        let mut lines = vec![self.kind.clone()];
+        for ((scope, key), value) in &self.attributes {
+            lines.push(format!("{} [{}=\"{}\"]", scope, key, value));
+        }
+        for (node, attrs) in &self.nodes {
+            lines.push(format!("{}{}", node, describe_attrs(attrs)));
+        }
+        let mut edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|(from, to, attrs)| format!("{} {} {}{}", from, op, to, describe_attrs(attrs)))
+            .collect();
+        edges.sort();
+        lines.extend(edges);
+        lines
+    }
+}
+
+fn describe_attrs(attrs: &Attrs) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+    let attrs: Vec<String> = attrs
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, value))
+        .collect();
+    format!(" [{}]", attrs.join(", "))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Id(String),
+    Punct(char),
+    Edge,
+}
+
+/// Parse a DOT document containing a single graph.
+///
+/// Quoted and unquoted IDs are equivalent, HTML-like labels keep their angle
+/// brackets, and comments are ignored. Ports stay part of edge endpoints.
+/// Undirected edges are stored with their endpoints in sorted order.
+pub(crate) fn parse(text: &str) -> Result<Graph, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        graph: Graph {
+            kind: String::new(),
+            attributes: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            edges: vec![],
+        },
+    };
+    let mut kind = vec![];
+    if parser.keyword("strict") {
+        kind.push("strict");
+    }
+    if parser.keyword("graph") {
+        kind.push("graph");
+    } else if parser.keyword("digraph") {
+        kind.push("digraph");
+    } else {
+        return Err("expected `graph` or `digraph`".to_string());
+    }
+    parser.graph.kind = kind.join(" ");
+    if let Some(Token::Id(_)) = parser.peek() {
+        parser.pos += 1;
+    }
+    parser.expect('{')?;
+    parser.statements()?;
+    if parser.pos < parser.tokens.len() {
+        return Err("trailing tokens after graph".to_string());
+    }
+    Ok(parser.graph)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    let mut line_start = true;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' => line_start = true,
+            c if c.is_whitespace() => {}
+            '#' if line_start => while chars.next_if(|&(_, c)| c != '\n').is_some() {},
+            '/' if text[i..].starts_with("//") => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '/' if text[i..].starts_with("/*") => {
+                chars.next();
+                let mut previous = ' ';
+                loop {
+                    match chars.next() {
+                        Some((_, '/')) if previous == '*' => break,
+                        Some((_, c)) => previous = c,
+                        None => return Err("unterminated comment".to_string()),
+                    }
+                }
+            }
+            '-' if text[i..].starts_with("->") || text[i..].starts_with("--") => {
+                chars.next();
+                tokens.push(Token::Edge);
+            }
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) if chars.peek().map(|&(_, c)| c) == Some('"') => {
+                            chars.next();
+                            id.push('"');
+                        }
+                        Some((_, '\\')) if chars.peek().map(|&(_, c)| c) == Some('\n') => {
+                            chars.next();
+                        }
+                        Some((_, c)) => id.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                // `"a" + "b"` concatenates quoted strings.
+                if let (Some(Token::Punct('+')), Some(Token::Id(previous))) =
+                    (tokens.last().cloned(), tokens.iter().rev().nth(1))
+                {
+                    let joined = format!("{}{}", previous, id);
+                    tokens.truncate(tokens.len() - 2);
+                    tokens.push(Token::Id(joined));
+                } else {
+                    tokens.push(Token::Id(id));
+                }
+            }
+            '<' => {
+                let mut id = String::from('<');
+                let mut depth = 1;
+                while depth > 0 {
+                    let (_, c) = chars.next().ok_or("unterminated HTML string")?;
+                    depth += match c {
+                        '<' => 1,
+                        '>' => -1,
+                        _ => 0,
+                    };
+                    id.push(c);
+                }
+                tokens.push(Token::Id(id));
+            }
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' | '+' => tokens.push(Token::Punct(c)),
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = String::from(c);
+                while let Some((_, c)) =
+                    chars.next_if(|&(_, c)| c.is_alphanumeric() || c == '_' || c == '.')
+                {
+                    id.push(c);
+                }
+                tokens.push(Token::Id(id));
+            }
+            c => return Err(format!("unexpected character {:?}", c)),
+        }
+        if c != '\n' && !c.is_whitespace() {
+            line_start = false;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    graph: Graph,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn punct(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.punct(c) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}`, found {}", c, self.describe_next()))
+        }
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        match self.peek() {
+            Some(Token::Id(id)) if id.eq_ignore_ascii_case(word) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn id(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Id(id)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Ok(id)
+            }
+            _ => Err(format!("expected ID, found {}", self.describe_next())),
+        }
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(Token::Id(id)) => format!("{:?}", id),
+            Some(Token::Punct(c)) => format!("`{}`", c),
+            Some(Token::Edge) => "edge operator".to_string(),
+            None => "end of input".to_string(),
+        }
+    }
+
+    /// Parse statements up to and including the closing `}`, returning the
+    /// nodes they mention.
+    fn statements(&mut self) -> Result<Vec<String>, String> {
+        let mut mentioned = vec![];
+        while !self.punct('}') {
+            if self.peek().is_none() {
+                return Err("expected `}`, found end of input".to_string());
+            }
+            mentioned.extend(self.statement()?);
+            while self.punct(';') || self.punct(',') {}
+        }
+        Ok(mentioned)
+    }
+
+    fn statement(&mut self) -> Result<Vec<String>, String> {
+        for scope in ["graph", "node", "edge"] {
+            if self.tokens.get(self.pos + 1) == Some(&Token::Punct('[')) && self.keyword(scope) {
+                for (key, value) in self.attr_lists()? {
+                    self.graph
+                        .attributes
+                        .insert((scope.to_string(), key), value);
+                }
+                return Ok(vec![]);
+            }
+        }
+        if let (Some(Token::Id(key)), Some(Token::Punct('='))) =
+            (self.peek().cloned(), self.tokens.get(self.pos + 1))
+        {
+            self.pos += 2;
+            let value = self.id()?;
+            self.graph
+                .attributes
+                .insert(("graph".to_string(), key), value);
+            return Ok(vec![]);
+        }
+
+        let mut endpoints = vec![self.endpoint()?];
+        while self.peek() == Some(&Token::Edge) {
+            self.pos += 1;
+            endpoints.push(self.endpoint()?);
+        }
+        let attrs = self.attr_lists()?;
+        let mentioned: Vec<String> = endpoints.iter().flatten().cloned().collect();
+        if endpoints.len() == 1 {
+            if let [node] = &endpoints[0][..] {
+                let node = node.split(':').next().unwrap().to_string();
+                self.graph.nodes.entry(node).or_default().extend(attrs);
+            }
+            return Ok(mentioned);
+        }
+        let directed = self.graph.kind.ends_with("digraph");
+        for pair in endpoints.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    let (from, to) = if directed || from <= to {
+                        (from, to)
+                    } else {
+                        (to, from)
+                    };
+                    self.graph
+                        .edges
+                        .push((from.clone(), to.clone(), attrs.clone()));
+                }
+            }
+        }
+        for node in &mentioned {
+            let node = node.split(':').next().unwrap().to_string();
+            self.graph.nodes.entry(node).or_default();
+        }
+        Ok(mentioned)
+    }
+
+    /// Parse a node ID with an optional port, or a subgraph, returning the
+    /// nodes it stands for.
+    fn endpoint(&mut self) -> Result<Vec<String>, String> {
+        if self.keyword("subgraph") {
+            if let Some(Token::Id(_)) = self.peek() {
+                self.pos += 1;
+            }
+            self.expect('{')?;
+            return self.statements();
+        }
+        if self.punct('{') {
+            return self.statements();
+        }
+        let mut node = self.id()?;
+        while self.punct(':') {
+            node.push(':');
+            node.push_str(&self.id()?);
+        }
+        Ok(vec![node])
+    }
+
+    /// Parse zero or more `[key=value, ...]` lists.
+    fn attr_lists(&mut self) -> Result<Attrs, String> {
+        let mut attrs = Attrs::new();
+        while self.punct('[') {
+            while !self.punct(']') {
+                let key = self.id()?;
+                self.expect('=')?;
+                attrs.insert(key, self.id()?);
+                while self.punct(';') || self.punct(',') {}
+            }
+        }
+        Ok(attrs)
+    }
+}
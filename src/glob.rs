@@ -0,0 +1,53 @@
+//! Matching relative paths against glob patterns.
+
+use std::path::Path;
+
+/// Whether `path` matches the glob `pattern`.
+///
+/// `*` matches any run of characters other than `/`, `?` matches one such
+/// character, and a `**` segment matches any number of directories. A pattern
+/// without a `/` matches any single component of the path, so `*.log` matches
+/// `a/b.log` and `target` matches everything under a `target` directory.
+/// Other patterns are matched against the whole path.
+pub(crate) fn matches(pattern: &str, path: &Path) -> bool {
+    let components: Vec<String> = path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let components: Vec<&str> = components.iter().map(String::as_str).collect();
+    if !pattern.contains('/') {
+        return components.iter().any(|c| matches_segment(pattern, c));
+    }
+    let segments: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+    matches_segments(&segments, &components)
+}
+
+fn matches_segments(segments: &[&str], components: &[&str]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=components.len()).any(|skip| matches_segments(rest, &components[skip..]))
+        }
+        Some((segment, rest)) => match components.split_first() {
+            Some((component, components)) => {
+                matches_segment(segment, component) && matches_segments(rest, components)
+            }
+            None => false,
+        },
+    }
+}
+
+fn matches_segment(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    wildcard(&pattern, &text)
+}
+
+fn wildcard(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| wildcard(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && wildcard(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && wildcard(rest, &text[1..]),
+    }
+}
@@ -0,0 +1,73 @@
+//! Per-goldenfile tolerances read from `.goldencfg` sidecar files.
+//!
+//! A sidecar is a small subset of TOML: `key = value` lines, with `#`
+//! comments and blank lines ignored. Supported keys for text goldenfiles are:
+//!
+//! - `epsilon`: compare with [`numeric_tolerant_diff`] and this tolerance.
+//! - `max_changed_fraction`: compare with [`fuzzy_line_diff`] and this
+//!   threshold.
+//!
+//! and for binary goldenfiles:
+//!
+//! - `max_changed_bytes`: compare with [`changed_bytes_diff`] and this count.
+//! - `max_mean_byte_diff`: compare with [`mean_byte_diff`] and this tolerance.
+//!
+//! At most one key may be set.
+
+use std::path::{Path, PathBuf};
+
+use crate::differs::*;
+
+/// The sidecar configuring the goldenfile whose old contents are at `old`.
+pub(crate) fn sidecar_path(old: &Path) -> PathBuf {
+    let mut path = old.as_os_str().to_owned();
+    path.push(".goldencfg");
+    PathBuf::from(path)
+}
+
+/// Parse a sidecar into the differ it configures for a text or, if `binary`, a
+/// binary goldenfile.
+pub(crate) fn parse(text: &str, binary: bool) -> Result<OutcomeDiffer, String> {
+    let mut differ = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", i + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+        let number: f64 = value
+            .parse()
+            .map_err(|_| format!("line {}: `{}` must be a number", i + 1, key))?;
+        if differ.is_some() {
+            return Err(format!("line {}: only one key may be set", i + 1));
+        }
+        let kind = if binary { "binary" } else { "text" };
+        differ = Some(match (key, binary) {
+            ("epsilon", false) => numeric_tolerant_outcome(number),
+            ("max_changed_fraction", false) => fuzzy_line_outcome(number),
+            ("max_changed_bytes", true) if number >= 0.0 && number.fract() == 0.0 => {
+                changed_bytes_outcome(number as usize)
+            }
+            ("max_changed_bytes", true) => {
+                return Err(format!("line {}: `{}` must be a whole number", i + 1, key))
+            }
+            ("max_mean_byte_diff", true) => mean_byte_outcome(number),
+            (
+                "epsilon" | "max_changed_fraction" | "max_changed_bytes" | "max_mean_byte_diff",
+                _,
+            ) => {
+                return Err(format!(
+                    "line {}: `{}` doesn't apply to {} goldenfiles",
+                    i + 1,
+                    key,
+                    kind
+                ))
+            }
+            _ => return Err(format!("line {}: unknown key `{}`", i + 1, key)),
+        });
+    }
+    differ.ok_or_else(|| "no keys set".to_string())
+}
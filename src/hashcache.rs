@@ -0,0 +1,92 @@
+//! A manifest of goldenfiles known to match, used to skip their differs.
+//!
+//! Each line of the manifest is `<hash> <mtime> <path>`: the FNV-1a hash of new
+//! contents that matched the goldenfile at `path`, and the goldenfile's
+//! modification time in nanoseconds when they did.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub(crate) struct HashCache {
+    manifest: PathBuf,
+    entries: RefCell<BTreeMap<PathBuf, (u64, u128)>>,
+    dirty: Cell<bool>,
+}
+
+impl HashCache {
+    /// Load the manifest at `manifest`, which may not exist yet. Lines that
+    /// can't be parsed are dropped.
+    pub(crate) fn load(manifest: &Path) -> io::Result<HashCache> {
+        let text = match fs::read_to_string(manifest) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            result => result?,
+        };
+        let entries = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+                let mtime = fields.next()?.parse().ok()?;
+                Some((PathBuf::from(fields.next()?), (hash, mtime)))
+            })
+            .collect();
+        Ok(HashCache {
+            manifest: manifest.to_path_buf(),
+            entries: RefCell::new(entries),
+            dirty: Cell::new(false),
+        })
+    }
+
+    /// The current hash of `new` and modification time of `old`, if both can
+    /// be read.
+    fn key(old: &Path, new: &Path) -> Option<(u64, u128)> {
+        let mtime = fs::metadata(old).ok()?.modified().ok()?;
+        let mtime = mtime.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+        Some((fnv1a(&fs::read(new).ok()?), mtime))
+    }
+
+    /// Whether `new` is known to match the goldenfile at `old`, which hasn't
+    /// been modified since.
+    pub(crate) fn is_current(&self, old: &Path, new: &Path) -> bool {
+        let entry = self.entries.borrow().get(old).copied();
+        entry.is_some() && entry == Self::key(old, new)
+    }
+
+    /// Record that `new` matches the goldenfile at `old`.
+    pub(crate) fn record(&self, old: &Path, new: &Path) {
+        if let Some(key) = Self::key(old, new) {
+            let previous = self.entries.borrow_mut().insert(old.to_path_buf(), key);
+            if previous != Some(key) {
+                self.dirty.set(true);
+            }
+        }
+    }
+
+    /// Write the manifest if any entries changed.
+    pub(crate) fn save(&self) -> io::Result<()> {
+        if !self.dirty.get() {
+            return Ok(());
+        }
+        let mut text = String::new();
+        for (path, (hash, mtime)) in self.entries.borrow().iter() {
+            text.push_str(&format!("{:016x} {} {}\n", hash, mtime, path.display()));
+        }
+        if let Some(parent) = self.manifest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.manifest, text)?;
+        self.dirty.set(false);
+        Ok(())
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
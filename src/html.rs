@@ -0,0 +1,313 @@
+//! A small, forgiving HTML parser used to compare documents by DOM structure.
+
+use std::fmt;
+
+/// A normalized DOM node.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Node {
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+    Text(String),
+}
+
+impl fmt::Display for Node {
+    /// Describe a node without its children.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Element { name, attrs, .. } => {
+                write!(f, "<{}", name)?;
+                for (key, value) in attrs {
+                    write!(f, " {}={:?}", key, value)?;
+                }
+                write!(f, ">")
+            }
+            Node::Text(text) => write!(f, "{:?}", text),
+        }
+    }
+}
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Elements whose text keeps its whitespace.
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea"];
+
+/// An open element's name, attributes and children so far.
+type OpenElement = (String, Vec<(String, String)>, Vec<Node>);
+
+/// Parse an HTML document into a list of top-level nodes.
+///
+/// Tag and attribute names are lowercased, attributes are sorted by name,
+/// character references are decoded, runs of whitespace in text are collapsed
+/// to a single space, and whitespace-only text, comments and doctypes are
+/// dropped. Text inside `<pre>` and `<textarea>` keeps its whitespace, apart
+/// from a newline straight after the start tag, as browsers render it.
+/// Unclosed elements are closed when their parent closes.
+pub(crate) fn parse(text: &str) -> Vec<Node> {
+    let mut stack: Vec<OpenElement> = vec![(String::new(), vec![], vec![])];
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            let name = after[..end].trim().to_ascii_lowercase();
+            rest = after.get(end + 1..).unwrap_or("");
+            if stack.iter().skip(1).any(|(open, _, _)| *open == name) {
+                loop {
+                    let done = stack.last().unwrap().0 == name;
+                    close(&mut stack);
+                    if done {
+                        break;
+                    }
+                }
+            }
+        } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            let (name, attrs, self_closing, after) = parse_tag(&rest[1..]);
+            rest = after;
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) || name == "textarea" {
+                let closing = format!("</{}", name);
+                let end = rest
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .unwrap_or(rest.len());
+                let mut children = vec![];
+                if name == "textarea" {
+                    let text = decode_entities(&rest[..end]);
+                    push_preformatted(&mut children, strip_leading_newline(&text));
+                } else {
+                    push_text(&mut children, &rest[..end]);
+                }
+                rest = rest[end..].find('>').map_or("", |i| &rest[end + i + 1..]);
+                add_child(&mut stack, name, attrs, children);
+            } else if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+                add_child(&mut stack, name, attrs, vec![]);
+            } else {
+                stack.push((name, attrs, vec![]));
+            }
+        } else {
+            let first = rest.chars().next().unwrap().len_utf8();
+            let end = rest[first..].find('<').map_or(rest.len(), |i| i + first);
+            let text = decode_entities(&rest[..end]);
+            rest = &rest[end..];
+            let preformatted = stack
+                .iter()
+                .any(|(name, _, _)| PREFORMATTED_ELEMENTS.contains(&name.as_str()));
+            let (name, _, children) = stack.last_mut().unwrap();
+            if !preformatted {
+                push_text(children, &text);
+            } else if name == "pre" && children.is_empty() {
+                push_preformatted(children, strip_leading_newline(&text));
+            } else {
+                push_preformatted(children, &text);
+            }
+        }
+    }
+
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+    stack.pop().unwrap().2
+}
+
+fn close(stack: &mut Vec<OpenElement>) {
+    let (name, attrs, children) = stack.pop().unwrap();
+    add_child(stack, name, attrs, children);
+}
+
+fn add_child(
+    stack: &mut [OpenElement],
+    name: String,
+    mut attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+) {
+    attrs.sort();
+    stack.last_mut().unwrap().2.push(Node::Element {
+        name,
+        attrs,
+        children,
+    });
+}
+
+fn push_text(children: &mut Vec<Node>, text: &str) {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return;
+    }
+    match children.last_mut() {
+        Some(Node::Text(previous)) => {
+            previous.push(' ');
+            previous.push_str(&text);
+        }
+        _ => children.push(Node::Text(text)),
+    }
+}
+
+/// Add text inside a preformatted element, keeping its whitespace.
+fn push_preformatted(children: &mut Vec<Node>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match children.last_mut() {
+        Some(Node::Text(previous)) => previous.push_str(text),
+        _ => children.push(Node::Text(text.to_string())),
+    }
+}
+
+fn strip_leading_newline(text: &str) -> &str {
+    text.strip_prefix("\r\n")
+        .or_else(|| text.strip_prefix('\n'))
+        .unwrap_or(text)
+}
+
+/// Parse a start tag after its `<`, returning the name, attributes, whether it
+/// was self-closing and the remaining input.
+fn parse_tag(text: &str) -> (String, Vec<(String, String)>, bool, &str) {
+    let is_name_char = |c: char| !c.is_whitespace() && !"/>=".contains(c);
+    let name_end = text.find(|c| !is_name_char(c)).unwrap_or(text.len());
+    let name = text[..name_end].to_ascii_lowercase();
+    let mut rest = &text[name_end..];
+    let mut attrs = vec![];
+    let mut self_closing = false;
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('>') {
+            return (name, attrs, self_closing, after);
+        } else if let Some(after) = rest.strip_prefix('/') {
+            self_closing = true;
+            rest = after;
+            continue;
+        } else if rest.is_empty() {
+            return (name, attrs, self_closing, rest);
+        }
+        self_closing = false;
+
+        let key_end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len()).max(1);
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            if let Some(quote) = after.chars().next().filter(|&c| c == '"' || c == '\'') {
+                let body = &after[1..];
+                let end = body.find(quote).unwrap_or(body.len());
+                value = decode_entities(&body[..end]);
+                rest = body.get(end + 1..).unwrap_or("");
+            } else {
+                let end = after
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .unwrap_or(after.len());
+                value = decode_entities(&after[..end]);
+                rest = &after[end..];
+            }
+        }
+        attrs.retain(|(k, _)| *k != key);
+        attrs.push((key, value));
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse().ok()))
+                    .flatten()
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Find the first difference between two node lists, returning its path and
+/// descriptions of the old and new nodes there.
+pub(crate) fn first_difference(
+    old: &[Node],
+    new: &[Node],
+    path: &str,
+) -> Option<(String, String, String)> {
+    let describe = |node: Option<&Node>| node.map_or("(nothing)".to_string(), |n| n.to_string());
+    for i in 0..old.len().max(new.len()) {
+        let (o, n) = (old.get(i), new.get(i));
+        let child_path = child_path(
+            old.get(i).or(new.get(i)).unwrap(),
+            &old[..i.min(old.len())],
+            path,
+        );
+        match (o, n) {
+            (
+                Some(Node::Element {
+                    name: old_name,
+                    attrs: old_attrs,
+                    children: old_children,
+                }),
+                Some(Node::Element {
+                    name: new_name,
+                    attrs: new_attrs,
+                    children: new_children,
+                }),
+            ) if old_name == new_name && old_attrs == new_attrs => {
+                if let Some(difference) = first_difference(old_children, new_children, &child_path)
+                {
+                    return Some(difference);
+                }
+            }
+            _ if o == n => {}
+            _ => return Some((child_path, describe(o), describe(n))),
+        }
+    }
+    None
+}
+
+/// An XPath-like path to `node`, given the siblings that precede it.
+fn child_path(node: &Node, preceding: &[Node], parent: &str) -> String {
+    let same = |other: &&Node| match (node, other) {
+        (Node::Element { name, .. }, Node::Element { name: other, .. }) => name == other,
+        (Node::Text(_), Node::Text(_)) => true,
+        _ => false,
+    };
+    let index = preceding.iter().filter(same).count() + 1;
+    match node {
+        Node::Element { name, .. } => format!("{}/{}[{}]", parent, name, index),
+        Node::Text(_) => format!("{}/text()[{}]", parent, index),
+    }
+}
@@ -0,0 +1,269 @@
+//! A small subset of jq filters, used to project JSON before comparing it.
+
+use crate::json::Value;
+
+/// A parsed filter.
+///
+/// Supports `.`, `.key`, `."key"`, `.[index]` (negative from the end),
+/// `.["key"]`, `.[]`, `|`, `,`, parentheses and the `keys` and `length`
+/// builtins.
+#[derive(Debug)]
+pub(crate) enum Filter {
+    Identity,
+    Field(Box<Filter>, String),
+    Index(Box<Filter>, i64),
+    Iterate(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Keys,
+    Length,
+}
+
+impl Filter {
+    pub(crate) fn parse(text: &str) -> Result<Filter, String> {
+        let mut parser = Parser { text, pos: 0 };
+        let filter = parser.pipe()?;
+        parser.skip_whitespace();
+        if parser.pos < text.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(filter)
+    }
+
+    /// Run the filter on `input`, returning every value it produces.
+    pub(crate) fn run(&self, input: &Value) -> Result<Vec<Value>, String> {
+        match self {
+            Filter::Identity => Ok(vec![input.clone()]),
+            Filter::Field(inner, key) => each(inner, input, |value| match value {
+                Value::Object(_) => Ok(vec![value.get(key).cloned().unwrap_or(Value::Null)]),
+                Value::Null => Ok(vec![Value::Null]),
+                _ => Err(format!("cannot index {} with {:?}", type_name(value), key)),
+            }),
+            Filter::Index(inner, index) => each(inner, input, |value| match value {
+                Value::Array(items) => {
+                    let i = if *index < 0 {
+                        items.len() as i64 + index
+                    } else {
+                        *index
+                    };
+                    let item = usize::try_from(i).ok().and_then(|i| items.get(i));
+                    Ok(vec![item.cloned().unwrap_or(Value::Null)])
+                }
+                Value::Null => Ok(vec![Value::Null]),
+                _ => Err(format!("cannot index {} with {}", type_name(value), index)),
+            }),
+            Filter::Iterate(inner) => each(inner, input, |value| match value {
+                Value::Array(items) => Ok(items.clone()),
+                Value::Object(entries) => Ok(entries.iter().map(|(_, v)| v.clone()).collect()),
+                _ => Err(format!("cannot iterate over {}", type_name(value))),
+            }),
+            Filter::Pipe(left, right) => each(left, input, |value| right.run(value)),
+            Filter::Comma(left, right) => {
+                let mut values = left.run(input)?;
+                values.extend(right.run(input)?);
+                Ok(values)
+            }
+            Filter::Keys => match input {
+                Value::Object(entries) => {
+                    let mut keys: Vec<&String> = entries.iter().map(|(k, _)| k).collect();
+                    keys.sort();
+                    let keys = keys.into_iter().map(|k| Value::String(k.clone()));
+                    Ok(vec![Value::Array(keys.collect())])
+                }
+                Value::Array(items) => {
+                    let indices = (0..items.len()).map(|i| Value::Number(i.to_string()));
+                    Ok(vec![Value::Array(indices.collect())])
+                }
+                _ => Err(format!("{} has no keys", type_name(input))),
+            },
+            Filter::Length => {
+                let length = match input {
+                    Value::Null => 0,
+                    Value::String(s) => s.chars().count(),
+                    Value::Array(items) => items.len(),
+                    Value::Object(entries) => entries.len(),
+                    Value::Number(n) => {
+                        return Ok(vec![Value::Number(n.trim_start_matches('-').to_string())])
+                    }
+                    Value::Bool(_) => return Err("boolean has no length".to_string()),
+                };
+                Ok(vec![Value::Number(length.to_string())])
+            }
+        }
+    }
+}
+
+/// Run `inner` on `input`, then `f` on each value it produces.
+fn each(
+    inner: &Filter,
+    input: &Value,
+    mut f: impl FnMut(&Value) -> Result<Vec<Value>, String>,
+) -> Result<Vec<Value>, String> {
+    let mut values = vec![];
+    for value in inner.run(input)? {
+        values.extend(f(&value)?);
+    }
+    Ok(values)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        if self.text[self.pos..].starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Filter, String> {
+        let mut filter = self.comma()?;
+        while self.eat("|") {
+            filter = Filter::Pipe(Box::new(filter), Box::new(self.comma()?));
+        }
+        Ok(filter)
+    }
+
+    fn comma(&mut self) -> Result<Filter, String> {
+        let mut filter = self.postfix()?;
+        while self.eat(",") {
+            filter = Filter::Comma(Box::new(filter), Box::new(self.postfix()?));
+        }
+        Ok(filter)
+    }
+
+    fn postfix(&mut self) -> Result<Filter, String> {
+        let mut filter = if self.eat("(") {
+            let filter = self.pipe()?;
+            if !self.eat(")") {
+                return Err(self.error("expected `)`"));
+            }
+            filter
+        } else if self.eat(".") {
+            self.suffix_after_dot(Filter::Identity, true)?
+        } else {
+            match self.ident().as_deref() {
+                Some("keys") => Filter::Keys,
+                Some("length") => Filter::Length,
+                Some(name) => return Err(format!("unsupported builtin `{}`", name)),
+                None => return Err(self.error("expected filter")),
+            }
+        };
+        loop {
+            if self.eat(".") {
+                filter = self.suffix_after_dot(filter, false)?;
+            } else if self.text[self.pos..].starts_with('[') {
+                filter = self.bracket(filter)?;
+            } else {
+                return Ok(filter);
+            }
+        }
+    }
+
+    /// Parse what follows a `.`, which may be nothing if it starts the filter.
+    fn suffix_after_dot(&mut self, filter: Filter, leading: bool) -> Result<Filter, String> {
+        if self.text[self.pos..].starts_with('"') {
+            let key = self.string()?;
+            Ok(Filter::Field(Box::new(filter), key))
+        } else if self.text[self.pos..].starts_with('[') {
+            self.bracket(filter)
+        } else if let Some(key) = self.ident() {
+            Ok(Filter::Field(Box::new(filter), key))
+        } else if leading {
+            Ok(filter)
+        } else {
+            Err(self.error("expected key after `.`"))
+        }
+    }
+
+    fn bracket(&mut self, filter: Filter) -> Result<Filter, String> {
+        self.pos += 1;
+        self.skip_whitespace();
+        let filter = if self.eat("]") {
+            return Ok(Filter::Iterate(Box::new(filter)));
+        } else if self.text[self.pos..].starts_with('"') {
+            Filter::Field(Box::new(filter), self.string()?)
+        } else {
+            let rest = &self.text[self.pos..];
+            let len = rest
+                .char_indices()
+                .find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+                .map_or(rest.len(), |(i, _)| i);
+            let index = rest[..len]
+                .parse()
+                .map_err(|_| self.error("expected index"))?;
+            self.pos += len;
+            Filter::Index(Box::new(filter), index)
+        };
+        if !self.eat("]") {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(filter)
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| !(c.is_alphabetic() || c == '_' || (i > 0 && c.is_ascii_digit())))
+            .map_or(rest.len(), |(i, _)| i);
+        if len == 0 {
+            return None;
+        }
+        self.pos += len;
+        Some(rest[..len].to_string())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = &self.text[self.pos..];
+        let mut end = 1;
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                end = i + 1;
+                break;
+            }
+        }
+        if end == 1 {
+            return Err(self.error("unterminated string"));
+        }
+        match crate::json::parse(&rest[..end]) {
+            Ok(Value::String(s)) => {
+                self.pos += end;
+                Ok(s)
+            }
+            _ => Err(self.error("invalid string")),
+        }
+    }
+}
@@ -0,0 +1,528 @@
+//! A minimal JSON parser used by the JSON differs.
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// A parsed JSON value.
+///
+/// Numbers keep their original token and objects keep their keys in document
+/// order, so values can be printed back the way they were written. Equality
+/// ignores both: see [`numbers_equal`] and the `PartialEq` impl.
+#[derive(Clone, Debug)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Look up a key in an object.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Remove object entries whose key is in `keys`, at every depth.
+    pub(crate) fn remove_keys(&mut self, keys: &HashSet<String>) {
+        match self {
+            Value::Object(entries) => {
+                entries.retain(|(key, _)| !keys.contains(key));
+                for (_, value) in entries {
+                    value.remove_keys(keys);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    item.remove_keys(keys);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ignore the entries `path` selects: remove matching object entries, and
+    /// replace matching array elements with `null` so later indices stay put.
+    pub(crate) fn ignore_path(&mut self, path: &[PathStep]) {
+        let Some((step, rest)) = path.split_first() else {
+            return;
+        };
+        match self {
+            Value::Object(entries) => {
+                let selected = |key: &str| match step {
+                    PathStep::Key(k) => k == key,
+                    PathStep::Any => true,
+                    PathStep::Index(_) => false,
+                };
+                if rest.is_empty() {
+                    entries.retain(|(key, _)| !selected(key));
+                } else {
+                    for (key, value) in entries {
+                        if selected(key) {
+                            value.ignore_path(rest);
+                        }
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    if matches!(step, PathStep::Any) || *step == PathStep::Index(i) {
+                        if rest.is_empty() {
+                            *item = Value::Null;
+                        } else {
+                            item.ignore_path(rest);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the value as JSON indented by two spaces, with object keys
+    /// sorted so the output doesn't depend on insertion order.
+    pub(crate) fn pretty(&self) -> String {
+        Pretty(self, 0).to_string()
+    }
+}
+
+/// A value written by [`Value::pretty`], nested `indent` levels deep.
+struct Pretty<'a>(&'a Value, usize);
+
+impl fmt::Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Pretty(value, indent) = *self;
+        let pad = |f: &mut fmt::Formatter, indent: usize| write!(f, "\n{}", "  ".repeat(indent));
+        match value {
+            Value::Array(items) if !items.is_empty() => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    pad(f, indent + 1)?;
+                    write!(f, "{}", Pretty(item, indent + 1))?;
+                }
+                pad(f, indent)?;
+                write!(f, "]")
+            }
+            Value::Object(entries) if !entries.is_empty() => {
+                let mut entries: Vec<&(String, Value)> = entries.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    pad(f, indent + 1)?;
+                    write_string(f, key)?;
+                    write!(f, ": {}", Pretty(value, indent + 1))?;
+                }
+                pad(f, indent)?;
+                write!(f, "}}")
+            }
+            value => write!(f, "{}", value),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    /// Objects compare equal regardless of key order, and numbers compare by
+    /// value.
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => numbers_equal(a, b),
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len() && a.iter().all(|(k, v)| other.get(k) == Some(v))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    /// Write the value as compact JSON.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write_string(f, s),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Compare two number tokens by value.
+///
+/// Integer tokens (no fraction or exponent) are compared exactly, so large IDs
+/// that differ in their last digit are never equal. Otherwise both are parsed
+/// as `f64` and must be exactly equal, so `1`, `1.0` and `1e0` are equal but
+/// `0.1 + 0.2`-style rounding differences are not. `-0` equals `0`.
+pub(crate) fn numbers_equal(a: &str, b: &str) -> bool {
+    let is_integer = |token: &str| !token.contains(['.', 'e', 'E']);
+    if is_integer(a) && is_integer(b) {
+        if let (Ok(a), Ok(b)) = (a.parse::<i128>(), b.parse::<i128>()) {
+            return a == b;
+        }
+    }
+    a.parse::<f64>().ok() == b.parse::<f64>().ok()
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Parse a complete JSON document.
+pub(crate) fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let mut end = self.pos;
+        while !self.text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let before = &self.text[..end];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        format!("{} at line {} column {}", message, line, column)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", byte as char)))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        if self.text[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("expected value"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'[') => self.array(),
+            Some(b'{') => self.object(),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        let token = &self.text[start..self.pos];
+        if token.parse::<f64>().is_err() {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+        Ok(Value::Number(token.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let c = rest
+                .chars()
+                .next()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escape {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => out.push(self.unicode_escape()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) && self.text[self.pos..].starts_with("\\u") {
+            self.pos += 2;
+            let low = self.hex4()?;
+            0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+        } else {
+            high
+        };
+        Ok(char::from_u32(code).unwrap_or('\u{FFFD}'))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect(b'[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut entries: Vec<(String, Value)> = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            let value = self.value()?;
+            // Later duplicates win, as in most JSON implementations.
+            entries.retain(|(k, _)| *k != key);
+            entries.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(entries));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+}
+
+/// One step of a path parsed by [`parse_path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PathStep {
+    Key(String),
+    Index(usize),
+    /// Every key of an object or element of an array.
+    Any,
+}
+
+/// Parse a JSON path like `$.a[0]`, `$["a key"]` or `$.items[*].id`.
+pub(crate) fn parse_path(path: &str) -> Result<Vec<PathStep>, String> {
+    let mut rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| "path must start with `$`".to_string())?;
+    let mut steps = vec![];
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            steps.push(match &after[..end] {
+                "" => return Err("empty key after `.`".to_string()),
+                "*" => PathStep::Any,
+                key => PathStep::Key(key.to_string()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            if after.starts_with('"') {
+                let mut parser = Parser {
+                    text: after,
+                    pos: 0,
+                };
+                steps.push(PathStep::Key(parser.string()?));
+                rest = &after[parser.pos..];
+            } else {
+                let end = after.find(']').ok_or_else(|| "unclosed `[`".to_string())?;
+                steps.push(match &after[..end] {
+                    "*" => PathStep::Any,
+                    index => PathStep::Index(
+                        index
+                            .parse()
+                            .map_err(|_| format!("invalid index `{}`", index))?,
+                    ),
+                });
+                rest = &after[end..];
+            }
+            rest = rest
+                .strip_prefix(']')
+                .ok_or_else(|| "expected `]`".to_string())?;
+        } else {
+            return Err(format!("unexpected `{}`", rest));
+        }
+    }
+    if steps.is_empty() {
+        return Err("path must select something below `$`".to_string());
+    }
+    Ok(steps)
+}
+
+/// Append an object key to a JSON path like `$.a[0]`.
+pub(crate) fn key_path(path: &str, key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{:?}]", path, key)
+    }
+}
+
+/// Append an array index to a JSON path like `$.a[0]`.
+pub(crate) fn index_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+/// Find the first difference between two values, returning its path and
+/// descriptions of the old and new values there.
+pub(crate) fn first_difference(
+    old: &Value,
+    new: &Value,
+    path: &str,
+) -> Option<(String, String, String)> {
+    let describe = |value: Option<&Value>| value.map_or("(nothing)".to_string(), Value::to_string);
+    match (old, new) {
+        (Value::Object(old_entries), Value::Object(new_entries)) => {
+            for (key, value) in old_entries {
+                let path = key_path(path, key);
+                match new.get(key) {
+                    Some(found) => {
+                        if let Some(difference) = first_difference(value, found, &path) {
+                            return Some(difference);
+                        }
+                    }
+                    None => return Some((path, describe(Some(value)), describe(None))),
+                }
+            }
+            new_entries
+                .iter()
+                .find(|(key, _)| old.get(key).is_none())
+                .map(|(key, value)| (key_path(path, key), describe(None), describe(Some(value))))
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for i in 0..old_items.len().max(new_items.len()) {
+                let path = index_path(path, i);
+                match (old_items.get(i), new_items.get(i)) {
+                    (Some(o), Some(n)) => {
+                        if let Some(difference) = first_difference(o, n, &path) {
+                            return Some(difference);
+                        }
+                    }
+                    (o, n) => return Some((path, describe(o), describe(n))),
+                }
+            }
+            None
+        }
+        _ if old == new => None,
+        _ => Some((path.to_string(), old.to_string(), new.to_string())),
+    }
+}
@@ -25,10 +25,33 @@
 
 #![deny(missing_docs)]
 
-#[macro_use(defer_on_unwind)]
-extern crate scopeguard;
-
+mod base64;
+mod bom;
+#[cfg(feature = "cas")]
+mod cas;
+mod csv;
 pub mod differs;
+#[cfg(feature = "dot")]
+mod dot;
+mod glob;
+mod goldencfg;
+mod hashcache;
+#[cfg(feature = "html")]
+mod html;
+mod jq;
+mod json;
+mod lockfile;
+#[cfg(feature = "markdown")]
+mod markdown;
 pub mod mint;
+#[cfg(feature = "json-schema")]
+mod schema;
+mod timestamp;
+#[cfg(feature = "vt100")]
+mod vt100;
+#[cfg(feature = "wav")]
+mod wav;
+#[cfg(feature = "xml")]
+mod xml;
 
 pub use mint::*;
@@ -0,0 +1,161 @@
+//! A small Cargo.lock parser used to compare lockfiles by their packages.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+type Fields = BTreeMap<String, String>;
+
+/// A package's name and version.
+type PackageId = (String, String);
+
+/// A parsed lockfile.
+///
+/// Only the subset of TOML that Cargo writes is supported: `key = value` lines,
+/// arrays that may span lines, `[table]` headers and `[[package]]` entries.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Lockfile {
+    /// Keys outside `[[package]]` entries, prefixed by their table name.
+    settings: Fields,
+    /// Packages keyed by name and version.
+    packages: BTreeMap<PackageId, Fields>,
+}
+
+pub(crate) fn parse(text: &str) -> Result<Lockfile, String> {
+    let mut settings = Fields::new();
+    let mut packages = BTreeMap::new();
+    let mut package: Option<Fields> = None;
+    let mut table = String::new();
+    let mut lines = text.lines().enumerate();
+    let mut finish = |package: Option<Fields>| -> Result<(), String> {
+        if let Some(mut fields) = package {
+            let name = fields.remove("name").ok_or("package without a name")?;
+            let version = fields.remove("version").unwrap_or_default();
+            packages.insert((unquote(&name), unquote(&version)), fields);
+        }
+        Ok(())
+    };
+    while let Some((i, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "[[package]]" {
+            finish(package.replace(Fields::new()))?;
+            continue;
+        }
+        if line.starts_with('[') {
+            finish(package.take())?;
+            table = line.trim_matches(['[', ']']).trim().to_string();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", i + 1))?;
+        let mut value = value.trim().to_string();
+        if value.starts_with('[') {
+            while !value.ends_with(']') {
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| format!("line {}: unterminated array", i + 1))?;
+                value.push_str(next.trim());
+            }
+            value = normalize_array(&value);
+        }
+        let key = key.trim().to_string();
+        match &mut package {
+            Some(fields) => fields.insert(key, value),
+            None if table.is_empty() => settings.insert(key, value),
+            None => settings.insert(format!("{}.{}", table, key), value),
+        };
+    }
+    finish(package)?;
+    Ok(Lockfile { settings, packages })
+}
+
+/// Rewrite an array with one space after each comma and no trailing comma.
+fn normalize_array(value: &str) -> String {
+    let items: Vec<&str> = value[1..value.len() - 1]
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches('"').to_string()
+}
+
+/// Describe how `new` differs from `old`, one line per change, or nothing if
+/// they match. A package whose only entry changes version is reported as
+/// updated rather than removed and added.
+pub(crate) fn differences(old: &Lockfile, new: &Lockfile) -> Vec<String> {
+    let mut lines = vec![];
+    for (key, value) in &old.settings {
+        match new.settings.get(key) {
+            None => lines.push(format!("removed setting: {}", key)),
+            Some(new_value) if new_value != value => {
+                lines.push(format!("changed setting: {} = {}", key, new_value))
+            }
+            _ => {}
+        }
+    }
+    for key in new.settings.keys() {
+        if !old.settings.contains_key(key) {
+            lines.push(format!("added setting: {}", key));
+        }
+    }
+
+    let mut removed: Vec<&PackageId> = vec![];
+    for (id, fields) in &old.packages {
+        match new.packages.get(id) {
+            None => removed.push(id),
+            Some(new_fields) if new_fields != fields => {
+                lines.push(format!(
+                    "changed: {} {} ({})",
+                    id.0,
+                    id.1,
+                    changed_fields(fields, new_fields).join(", ")
+                ));
+            }
+            _ => {}
+        }
+    }
+    let mut added: Vec<&PackageId> = new
+        .packages
+        .keys()
+        .filter(|id| !old.packages.contains_key(*id))
+        .collect();
+
+    let count = |ids: &[&PackageId], name: &str| ids.iter().filter(|id| id.0 == name).count();
+    let updated: Vec<(&PackageId, &PackageId)> = removed
+        .iter()
+        .filter(|old_id| count(&removed, &old_id.0) == 1 && count(&added, &old_id.0) == 1)
+        .map(|old_id| (*old_id, *added.iter().find(|id| id.0 == old_id.0).unwrap()))
+        .collect();
+    for (old_id, new_id) in &updated {
+        lines.push(format!(
+            "updated: {} {} -> {}",
+            old_id.0, old_id.1, new_id.1
+        ));
+        removed.retain(|id| id != old_id);
+        added.retain(|id| id != new_id);
+    }
+    lines.extend(
+        removed
+            .iter()
+            .map(|id| format!("removed: {} {}", id.0, id.1)),
+    );
+    lines.extend(added.iter().map(|id| format!("added: {} {}", id.0, id.1)));
+    lines
+}
+
+/// The keys whose values differ between two packages.
+fn changed_fields<'a>(old: &'a Fields, new: &'a Fields) -> Vec<&'a str> {
+    let keys: BTreeSet<&str> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(String::as_str)
+        .collect();
+    keys.into_iter().collect()
+}
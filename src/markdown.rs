@@ -0,0 +1,236 @@
+//! A small Markdown block parser used to compare documents by structure.
+
+/// A normalized Markdown block.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    ListItem {
+        ordered: bool,
+        depth: usize,
+        text: String,
+    },
+    Quote(String),
+    Code {
+        info: String,
+        code: String,
+    },
+    Rule,
+}
+
+/// Parse a Markdown document into normalized blocks.
+///
+/// Inline text has runs of whitespace collapsed to a single space, so
+/// re-wrapping a paragraph does not change its blocks, and `_` emphasis is
+/// rewritten as `*`, so `_x_` and `*x*` are the same. Code block contents
+/// are kept verbatim. List markers (`-`, `*`, `+`, `1.`, `1)`) only record
+/// whether the list is ordered.
+pub(crate) fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = vec![];
+    let mut lines = text.lines().peekable();
+    // Whether the last block can absorb continuation lines.
+    let mut open = false;
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            open = false;
+            continue;
+        }
+
+        if let Some(fence) = code_fence(trimmed) {
+            let info = trimmed[fence.len()..].trim().to_string();
+            let mut code = String::new();
+            for line in lines.by_ref() {
+                if line.trim().starts_with(fence) {
+                    break;
+                }
+                code.push_str(line);
+                code.push('\n');
+            }
+            blocks.push(Block::Code { info, code });
+            open = false;
+            continue;
+        }
+
+        let indented = line.starts_with("    ") || line.starts_with('\t');
+        let after_list = matches!(blocks.last(), Some(Block::ListItem { .. }));
+        if indented && !open && !after_list {
+            let mut code = format!("{}\n", strip_indent(line));
+            while let Some(next) = lines.peek() {
+                if !(next.starts_with("    ") || next.starts_with('\t')) {
+                    break;
+                }
+                code.push_str(strip_indent(next));
+                code.push('\n');
+                lines.next();
+            }
+            blocks.push(Block::Code {
+                info: String::new(),
+                code,
+            });
+            continue;
+        }
+
+        if open {
+            if let Some(Block::Paragraph(text)) = blocks.last() {
+                if let Some(level) = setext_level(trimmed) {
+                    let text = text.clone();
+                    blocks.pop();
+                    blocks.push(Block::Heading(level, text));
+                    open = false;
+                    continue;
+                }
+            }
+        }
+
+        if is_rule(trimmed) {
+            blocks.push(Block::Rule);
+            open = false;
+        } else if let Some((level, text)) = atx_heading(trimmed) {
+            blocks.push(Block::Heading(level, normalize_inline(text)));
+            open = false;
+        } else if let Some((ordered, rest)) = list_item(trimmed) {
+            let indent = line.len() - line.trim_start().len();
+            blocks.push(Block::ListItem {
+                ordered,
+                depth: indent / 2,
+                text: normalize_inline(rest),
+            });
+            open = true;
+        } else if let Some(rest) = trimmed.strip_prefix('>') {
+            match blocks.last_mut() {
+                Some(Block::Quote(text)) if open => append_inline(text, rest),
+                _ => blocks.push(Block::Quote(normalize_inline(rest))),
+            }
+            open = true;
+        } else if open {
+            match blocks.last_mut() {
+                Some(Block::Paragraph(text))
+                | Some(Block::Quote(text))
+                | Some(Block::ListItem { text, .. }) => append_inline(text, trimmed),
+                _ => unreachable!("only text blocks stay open"),
+            }
+        } else {
+            blocks.push(Block::Paragraph(normalize_inline(trimmed)));
+            open = true;
+        }
+    }
+
+    blocks
+}
+
+fn code_fence(line: &str) -> Option<&'static str> {
+    ["```", "~~~"]
+        .into_iter()
+        .find(|fence| line.starts_with(fence))
+}
+
+fn strip_indent(line: &str) -> &str {
+    line.strip_prefix('\t')
+        .unwrap_or_else(|| line.get(4..).unwrap_or(""))
+}
+
+fn setext_level(line: &str) -> Option<usize> {
+    if line.chars().all(|c| c == '=') {
+        Some(1)
+    } else if line.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && ['*', '-', '_'].contains(&chars[0]) && chars.iter().all(|&c| c == chars[0])
+}
+
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some((level, rest.trim().trim_end_matches('#')))
+    } else {
+        None
+    }
+}
+
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return Some((false, rest));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    if digits > 0 && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return Some((true, &rest[2..]));
+    }
+    None
+}
+
+fn normalize_inline(text: &str) -> String {
+    normalize_emphasis(&text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Rewrite runs of `_` that can open or close emphasis as `*`, leaving
+/// underscores within words, escaped ones and code spans alone.
+fn normalize_emphasis(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let run = chars[i..].iter().take_while(|&&other| other == c).count();
+        match c {
+            '\\' => {
+                out.extend(&chars[i..(i + 2).min(chars.len())]);
+                i += 2;
+                continue;
+            }
+            '`' => {
+                // A code span ends at the next run of as many backticks.
+                let mut end = i + run;
+                let close = loop {
+                    let Some(start) = chars[end..].iter().position(|&c| c == '`') else {
+                        break None;
+                    };
+                    let start = end + start;
+                    let len = chars[start..].iter().take_while(|&&c| c == '`').count();
+                    if len == run {
+                        break Some(start + len);
+                    }
+                    end = start + len;
+                };
+                let end = close.unwrap_or(i + run);
+                out.extend(&chars[i..end]);
+                i = end;
+                continue;
+            }
+            '_' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + run).copied();
+                let opens = after.is_some_and(|c| !c.is_whitespace())
+                    && !before.is_some_and(char::is_alphanumeric);
+                let closes = before.is_some_and(|c| !c.is_whitespace())
+                    && !after.is_some_and(char::is_alphanumeric);
+                let marker = if opens || closes { '*' } else { '_' };
+                out.extend(std::iter::repeat_n(marker, run));
+            }
+            _ => out.extend(&chars[i..i + run]),
+        }
+        i += run;
+    }
+    out
+}
+
+fn append_inline(text: &mut String, more: &str) {
+    let more = normalize_inline(more);
+    if !more.is_empty() {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(&more);
+    }
+}
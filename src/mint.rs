@@ -1,16 +1,45 @@
 //! Used to create goldenfiles.
 
+use std::cell::{Cell, RefCell};
+#[cfg(feature = "cas")]
+use std::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt;
 use std::fs;
 use std::fs::File;
-use std::io::{Error, ErrorKind, Result};
-use std::path::{Path, PathBuf};
+use std::io::{self, BufWriter, Error, ErrorKind, Read, Result, Write};
+use std::panic::{self, UnwindSafe};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Mutex, Once};
 use std::thread;
 
+use similar::TextDiff;
 use tempfile::TempDir;
 use yansi::Paint;
 
+use crate::base64;
+use crate::bom;
+#[cfg(feature = "cas")]
+use crate::cas;
 use crate::differs::*;
+use crate::glob;
+use crate::goldencfg;
+use crate::hashcache::HashCache;
+use crate::json;
+
+static DEFAULT_ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Serializes appends to patches written by [`Mint::write_patch`].
+static PATCH_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes appends to the `GOLDENFILE_DIFF_LOG` file.
+static DIFF_LOG_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes changes to the blobs and indexes of [`CasStore`]s.
+#[cfg(feature = "cas")]
+static CAS_LOCK: Mutex<()> = Mutex::new(());
 
 /// A Mint creates goldenfiles.
 ///
@@ -21,22 +50,185 @@ use crate::differs::*;
 ///      contents against their old contents, and panic if they differ.
 ///   2. If `UPDATE_GOLDENFILES=1`, it will replace the old goldenfile
 ///      contents with the newly written contents.
+///
+/// If `GOLDENFILE_DRYRUN=1`, it instead lists the goldenfiles that would
+/// change, without panicking or writing anything.
+///
+/// Setting one of the misspellings `UPDATE_GOLDENFILE`, `UPDATE_GOLDEN_FILES`,
+/// `UPDATE_GOLDENS` or `UPDATE_GOLDEN` to `1` instead prints a warning
+/// naming the correct variable, and goldenfiles are still checked.
+///
+/// If `GOLDENFILE_DIFF_LOG` names a file, each mismatch found while checking
+/// is also appended to it, under a `==== path ====` line and without color,
+/// so diffs survive CI logs that truncate stderr.
 pub struct Mint {
     path: PathBuf,
     tempdir: TempDir,
-    files: Vec<(PathBuf, Differ)>,
+    files: Vec<Goldenfile>,
     create_empty: bool,
+    verbose: bool,
+    header: Option<Header>,
+    check_on_drop: bool,
+    forbid_empty: bool,
+    atomic_writes: bool,
+    github_annotations: bool,
+    /// Failures from the last [`Mint::soft_check`].
+    soft_failures: Vec<CheckError>,
+    /// How many goldenfiles and golden dirs the last [`Mint::soft_check`]
+    /// covered, so those registered later are still checked on drop.
+    soft_checked: (usize, usize),
+    path_resolver: Option<PathResolver>,
+    compare_symlinks: bool,
+    treat_empty_as_equal: bool,
+    canonicalize_json: bool,
+    hash_cache: Option<HashCache>,
+    /// Temporary subdirectories known to exist, so each is created once.
+    temp_dirs: HashSet<PathBuf>,
+    golden_dirs: Vec<GoldenDir>,
+    store: Option<StoreState>,
+    budget: Rc<ByteBudget>,
+}
+
+/// A registered goldenfile.
+struct Goldenfile {
+    /// The path relative to the Mint, or of a golden sibling.
+    path: PathBuf,
+    /// Where the old contents are read from and updated.
+    old: PathBuf,
+    /// Where the new contents are written.
+    new: PathBuf,
+    /// The old contents' previous location, if [`Mint::rename_goldenfile`]
+    /// moved them.
+    moved_from: Option<PathBuf>,
+    differ: Rc<AnyDiffer>,
+    /// Whether the contents are binary, so text options don't apply.
+    binary: bool,
+    /// Whether the differ was inferred from the extension, so a `.goldencfg`
+    /// sidecar may override it.
+    inferred: bool,
+    /// The canonical goldenfile backing it, relative to the Mint, if
+    /// registered with [`Mint::register_shared_golden`].
+    shared: Option<PathBuf>,
+    /// Whether updating keeps the text encoding of the old contents.
+    preserve_encoding: bool,
+    /// Whether nothing should be written, as registered with
+    /// [`Mint::assert_no_goldenfile`].
+    expect_missing: bool,
+    /// Whether a mismatch only prints a warning, as registered with
+    /// [`Mint::register_goldenfile_lenient`].
+    lenient: bool,
+    /// How to regenerate the new contents on a mismatch, as registered with
+    /// [`Mint::register_goldenfile_with_regen`].
+    regen: Option<Regen>,
+    /// Whether the old contents are a JSON Schema that updating leaves alone,
+    /// as registered with [`Mint::register_schema_goldenfile`].
+    schema: bool,
+}
+
+/// Writes a goldenfile's new contents again.
+type Generator = Box<dyn Fn(&mut File) -> Result<()>>;
+
+/// A generator rerun when a flaky goldenfile doesn't match.
+struct Regen {
+    generate: Generator,
+    /// How many times to rerun it before failing.
+    attempts: usize,
+}
+
+/// A directory registered with [`Mint::golden_dir_with_excludes`].
+struct GoldenDir {
+    /// The path relative to the Mint.
+    path: PathBuf,
+    /// Where the goldenfiles are read from and updated.
+    old: PathBuf,
+    /// Globs of paths relative to the directory that are skipped.
+    excludes: Vec<String>,
+}
+
+impl GoldenDir {
+    fn includes(&self, file: &Path) -> bool {
+        !self
+            .excludes
+            .iter()
+            .any(|pattern| glob::matches(pattern, file))
+    }
+}
+
+/// The bytes written through a Mint's writers, and the limit set by
+/// [`Mint::max_total_bytes`].
+#[derive(Default)]
+struct ByteBudget {
+    written: Cell<u64>,
+    limit: Cell<Option<u64>>,
+}
+
+impl ByteBudget {
+    /// Count `n` more bytes written, panicking if that exceeds the limit.
+    fn spend(&self, n: usize) {
+        let written = self.written.get().saturating_add(n as u64);
+        self.written.set(written);
+        self.check(written);
+    }
+
+    /// Panic if `written` bytes exceed the limit.
+    fn check(&self, written: u64) {
+        if let Some(limit) = self.limit.get().filter(|&limit| written > limit) {
+            panic!(
+                "goldenfiles: wrote {} bytes, more than the limit of {} set by Mint::max_total_bytes",
+                written, limit
+            );
+        }
+    }
+}
+
+/// A [`GoldenStore`] and the goldenfiles staged from it.
+///
+/// Goldenfiles are copied from the store into `staging` when registered, so
+/// the Mint and its differs work on local files, and updates are written back
+/// from there.
+struct StoreState {
+    store: Box<dyn GoldenStore>,
+    staging: TempDir,
+    /// The contents read from the store for each staged path, or `None` if the
+    /// store had no goldenfile there.
+    fetched: RefCell<HashMap<PathBuf, Option<Vec<u8>>>>,
+}
+
+/// Maps a registered goldenfile path to where it is stored.
+type PathResolver = Box<dyn Fn(&Path) -> PathBuf>;
+
+/// A leading header to strip from text goldenfiles before comparing them.
+#[derive(Clone)]
+enum Header {
+    Lines(usize),
+    UntilMarker(String),
 }
 
 impl Mint {
     /// Create a new goldenfile Mint.
-    fn new_internal<P: AsRef<Path>>(path: P, create_empty: bool) -> Self {
-        let tempdir = TempDir::new().unwrap();
+    fn new_internal<P: AsRef<Path>>(path: P, create_empty: bool, tempdir: TempDir) -> Self {
         let mint = Mint {
-            path: path.as_ref().to_path_buf(),
+            path: Self::default_root().join(path),
             files: vec![],
             tempdir,
             create_empty,
+            verbose: false,
+            header: None,
+            check_on_drop: false,
+            forbid_empty: false,
+            atomic_writes: false,
+            github_annotations: env::var("GITHUB_ACTIONS").is_ok_and(|var| var == "true"),
+            soft_failures: vec![],
+            soft_checked: (0, 0),
+            path_resolver: None,
+            compare_symlinks: false,
+            treat_empty_as_equal: false,
+            canonicalize_json: false,
+            hash_cache: None,
+            temp_dirs: HashSet::new(),
+            golden_dirs: vec![],
+            store: None,
+            budget: Rc::default(),
         };
         fs::create_dir_all(&mint.path).unwrap_or_else(|err| {
             panic!(
@@ -47,21 +239,314 @@ impl Mint {
         mint
     }
 
+    /// Set a process-wide directory that relative Mint paths are joined onto.
+    ///
+    /// This takes precedence over the `GOLDENFILE_ROOT` environment variable.
+    /// Absolute Mint paths are used as-is.
+    pub fn set_default_root<P: AsRef<Path>>(path: P) {
+        *DEFAULT_ROOT.lock().unwrap() = Some(path.as_ref().to_path_buf());
+    }
+
+    /// The directory relative Mint paths are joined onto: the one passed to
+    /// [`Mint::set_default_root`], else `GOLDENFILE_ROOT`, else the current
+    /// directory.
+    fn default_root() -> PathBuf {
+        DEFAULT_ROOT
+            .lock()
+            .unwrap()
+            .clone()
+            .or_else(|| env::var_os("GOLDENFILE_ROOT").map(PathBuf::from))
+            .unwrap_or_default()
+    }
+
     /// Create a new goldenfile Mint.
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
-        Self::new_internal(path, true)
+        Self::new_internal(path, true, TempDir::new().unwrap())
     }
 
     /// Create a new goldenfile Mint. Goldenfiles will only be created when non-empty.
     pub fn new_nonempty<P: AsRef<Path>>(path: P) -> Self {
-        Self::new_internal(path, false)
+        Self::new_internal(path, false, TempDir::new().unwrap())
+    }
+
+    /// Create a new goldenfile Mint whose temporary directory's name starts
+    /// with `prefix`, e.g. `goldenfile-mymint-`, so it can be told apart from
+    /// other Mints' while debugging.
+    pub fn with_temp_prefix<P: AsRef<Path>, S: AsRef<str>>(path: P, prefix: S) -> Self {
+        let tempdir = tempfile::Builder::new()
+            .prefix(prefix.as_ref())
+            .tempdir()
+            .unwrap();
+        Self::new_internal(path, true, tempdir)
+    }
+
+    /// Create a new goldenfile Mint that reads and writes goldenfiles through
+    /// `store`, with paths relative to the store's root.
+    ///
+    /// Each goldenfile is read from the store when it's registered, and when
+    /// updating, only goldenfiles whose contents changed are written back or
+    /// removed. [`Mint::set_path_resolver`] doesn't apply, and golden
+    /// directories aren't supported.
+    pub fn with_store<S: GoldenStore + 'static>(store: S) -> Self {
+        let staging = TempDir::new().unwrap();
+        let mut mint = Self::new_internal(staging.path(), true, TempDir::new().unwrap());
+        mint.store = Some(StoreState {
+            store: Box::new(store),
+            staging,
+            fetched: RefCell::new(HashMap::new()),
+        });
+        mint
+    }
+
+    /// Whether the Mint will update goldenfiles rather than check them, because
+    /// `UPDATE_GOLDENFILES=1` (or the legacy `REGENERATE_GOLDENFILES=1`) is set.
+    pub fn is_updating(&self) -> bool {
+        update_mode()
+    }
+
+    /// Print a one-line summary of how many goldenfiles were checked or
+    /// updated when the Mint finishes. Disabled by default.
+    ///
+    /// Verbose Mints also print which differ was inferred for each goldenfile
+    /// registered without an explicit one, as they do for every Mint when
+    /// `GOLDENFILE_LOG_DIFFERS=1`. A `.goldencfg` sidecar may still override
+    /// it when checking.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Ignore the first `n` lines of every text goldenfile when checking.
+    ///
+    /// Binary goldenfiles are compared in full. A goldenfile is binary if it was
+    /// created with [`Mint::new_goldenfile_exact`], its extension maps to
+    /// [`binary_diff`] in [`get_differ_for_path`], or either version contains a
+    /// NUL byte or isn't valid UTF-8. Goldenfiles are still updated in full.
+    ///
+    /// Differs compare copies with the header stripped, so the diff a custom
+    /// differ prints may name those temporary copies, though its failure is
+    /// raised again naming the goldenfile.
+    pub fn ignore_leading_lines(&mut self, n: usize) {
+        self.header = Some(Header::Lines(n));
+    }
+
+    /// Ignore every line up to and including the first one containing `marker`
+    /// in text goldenfiles when checking. Files without the marker are compared
+    /// in full.
+    ///
+    /// Like [`Mint::ignore_leading_lines`], this doesn't apply to binary
+    /// goldenfiles.
+    pub fn ignore_header_until<S: AsRef<str>>(&mut self, marker: S) {
+        self.header = Some(Header::UntilMarker(marker.as_ref().to_string()));
+    }
+
+    /// Fail the check of any goldenfile whose new contents are empty, to catch
+    /// tests that create a goldenfile but never write to it.
+    pub fn forbid_empty(&mut self) {
+        self.forbid_empty = true;
+    }
+
+    /// Make [`Mint::write_goldenfile`] write to a staging file and rename it
+    /// into place, so processes reading the temporary file never see partial
+    /// contents. Disabled by default.
+    pub fn set_atomic_writes(&mut self, atomic_writes: bool) {
+        self.atomic_writes = atomic_writes;
+    }
+
+    /// Also print a GitHub Actions `::error` annotation for each changed
+    /// goldenfile, so failures show up inline in pull requests. Enabled by
+    /// default when `GITHUB_ACTIONS=true`.
+    pub fn set_github_annotations(&mut self, github_annotations: bool) {
+        self.github_annotations = github_annotations;
+    }
+
+    /// Panic as soon as more than `limit` bytes in total have been written
+    /// through this Mint's writers, to stop a runaway generator before it
+    /// fills the disk.
+    ///
+    /// Bytes are counted by [`Mint::write_goldenfile`],
+    /// [`Mint::new_goldenfile_fmt_writer`], [`Mint::new_goldenfile_guard`] and
+    /// [`Mint::assert_golden_reader`], including those written before this is
+    /// called. Writes to the `File`s and paths other methods return can't be
+    /// counted as they happen, so the total size of all new goldenfiles is
+    /// also checked before they are checked or updated.
+    pub fn max_total_bytes(&mut self, limit: u64) {
+        self.budget.limit.set(Some(limit));
+    }
+
+    /// Treat an empty new goldenfile as matching an empty or missing old one,
+    /// without running its differ.
+    ///
+    /// By default the differ decides: text differs read a missing goldenfile
+    /// as empty, but [`binary_diff`] fails to read it. Either way, updating
+    /// with an empty new goldenfile writes an empty goldenfile for a Mint from
+    /// [`Mint::new`], and removes the old one for a Mint from
+    /// [`Mint::new_nonempty`]. [`Mint::forbid_empty`] still fails empty
+    /// goldenfiles.
+    pub fn set_treat_empty_as_equal(&mut self, treat_empty_as_equal: bool) {
+        self.treat_empty_as_equal = treat_empty_as_equal;
+    }
+
+    /// Write `.json` goldenfiles in a canonical form when updating: with object
+    /// keys sorted and indented by two spaces. Disabled by default, so
+    /// intentionally ordered goldenfiles are left alone.
+    ///
+    /// This keeps goldenfiles stable when a serializer's key order varies
+    /// between runs. The new contents are no longer byte-for-byte identical to
+    /// the goldenfile, so `.json` goldenfiles registered without a differ are
+    /// compared with [`json_diff`] instead of [`text_diff`] while this is
+    /// enabled. New contents that aren't valid JSON are written and compared
+    /// as-is.
+    pub fn set_canonicalize_json(&mut self, canonicalize_json: bool) {
+        self.canonicalize_json = canonicalize_json;
+    }
+
+    /// Skip the differs of goldenfiles whose new contents are known to match,
+    /// using a manifest of hashes stored at `manifest`.
+    ///
+    /// When a goldenfile matches, the hash of its new contents and the
+    /// goldenfile's modification time are recorded, and the manifest is saved
+    /// when the Mint goes out of scope. While both stay the same, later checks
+    /// pass without running the differ, which saves time in large suites.
+    /// Anything else falls back to the differ. The manifest doesn't record
+    /// differs or their settings, so delete it after changing them.
+    pub fn set_hash_cache<P: AsRef<Path>>(&mut self, manifest: P) -> Result<()> {
+        self.hash_cache = Some(HashCache::load(manifest.as_ref())?);
+        Ok(())
+    }
+
+    /// Compare goldenfiles that are symlinks by their target paths rather than
+    /// the contents they point to.
+    ///
+    /// A symlink never matches a regular file. When updating, symlinks are
+    /// recreated with the same target instead of being copied through, so
+    /// snapshots of generated trees keep their structure.
+    pub fn set_compare_symlinks(&mut self, compare_symlinks: bool) {
+        self.compare_symlinks = compare_symlinks;
+    }
+
+    /// Compute where goldenfiles are stored from their registered relative
+    /// paths with `resolver`, instead of joining them onto the Mint's directory.
+    ///
+    /// The resolved path is used as-is. Goldenfiles registered before this is
+    /// called keep their existing location.
+    pub fn set_path_resolver<F: Fn(&Path) -> PathBuf + 'static>(&mut self, resolver: F) {
+        self.path_resolver = Some(Box::new(resolver));
+    }
+
+    /// Where the goldenfile registered at `path` is stored.
+    fn golden_path(&self, path: &Path) -> PathBuf {
+        if let Some(state) = &self.store {
+            return state.stage(path);
+        }
+        match &self.path_resolver {
+            Some(resolver) => resolver(path),
+            None => self.path.join(path),
+        }
+    }
+
+    /// Check each goldenfile created with [`Mint::new_goldenfile_guard`] as soon
+    /// as its writer is dropped, rather than only when the Mint finishes.
+    /// Disabled by default, and ignored when updating or dry-running.
+    pub fn set_check_on_drop(&mut self, check_on_drop: bool) {
+        self.check_on_drop = check_on_drop;
     }
 
     /// Create a new goldenfile using a differ inferred from the file extension.
     ///
+    /// If the goldenfile has a `name.ext.goldencfg` sidecar, the tolerance it
+    /// sets overrides the inferred differ when checking. The sidecar holds one
+    /// `key = value` line, with `#` comments allowed. For text goldenfiles the
+    /// key is either `epsilon`, to compare with [`numeric_tolerant_diff`], or
+    /// `max_changed_fraction`, to compare with [`fuzzy_line_diff`]. For binary
+    /// ones it is either `max_changed_bytes`, to compare with
+    /// [`changed_bytes_diff`], or `max_mean_byte_diff`, to compare with
+    /// [`mean_byte_diff`]. Sidecars are ignored by goldenfiles created with an
+    /// explicit differ.
+    ///
     /// The returned File is a temporary file, not the goldenfile itself.
     pub fn new_goldenfile<P: AsRef<Path>>(&mut self, path: P) -> Result<File> {
-        self.new_goldenfile_with_differ(&path, get_differ_for_path(&path))
+        let file = self.new_goldenfile_comparing(&path, self.infer_differ(path.as_ref()))?;
+        self.files.last_mut().unwrap().inferred = true;
+        Ok(file)
+    }
+
+    /// Create a new goldenfile that must match its golden version byte for byte,
+    /// regardless of the file extension.
+    ///
+    /// The returned File is a temporary file, not the goldenfile itself.
+    pub fn new_goldenfile_exact<P: AsRef<Path>>(&mut self, path: P) -> Result<File> {
+        let file =
+            self.new_goldenfile_comparing(path, AnyDiffer::Outcome(Box::new(binary_outcome)))?;
+        self.files.last_mut().unwrap().binary = true;
+        Ok(file)
+    }
+
+    /// Create a new goldenfile compared with [`bom_aware_text_diff`], so UTF-8
+    /// and UTF-16 contents with the same text match.
+    ///
+    /// When updating, the new text is re-encoded to match the existing
+    /// goldenfile's encoding and byte order mark. New goldenfiles are written
+    /// as they are.
+    ///
+    /// The returned File is a temporary file, not the goldenfile itself.
+    pub fn new_goldenfile_bom_aware<P: AsRef<Path>>(&mut self, path: P) -> Result<File> {
+        let file = self
+            .new_goldenfile_comparing(path, AnyDiffer::Outcome(Box::new(bom_aware_text_outcome)))?;
+        let goldenfile = self.files.last_mut().unwrap();
+        goldenfile.binary = false;
+        goldenfile.preserve_encoding = true;
+        Ok(file)
+    }
+
+    /// Create a new goldenfile that is compared as text, regardless of the
+    /// file extension.
+    ///
+    /// The returned File is a temporary file, not the goldenfile itself.
+    pub fn new_goldenfile_text<P: AsRef<Path>>(&mut self, path: P) -> Result<File> {
+        let file =
+            self.new_goldenfile_comparing(path, AnyDiffer::Outcome(Box::new(text_outcome)))?;
+        self.files.last_mut().unwrap().binary = false;
+        Ok(file)
+    }
+
+    /// Create a new goldenfile whose new contents are normalized for the current
+    /// platform before being compared with a differ inferred from the file
+    /// extension.
+    ///
+    /// Each transform whose [`Platform`] matches the platform the test was
+    /// compiled for is applied in order, so a single golden can serve every
+    /// platform. For example, `(Platform::Windows, Box::new(|s| s.replace('\\', "/")))`
+    /// normalizes path separators in Windows output. The old contents and the
+    /// updated goldenfile are not transformed.
+    ///
+    /// The returned File is a temporary file, not the goldenfile itself.
+    pub fn new_goldenfile_platform<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        transforms: Vec<PlatformTransform>,
+    ) -> Result<File> {
+        let inner = AnyDiffer::Outcome(inferred_differ(path.as_ref()).1);
+        let differ: OutcomeDiffer = Box::new(move |old, new| {
+            let normalize = |contents: Vec<u8>| match String::from_utf8(contents) {
+                Ok(mut text) => {
+                    for (platform, transform) in &transforms {
+                        if platform.is_current() {
+                            text = transform(&text);
+                        }
+                    }
+                    text.into_bytes()
+                }
+                Err(err) => err.into_bytes(),
+            };
+            into_outcome(diff_transformed(
+                old,
+                new,
+                &inner,
+                &|contents| contents,
+                &normalize,
+            ))
+        });
+        self.new_goldenfile_comparing(path, AnyDiffer::Outcome(differ))
     }
 
     /// Create a new goldenfile with the specified diff function.
@@ -72,18 +557,17 @@ impl Mint {
         path: P,
         differ: Differ,
     ) -> Result<File> {
-        let abs_path = self.register_goldenfile_with_differ(path, differ)?;
+        self.new_goldenfile_comparing(path, AnyDiffer::Custom(differ))
+    }
 
-        if let Some(abs_parent) = abs_path.parent() {
-            if abs_parent != self.tempdir.path() {
-                fs::create_dir_all(abs_parent).unwrap_or_else(|err| {
-                    panic!(
-                        "Failed to create temporary subdirectory {:?}: {:?}",
-                        abs_parent, err
-                    )
-                });
-            }
-        }
+    /// Create a new goldenfile compared with `differ`.
+    fn new_goldenfile_comparing<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        differ: AnyDiffer,
+    ) -> Result<File> {
+        let abs_path = self.register_goldenfile_comparing(path, differ)?;
+        self.create_temp_parent(&abs_path);
         let maybe_file = File::create(abs_path);
         if !maybe_file.is_ok() {
             self.files.pop();
@@ -91,105 +575,2308 @@ impl Mint {
         maybe_file
     }
 
-    /// Check new goldenfile contents against old, and panic if they differ.
+    /// Create a new goldenfile with the given contents, using a differ inferred
+    /// from the file extension.
     ///
-    /// Called automatically when a Mint goes out of scope and
-    /// `UPDATE_GOLDENFILES!=1`.
-    pub fn check_goldenfiles(&self) {
-        for (file, differ) in &self.files {
-            let old = self.path.join(file);
-            let new = self.tempdir.path().join(file);
-            defer_on_unwind! {
-                eprintln!("note: run with `UPDATE_GOLDENFILES=1` to update goldenfiles");
-                eprintln!(
-                    "{}: goldenfile changed: {}",
-                    "error".bold().red(),
-                    file.to_str().unwrap()
-                );
+    /// With [`Mint::set_atomic_writes`], the contents are staged in another
+    /// temporary file and renamed into place once fully written.
+    pub fn write_goldenfile<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &mut self,
+        path: P,
+        contents: C,
+    ) -> Result<()> {
+        if !self.atomic_writes {
+            let mut file = GoldenWriter {
+                inner: BufWriter::new(self.new_goldenfile(path)?),
+                check: None,
+                budget: Rc::clone(&self.budget),
+            };
+            file.write_all(contents.as_ref())?;
+            return file.flush();
+        }
+        let abs_path = self.register_goldenfile(path)?;
+        self.create_temp_parent(&abs_path);
+        let result = tempfile::NamedTempFile::new_in(self.tempdir.path()).and_then(|mut staged| {
+            staged.write_all(contents.as_ref())?;
+            staged.persist(&abs_path)?;
+            Ok(())
+        });
+        match result {
+            Ok(()) => self.budget.spend(contents.as_ref().len()),
+            Err(_) => {
+                self.files.pop();
             }
-            differ(&old, &new);
         }
+        result
     }
 
-    /// Overwrite old goldenfile contents with their new contents.
+    /// Create the directory a temporary file will be written to.
+    fn create_temp_parent(&mut self, abs_path: &Path) {
+        let Some(abs_parent) = abs_path.parent() else {
+            return;
+        };
+        if abs_parent == self.tempdir.path() || self.temp_dirs.contains(abs_parent) {
+            return;
+        }
+        fs::create_dir_all(abs_parent).unwrap_or_else(|err| {
+            panic!(
+                "Failed to create temporary subdirectory {:?}: {:?}",
+                abs_parent, err
+            )
+        });
+        for dir in abs_parent.ancestors() {
+            if dir == self.tempdir.path() || !self.temp_dirs.insert(dir.to_path_buf()) {
+                break;
+            }
+        }
+    }
+
+    /// Create the temporary subdirectories of every registered goldenfile.
     ///
-    /// Called automatically when a Mint goes out of scope and
-    /// `UPDATE_GOLDENFILES=1`.
-    pub fn update_goldenfiles(&self) {
-        for (file, _) in &self.files {
-            let old = self.path.join(file);
-            let new = self.tempdir.path().join(file);
+    /// Goldenfiles registered with [`Mint::register_goldenfile`] are written by
+    /// the caller, so this saves creating their parent directories one by one.
+    /// Each directory is only created once, however many goldenfiles share it.
+    pub fn prepare_dirs(&mut self) {
+        let temp_paths: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|goldenfile| goldenfile.new.starts_with(self.tempdir.path()))
+            .map(|goldenfile| goldenfile.new.clone())
+            .collect();
+        for temp_path in temp_paths {
+            self.create_temp_parent(&temp_path);
+        }
+    }
 
-            let empty = File::open(&new).unwrap().metadata().unwrap().len() == 0;
-            if self.create_empty || !empty {
-                println!("Updating {:?}.", file.to_str().unwrap());
-                fs::copy(&new, &old).unwrap_or_else(|err| {
-                    panic!("Error copying {:?} to {:?}: {:?}", &new, &old, err)
-                });
-            } else if old.exists() {
-                std::fs::remove_file(&old).unwrap();
+    /// Create a new goldenfile using a differ inferred from the file extension,
+    /// written through [`std::fmt::Write`].
+    ///
+    /// The returned writer buffers into a temporary file, not the goldenfile
+    /// itself, and flushes when dropped.
+    pub fn new_goldenfile_fmt_writer<P: AsRef<Path>>(&mut self, path: P) -> Result<FmtWriter> {
+        let file = self.new_goldenfile(path)?;
+        Ok(FmtWriter {
+            inner: BufWriter::new(file),
+            budget: Rc::clone(&self.budget),
+        })
+    }
+
+    /// Create a new goldenfile using a differ inferred from the file extension,
+    /// returning a writer that flushes when dropped.
+    ///
+    /// With [`Mint::set_check_on_drop`], dropping the writer also checks this
+    /// goldenfile immediately. It is still checked again when the Mint finishes.
+    ///
+    /// The returned writer buffers into a temporary file, not the goldenfile
+    /// itself.
+    pub fn new_goldenfile_guard<P: AsRef<Path>>(&mut self, path: P) -> Result<GoldenWriter> {
+        let file = self.new_goldenfile(path)?;
+        let check: Option<Box<dyn FnOnce()>> =
+            if self.check_on_drop && !update_mode() && !dry_run_mode() {
+                let goldenfile = self.files.last().unwrap();
+                let name = goldenfile.path.clone();
+                let (old, new) = (goldenfile.old.clone(), goldenfile.new.clone());
+                let differ = Rc::clone(&goldenfile.differ);
+                let header = self.header.clone().filter(|_| !goldenfile.binary);
+                let annotate = self.github_annotations;
+                let (inferred, binary) = (goldenfile.inferred, goldenfile.binary);
+                Some(Box::new(move || {
+                    let sidecar = match inferred {
+                        true => sidecar_differ(&old, binary),
+                        false => Ok(None),
+                    };
+                    let result = sidecar.and_then(|sidecar| {
+                        let differ = sidecar.as_ref().unwrap_or(&*differ);
+                        run_differ(&old, &new, differ, header.as_ref())
+                    });
+                    if let Err(failure) = result {
+                        report_change(&name, &old, annotate);
+                        print_update_hint();
+                        failure.raise();
+                    }
+                }))
+            } else {
+                None
+            };
+        Ok(GoldenWriter {
+            inner: BufWriter::new(file),
+            check,
+            budget: Rc::clone(&self.budget),
+        })
+    }
+
+    /// Create a new goldenfile from everything `reader` produces, using a differ
+    /// inferred from the file extension, and check it immediately.
+    ///
+    /// The reader is streamed into the temporary file rather than buffered in
+    /// memory. The goldenfile is still checked or updated again when the Mint
+    /// finishes.
+    pub fn assert_golden_reader<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        reader: &mut impl Read,
+    ) -> Result<()> {
+        let mut file = GoldenWriter {
+            inner: BufWriter::new(self.new_goldenfile(path)?),
+            check: None,
+            budget: Rc::clone(&self.budget),
+        };
+        io::copy(reader, &mut file)?;
+        file.flush()?;
+        self.check_last_goldenfile();
+        Ok(())
+    }
+
+    /// Create a new goldenfile holding the pretty-printed `Debug`
+    /// representation of `value`, and check it immediately with [`text_diff`]
+    /// whatever its extension.
+    ///
+    /// The goldenfile is still checked or updated again when the Mint
+    /// finishes, like one written by [`Mint::assert_golden_reader`].
+    pub fn assert_golden_debug<P: AsRef<Path>, T: fmt::Debug>(
+        &mut self,
+        path: P,
+        value: &T,
+    ) -> Result<()> {
+        let mut file =
+            self.new_goldenfile_comparing(path, AnyDiffer::Outcome(Box::new(text_outcome)))?;
+        writeln!(file, "{:#?}", value)?;
+        self.check_last_goldenfile();
+        Ok(())
+    }
+
+    /// Create a new goldenfile holding a JSON `value`, pretty-printed, and
+    /// check it immediately with [`json_diff`] whatever its extension.
+    ///
+    /// The value may be anything whose `Display` output is JSON. A
+    /// `&serde_json::Value` can be passed as-is, but the parameter is generic
+    /// rather than `&serde_json::Value` so goldenfile needn't depend on
+    /// `serde_json`. Object keys are written sorted, whatever order the value
+    /// keeps them in, so the goldenfile is stable. Updating writes the
+    /// pretty-printed form. The goldenfile is still checked or updated again
+    /// when the Mint finishes, like with [`Mint::assert_golden_debug`].
+    pub fn assert_golden_value<P: AsRef<Path>, V: fmt::Display>(
+        &mut self,
+        path: P,
+        value: &V,
+    ) -> Result<()> {
+        let value = json::parse(&value.to_string()).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("value is not valid JSON: {}", err),
+            )
+        })?;
+        let mut file =
+            self.new_goldenfile_comparing(path, AnyDiffer::Outcome(Box::new(json_outcome)))?;
+        writeln!(file, "{}", value.pretty())?;
+        self.check_last_goldenfile();
+        Ok(())
+    }
+
+    /// Run `f`, which must panic, and create a new goldenfile holding its
+    /// panic message, using a differ inferred from the file extension. The
+    /// goldenfile is checked immediately, like with
+    /// [`Mint::assert_golden_debug`].
+    ///
+    /// Panic payloads that aren't strings are recorded as `Box<dyn Any>`. The
+    /// panic hook still runs, so the message is also printed to stderr. Panics
+    /// if `f` returns normally.
+    pub fn assert_golden_panic<P: AsRef<Path>, F: FnOnce() + UnwindSafe>(
+        &mut self,
+        path: P,
+        f: F,
+    ) -> Result<()> {
+        let payload = match panic::catch_unwind(f) {
+            Ok(()) => panic!("{:?}: expected a panic", path.as_ref()),
+            Err(payload) => payload,
+        };
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_string()
+        };
+        writeln!(self.new_goldenfile(path)?, "{}", message)?;
+        self.check_last_goldenfile();
+        Ok(())
+    }
+
+    /// Check the most recently registered goldenfile now, unless updating.
+    fn check_last_goldenfile(&self) {
+        if !update_mode() && !dry_run_mode() {
+            let goldenfile = self.files.last().unwrap();
+            let result = self.check_goldenfile(goldenfile, || self.report_change(goldenfile));
+            if let Err(failure) = result {
+                print_update_hint();
+                failure.raise();
             }
         }
     }
 
-    /// Register a new goldenfile using a differ inferred from the file extension.
+    /// Compare bytes against a golden embedded in the test, e.g. with
+    /// `include_bytes!`, using a differ inferred from `name`'s extension, and
+    /// panic if they differ.
     ///
-    /// The returned PathBuf references a temporary file, not the goldenfile itself.
-    pub fn register_goldenfile<P: AsRef<Path>>(&mut self, path: P) -> Result<PathBuf> {
-        self.register_goldenfile_with_differ(&path, get_differ_for_path(&path))
+    /// Embedded goldens can't be updated in place, so when
+    /// `UPDATE_GOLDENFILES=1` this never fails and instead writes the new
+    /// contents to `name` in the Mint's directory. Embedding that file makes
+    /// the next build pick up the update.
+    pub fn assert_golden_embedded<N: AsRef<Path>>(
+        &mut self,
+        name: N,
+        actual: &[u8],
+        expected: &[u8],
+    ) {
+        let name = name.as_ref();
+        if update_mode() {
+            let path = self.golden_path(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(&path, actual)
+                .unwrap_or_else(|err| panic!("Error writing {:?}: {:?}", path, err));
+            println!(
+                "Embedded golden {:?} can't be updated; wrote new contents to {:?}.",
+                name, path
+            );
+            return;
+        }
+        let dir = self.tempdir.path().join(".embedded");
+        let write = |side: &str, contents: &[u8]| {
+            let path = dir.join(side).join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            path
+        };
+        let (old, new) = (write("old", expected), write("new", actual));
+        let differ = AnyDiffer::Outcome(inferred_differ(name).1);
+        if let Err(failure) = diff_renamed(&old, &new, name, name, &differ) {
+            report_change(name, &self.golden_path(name), self.github_annotations);
+            log_diff(name, &failure.message());
+            print_update_hint();
+            failure.raise();
+        }
     }
 
-    /// Register a new goldenfile with the specified diff function.
+    /// Compare text against a golden embedded in the test, e.g. with
+    /// `include_str!`. See [`Mint::assert_golden_embedded`].
+    pub fn assert_golden_embedded_str<N: AsRef<Path>>(
+        &mut self,
+        name: N,
+        actual: &str,
+        expected: &str,
+    ) {
+        self.assert_golden_embedded(name, actual.as_bytes(), expected.as_bytes());
+    }
+
+    /// Compare bytes against a golden stored inline as base64, and panic if they
+    /// differ.
     ///
-    /// The returned PathBuf references a temporary file, not the goldenfile itself.
-    pub fn register_goldenfile_with_differ<P: AsRef<Path>>(
+    /// `name` is only used in messages. When `UPDATE_GOLDENFILES=1` this never
+    /// fails and instead prints the new base64 to paste into the test.
+    pub fn assert_golden_base64<N: AsRef<str>>(
         &mut self,
-        path: P,
-        differ: Differ,
-    ) -> Result<PathBuf> {
-        if !path.as_ref().is_relative() {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Path must be relative.",
+        name: N,
+        actual: &[u8],
+        expected_b64: &str,
+    ) {
+        let name = name.as_ref();
+        if update_mode() {
+            println!("New base64 for {:?}: {}", name, base64::encode(actual));
+            return;
+        }
+        let expected = base64::decode(expected_b64)
+            .unwrap_or_else(|err| panic!("{}: invalid base64 golden: {}", name, err));
+        if expected != actual {
+            eprintln!("note: run with `UPDATE_GOLDENFILES=1` to print the new base64");
+            eprintln!("{}: goldenfile changed: {}", "error".bold().red(), name);
+            let position = expected
+                .iter()
+                .zip(actual)
+                .position(|(old, new)| old != new)
+                .unwrap_or(expected.len().min(actual.len()));
+            panic!(
+                "{}: Base64 golden differs at byte {}: old is {} bytes, new is {} bytes",
+                name,
+                position + 1,
+                expected.len(),
+                actual.len()
+            );
+        }
+    }
+
+    /// Compare text against a golden stored inline as a string, and panic with
+    /// the same diff [`text_diff`] prints if they differ.
+    ///
+    /// `name` is only used in messages. When `UPDATE_GOLDENFILES=1` this never
+    /// fails and instead prints the new value as a string literal to paste
+    /// into the test.
+    pub fn assert_golden_matches_str<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        actual: &str,
+        expected: &str,
+    ) {
+        let name = name.as_ref();
+        if actual == expected {
+            return;
+        }
+        if update_mode() {
+            println!("New value for {:?}: {:?}", name, actual);
+            return;
+        }
+        eprintln!("note: run with `UPDATE_GOLDENFILES=1` to print the new value");
+        eprintln!("{}: goldenfile changed: {}", "error".bold().red(), name);
+        if let Some(message) = text_mismatch(Path::new(name), expected, actual) {
+            panic!("{}", message);
+        }
+    }
+
+    /// Check that at least one of `candidates` matches the goldenfile at
+    /// `path`, compared now with the differ inferred from its extension, and
+    /// panic with every candidate's failure if none do.
+    ///
+    /// This suits generators that write equivalent output to one of several
+    /// places. When `UPDATE_GOLDENFILES=1` and no candidate matches, the
+    /// goldenfile is updated from the first candidate that exists instead.
+    pub fn check_any<P: AsRef<Path>>(&self, path: P, candidates: Vec<PathBuf>) {
+        let path = path.as_ref();
+        assert!(!candidates.is_empty(), "{:?}: no candidates to check", path);
+        let old = self.golden_path(path);
+        let differ = self.infer_differ(path);
+        let header = self.header.as_ref().filter(|_| !has_binary_extension(path));
+        let mut failures = vec![];
+        for candidate in &candidates {
+            match run_differ(&old, candidate, &differ, header) {
+                Ok(()) => return,
+                Err(failure) => failures.push((candidate, failure)),
+            }
+        }
+        if update_mode() {
+            let candidate = candidates
+                .iter()
+                .find(|candidate| candidate.exists())
+                .unwrap_or_else(|| panic!("{:?}: none of the candidates exist", path));
+            println!("Updating {:?}.", path.to_str().unwrap());
+            if let Some(parent) = old.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::copy(candidate, &old).unwrap_or_else(|err| {
+                panic!("Error copying {:?} to {:?}: {:?}", candidate, old, err)
+            });
+            return;
+        }
+        report_change(path, &old, self.github_annotations);
+        let mut message = format!("{}: no candidate matches the goldenfile", path.display());
+        for (candidate, failure) in failures {
+            message.push_str(&format!(
+                "\n  {}: {}",
+                candidate.display(),
+                failure.message()
             ));
         }
+        log_diff(path, &message);
+        print_update_hint();
+        panic!("{}", message);
+    }
 
-        let abs_path = self.tempdir.path().to_path_buf().join(path.as_ref());
-        self.files.push((path.as_ref().to_path_buf(), differ));
-        Ok(abs_path)
+    /// Check new goldenfile contents against old, and panic if they differ.
+    ///
+    /// Checking stops at the first goldenfile that differs, and the hint to
+    /// set `UPDATE_GOLDENFILES=1` is printed once before its panic is raised.
+    ///
+    /// Called automatically when a Mint goes out of scope and
+    /// `UPDATE_GOLDENFILES!=1`.
+    pub fn check_goldenfiles(&self) {
+        self.check_goldenfiles_from(0, 0);
     }
-}
 
-/// Get the diff function to use for a given file path.
-pub fn get_differ_for_path<P: AsRef<Path>>(_path: P) -> Differ {
-    match _path.as_ref().extension() {
-        Some(os_str) => match os_str.to_str() {
-            Some("bin") => Box::new(binary_diff),
-            Some("exe") => Box::new(binary_diff),
-            Some("gz") => Box::new(binary_diff),
-            Some("tar") => Box::new(binary_diff),
-            Some("zip") => Box::new(binary_diff),
-            _ => Box::new(text_diff),
-        },
-        _ => Box::new(text_diff),
+    /// Check the goldenfiles and golden dirs registered after the first
+    /// `files` and `dirs` of them, as [`Mint::check_goldenfiles`] does.
+    fn check_goldenfiles_from(&self, files: usize, dirs: usize) {
+        self.check_total_bytes();
+        let fail = |checked, failure: Failure| -> ! {
+            if self.verbose {
+                println!("goldenfiles: {} checked, 1 changed", checked);
+            }
+            print_update_hint();
+            failure.raise();
+        };
+        let files = &self.files[files..];
+        let dirs = &self.golden_dirs[dirs..];
+        for (i, goldenfile) in files.iter().enumerate() {
+            let result = self.check_goldenfile(goldenfile, || self.report_change(goldenfile));
+            if let Err(failure) = result {
+                fail(i + 1, failure);
+            }
+        }
+        for (i, dir) in dirs.iter().enumerate() {
+            if let Some(err) = self.check_golden_dir(dir, true) {
+                log_diff(&dir.path, &err.to_string());
+                let failure = match err {
+                    CheckError::Io { file, error } => {
+                        Failure::Io(DiffIoError { path: file, error })
+                    }
+                    CheckError::Changed { message, .. } => Failure::Changed(message),
+                };
+                fail(files.len() + i + 1, failure);
+            }
+        }
+        if self.verbose {
+            println!(
+                "goldenfiles: {} checked, 0 changed",
+                files.len() + dirs.len()
+            );
+        }
     }
-}
 
-impl Drop for Mint {
-    /// Called when the mint goes out of scope to check or update goldenfiles.
-    fn drop(&mut self) {
-        if thread::panicking() {
-            return;
+    /// Check new goldenfile contents against old, and return the first
+    /// failure instead of panicking.
+    ///
+    /// Diffs are still printed, but IO errors from the built-in differs are
+    /// returned as [`CheckError::Io`] rather than counted as mismatches.
+    pub fn try_check_goldenfiles(&self) -> std::result::Result<(), CheckError> {
+        for goldenfile in &self.files {
+            if let Err(failure) = self.check_goldenfile(goldenfile, || {}) {
+                return Err(check_error(goldenfile, failure));
+            }
         }
-        // For backwards compatibility with 1.4 and below.
-        let legacy_var = env::var("REGENERATE_GOLDENFILES");
-        let update_var = env::var("UPDATE_GOLDENFILES");
-        if (legacy_var.is_ok() && legacy_var.unwrap() == "1")
-            || (update_var.is_ok() && update_var.unwrap() == "1")
+        match self.golden_dirs.iter().find_map(|dir| {
+            let err = self.check_golden_dir(dir, false)?;
+            log_diff(&dir.path, &err.to_string());
+            Some(err)
+        }) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Check only the goldenfile registered at `path` now, returning its
+    /// failure instead of panicking, like [`Mint::try_check_goldenfiles`].
+    ///
+    /// Other goldenfiles aren't checked, and this one is still checked or
+    /// updated as usual when the Mint finishes. A mismatch is a
+    /// [`CheckError::Changed`], while an unreadable goldenfile or a `path` that
+    /// isn't registered is a [`CheckError::Io`], the latter of kind
+    /// [`ErrorKind::NotFound`].
+    pub fn check_one<P: AsRef<Path>>(&self, path: P) -> std::result::Result<(), CheckError> {
+        let path = path.as_ref();
+        let goldenfile = self
+            .files
+            .iter()
+            .rev()
+            .find(|goldenfile| goldenfile.path == path)
+            .ok_or_else(|| CheckError::Io {
+                file: path.to_path_buf(),
+                error: Error::new(ErrorKind::NotFound, "goldenfile is not registered"),
+            })?;
+        self.check_goldenfile(goldenfile, || {})
+            .map_err(|failure| check_error(goldenfile, failure))
+    }
+
+    /// Check every goldenfile and record the failures instead of panicking, so
+    /// a test can report many mismatches at once.
+    ///
+    /// Afterwards the Mint no longer checks these goldenfiles when it goes out
+    /// of scope, only those registered after this call; call
+    /// [`Mint::assert_no_soft_failures`] to panic if any failed. Updating
+    /// still happens on drop when `UPDATE_GOLDENFILES=1`.
+    pub fn soft_check(&mut self) -> &[CheckError] {
+        let failures = self
+            .files
+            .iter()
+            .filter_map(|goldenfile| {
+                let result = self.check_goldenfile(goldenfile, || self.report_change(goldenfile));
+                result.err().map(|failure| check_error(goldenfile, failure))
+            })
+            .chain(self.golden_dirs.iter().filter_map(|dir| {
+                let err = self.check_golden_dir(dir, true)?;
+                log_diff(&dir.path, &err.to_string());
+                Some(err)
+            }))
+            .collect();
+        self.soft_failures = failures;
+        self.soft_checked = (self.files.len(), self.golden_dirs.len());
+        &self.soft_failures
+    }
+
+    /// Panic listing every failure recorded by the last [`Mint::soft_check`],
+    /// if there were any.
+    pub fn assert_no_soft_failures(&self) {
+        let failures = &self.soft_failures;
+        if !failures.is_empty() {
+            let mut message = format!("{} goldenfile(s) failed:", failures.len());
+            for failure in failures {
+                message.push_str(&format!("\n  {}", failure));
+            }
+            print_update_hint();
+            panic!("{}", message);
+        }
+    }
+
+    /// List the goldenfiles whose new contents differ from their old contents,
+    /// without panicking or writing anything.
+    ///
+    /// Each differ still prints its diff. Called automatically when a Mint goes
+    /// out of scope and `GOLDENFILE_DRYRUN=1`.
+    pub fn dry_run_goldenfiles(&self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+        for goldenfile in &self.files {
+            if let Err(failure) = self.diff_goldenfile(goldenfile, || {}) {
+                failure.print();
+                println!("Would update {:?}.", goldenfile.path.to_str().unwrap());
+                changed.push(goldenfile.path.clone());
+            }
+        }
+        for dir in &self.golden_dirs {
+            if self.check_golden_dir(dir, false).is_some() {
+                println!("Would update {:?}.", dir.path.to_str().unwrap());
+                changed.push(dir.path.clone());
+            }
+        }
+        changed
+    }
+
+    /// Write the new contents of every goldenfile that changed into `dir`,
+    /// mirroring where the goldenfiles are stored, instead of overwriting them.
+    /// Returns the proposed files' paths.
+    ///
+    /// The proposals can then be reviewed and copied over the goldenfiles. A
+    /// goldenfile that updating would delete is proposed as an empty
+    /// `<name>.deleted` marker rather than as an empty file.
+    ///
+    /// Called automatically instead of [`Mint::update_goldenfiles`] when a Mint
+    /// goes out of scope, `UPDATE_GOLDENFILES=1` and
+    /// `GOLDENFILE_PROPOSE_DIR` is set.
+    pub fn propose_goldenfiles<P: AsRef<Path>>(&self, dir: P) -> Vec<PathBuf> {
+        let dir = dir.as_ref();
+        let mut proposed = vec![];
+        for goldenfile in &self.files {
+            if self.diff_goldenfile(goldenfile, || {}).is_ok() {
+                continue;
+            }
+            let relative = goldenfile
+                .old
+                .strip_prefix(&self.path)
+                .unwrap_or(&goldenfile.old);
+            // Goldenfiles outside the Mint keep their absolute path below `dir`.
+            let relative: PathBuf = relative
+                .components()
+                .filter(|c| matches!(c, Component::Normal(_) | Component::ParentDir))
+                .collect();
+            let mut path = dir.join(relative);
+            let empty = fs::metadata(&goldenfile.new).map_or(true, |meta| meta.len() == 0);
+            let deleted = goldenfile.expect_missing || (!self.create_empty && empty);
+            if deleted && !goldenfile.old.exists() {
+                continue;
+            }
+            if deleted {
+                path.as_mut_os_string().push(".deleted");
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap_or_else(|err| {
+                    panic!("Failed to create directory {:?}: {:?}", parent, err)
+                });
+            }
+            if deleted {
+                fs::write(&path, "")
+                    .unwrap_or_else(|err| panic!("Error writing {:?}: {:?}", path, err));
+            } else {
+                fs::copy(&goldenfile.new, &path).unwrap_or_else(|err| {
+                    panic!(
+                        "Error copying {:?} to {:?}: {:?}",
+                        goldenfile.new, path, err
+                    )
+                });
+            }
+            proposed.push((path, deleted));
+        }
+        println!(
+            "goldenfiles: proposed {} updates in {:?}",
+            proposed.len(),
+            dir
+        );
+        for (path, deleted) in &proposed {
+            if *deleted {
+                println!("  {:?} (delete)", path);
+            } else {
+                println!("  {:?}", path);
+            }
+        }
+        proposed.into_iter().map(|(path, _)| path).collect()
+    }
+
+    /// Append a unified diff of every goldenfile that changed to `patch`,
+    /// instead of overwriting them, and return the goldenfiles' paths.
+    ///
+    /// Paths in the patch are relative to the Mint's directory, under `a/` and
+    /// `b/`, so running `git apply` there, or `git apply --directory=<dir>` from
+    /// the repository root, applies the update. Goldenfiles a path resolver
+    /// put elsewhere are relative to the current directory instead. Created
+    /// and deleted goldenfiles are marked as new and deleted files.
+    /// Goldenfiles that aren't valid UTF-8 are skipped with a warning. The
+    /// patch is appended to so the Mints of a whole test run can share it;
+    /// remove it before a run to start afresh.
+    ///
+    /// Called automatically instead of [`Mint::update_goldenfiles`] when a Mint
+    /// goes out of scope, `UPDATE_GOLDENFILES=1` and `GOLDENFILE_PATCH` is
+    /// set to the patch's path.
+    pub fn write_patch<P: AsRef<Path>>(&self, patch: P) -> Vec<PathBuf> {
+        let patch = patch.as_ref();
+        let mut text = String::new();
+        let mut patched = vec![];
+        let mut seen = HashSet::new();
+        for goldenfile in &self.files {
+            if !seen.insert(&goldenfile.old) || self.diff_goldenfile(goldenfile, || {}).is_ok() {
+                continue;
+            }
+            let new_bytes = fs::read(&goldenfile.new).unwrap_or_default();
+            let deleted = goldenfile.expect_missing || (!self.create_empty && new_bytes.is_empty());
+            // Missing contents are None, and contents that aren't UTF-8 an error.
+            let decode = |bytes: Option<Vec<u8>>| bytes.map(String::from_utf8).transpose();
+            let old_text = decode(fs::read(&goldenfile.old).ok());
+            let new_text = decode((!deleted).then_some(new_bytes));
+            let (Ok(old_text), Ok(new_text)) = (old_text, new_text) else {
+                eprintln!(
+                    "warning: skipping binary goldenfile {:?} in patch",
+                    goldenfile.path
+                );
+                continue;
+            };
+            if old_text == new_text {
+                continue;
+            }
+            let name = patch_path(&self.path, &goldenfile.old);
+            let (a, b) = (format!("a/{}", name), format!("b/{}", name));
+            text.push_str(&format!("diff --git {} {}\n", a, b));
+            let (a, b) = match (&old_text, &new_text) {
+                (None, _) => {
+                    text.push_str("new file mode 100644\n");
+                    ("/dev/null", b.as_str())
+                }
+                (_, None) => {
+                    text.push_str("deleted file mode 100644\n");
+                    (a.as_str(), "/dev/null")
+                }
+                _ => (a.as_str(), b.as_str()),
+            };
+            let (old_text, new_text) = (old_text.unwrap_or_default(), new_text.unwrap_or_default());
+            let diff = TextDiff::from_lines(&old_text, &new_text);
+            text.push_str(&diff.unified_diff().header(a, b).to_string());
+            patched.push(goldenfile.path.clone());
+        }
+        if !text.is_empty() {
+            let _guard = PATCH_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(patch)
+                .and_then(|mut file| file.write_all(text.as_bytes()))
+                .unwrap_or_else(|err| panic!("Error writing patch {:?}: {:?}", patch, err));
+        }
+        println!(
+            "goldenfiles: wrote {} updates to patch {:?}",
+            patched.len(),
+            patch
+        );
+        patched
+    }
+
+    /// The differ inferred from `path`'s extension, printing which one it is
+    /// if verbose or `GOLDENFILE_LOG_DIFFERS=1`.
+    fn infer_differ(&self, path: &Path) -> AnyDiffer {
+        let (name, differ) = inferred_differ(path);
+        if self.verbose || log_differs_mode() {
+            eprintln!("goldenfiles: comparing {:?} with {}", path, name);
+        }
+        AnyDiffer::Outcome(differ)
+    }
+
+    /// Print that a goldenfile changed.
+    fn report_change(&self, goldenfile: &Goldenfile) {
+        report_change(&goldenfile.path, &goldenfile.old, self.github_annotations);
+        if let Some(canonical) = &goldenfile.shared {
+            eprintln!("  compared with shared goldenfile {:?}", canonical);
+        }
+    }
+
+    /// Check a goldenfile like [`Mint::diff_goldenfile`], except that a
+    /// lenient goldenfile's mismatch is printed as a warning and passes.
+    fn check_goldenfile(
+        &self,
+        goldenfile: &Goldenfile,
+        on_change: impl FnOnce(),
+    ) -> std::result::Result<(), Failure> {
+        if !goldenfile.lenient {
+            let result = self.diff_regenerating(goldenfile, on_change);
+            if let Err(failure) = &result {
+                log_diff(&goldenfile.path, &failure.message());
+            }
+            return result;
+        }
+        if let Err(failure) = self.diff_regenerating(goldenfile, || {}) {
+            let message = failure.message();
+            log_diff(&goldenfile.path, &message);
+            eprintln!(
+                "{}: lenient goldenfile changed, not failing: {}\n{}",
+                "warning".bold().yellow(),
+                goldenfile.path.to_str().unwrap(),
+                message
+            );
+            if self.github_annotations {
+                println!(
+                    "::warning file={}::lenient goldenfile changed",
+                    annotation_file(&goldenfile.old)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a goldenfile's differ like [`Mint::diff_goldenfile`], rerunning its
+    /// generator on a mismatch if it was registered with
+    /// [`Mint::register_goldenfile_with_regen`]. `on_change` is only called
+    /// if every attempt fails.
+    fn diff_regenerating(
+        &self,
+        goldenfile: &Goldenfile,
+        on_change: impl FnOnce(),
+    ) -> std::result::Result<(), Failure> {
+        let Some(regen) = goldenfile.regen.as_ref().filter(|regen| regen.attempts > 0) else {
+            return self.diff_goldenfile(goldenfile, on_change);
+        };
+        let path = goldenfile.path.to_str().unwrap();
+        let total = regen.attempts + 1;
+        let mut messages = vec![];
+        for attempt in 1..=total {
+            if attempt > 1 {
+                let regenerated =
+                    File::create(&goldenfile.new).and_then(|mut file| (regen.generate)(&mut file));
+                if let Err(err) = regenerated {
+                    messages.push(format!("regenerating failed: {}", err));
+                    continue;
+                }
+            }
+            match self.diff_goldenfile(goldenfile, || {}) {
+                Ok(()) => {
+                    if attempt > 1 {
+                        eprintln!(
+                            "{}: flaky goldenfile matched on attempt {} of {}: {}",
+                            "warning".bold().yellow(),
+                            attempt,
+                            total,
+                            path
+                        );
+                    }
+                    return Ok(());
+                }
+                Err(failure) => messages.push(failure.message()),
+            }
+        }
+        on_change();
+        let mut report = format!("{}: none of {} attempts matched", path, total);
+        for (i, message) in messages.iter().enumerate() {
+            report.push_str(&format!("\nattempt {}: {}", i + 1, message));
+        }
+        Err(Failure::Changed(report))
+    }
+
+    /// Run a goldenfile's differ, calling `on_change` if it fails.
+    fn diff_goldenfile(
+        &self,
+        goldenfile: &Goldenfile,
+        on_change: impl FnOnce(),
+    ) -> std::result::Result<(), Failure> {
+        let mut old = &goldenfile.old;
+        if let Some(moved_from) = goldenfile.moved_from.as_ref().filter(|_| !old.exists()) {
+            old = moved_from;
+        }
+        let cache = self.hash_cache.as_ref();
+        if cache.is_some_and(|cache| cache.is_current(old, &goldenfile.new)) {
+            return Ok(());
+        }
+        let result = self.diff_goldenfile_against(goldenfile, old, old, on_change);
+        if let (Some(cache), Ok(())) = (cache, &result) {
+            cache.record(old, &goldenfile.new);
+        }
+        result
+    }
+
+    /// Run a goldenfile's differ with `old` as its old contents, using the
+    /// `.goldencfg` sidecar of `configured`.
+    fn diff_goldenfile_against(
+        &self,
+        goldenfile: &Goldenfile,
+        old: &Path,
+        configured: &Path,
+        on_change: impl FnOnce(),
+    ) -> std::result::Result<(), Failure> {
+        let result = self.compare_goldenfile(goldenfile, old, configured);
+        if result.is_err() {
+            on_change();
+        }
+        result
+    }
+
+    /// Compare a goldenfile's new contents with `old`, as for
+    /// [`Mint::diff_goldenfile_against`].
+    fn compare_goldenfile(
+        &self,
+        goldenfile: &Goldenfile,
+        old: &Path,
+        configured: &Path,
+    ) -> std::result::Result<(), Failure> {
+        let new = &goldenfile.new;
+        if goldenfile.expect_missing {
+            let len = fs::metadata(new).map_or(0, |meta| meta.len());
+            if len > 0 {
+                return Err(Failure::Changed(format!(
+                    "{}: expected no goldenfile, but {} bytes were written",
+                    goldenfile.path.display(),
+                    len
+                )));
+            }
+            return Ok(());
+        }
+        if self.forbid_empty && fs::metadata(new).map_or(true, |meta| meta.len() == 0) {
+            return Err(Failure::Changed(format!(
+                "{}: goldenfile was never written to",
+                goldenfile.path.display()
+            )));
+        }
+        let is_empty = |path: &Path| fs::metadata(path).map_or(true, |meta| meta.len() == 0);
+        if self.treat_empty_as_equal && is_empty(new) && is_empty(old) {
+            return Ok(());
+        }
+        if self.compare_symlinks
+            && diff_symlinks(&goldenfile.path, old, new).map_err(Failure::Changed)?
+        {
+            return Ok(());
+        }
+        let header = self.header.as_ref().filter(|_| !goldenfile.binary);
+        let sidecar = match goldenfile.inferred {
+            true => sidecar_differ(configured, goldenfile.binary)?,
+            false => None,
+        };
+        let is_json = goldenfile.old.extension().is_some_and(|ext| ext == "json");
+        let json = (goldenfile.inferred && self.canonicalize_json && is_json)
+            .then(|| canonical_json(new).map(|_| AnyDiffer::Outcome(Box::new(json_outcome))))
+            .flatten();
+        let differ = sidecar
+            .as_ref()
+            .or(json.as_ref())
+            .unwrap_or(&goldenfile.differ);
+        run_differ(old, new, differ, header)
+    }
+
+    /// Check that updating would be a no-op the second time: that every
+    /// goldenfile's new contents match themselves as its old contents.
+    ///
+    /// Nothing is written. A differ that fails here normalizes the two sides
+    /// differently, so checks would keep failing right after an update. Panic
+    /// listing every such goldenfile.
+    pub fn verify_update_idempotent(&self) {
+        let unstable: Vec<&str> = self
+            .files
+            .iter()
+            .filter(|goldenfile| {
+                !goldenfile.schema
+                    && self
+                        .diff_goldenfile_against(
+                            goldenfile,
+                            &goldenfile.new,
+                            &goldenfile.old,
+                            || {},
+                        )
+                        .inspect_err(Failure::print)
+                        .is_err()
+            })
+            .map(|goldenfile| goldenfile.path.to_str().unwrap())
+            .collect();
+        if !unstable.is_empty() {
+            panic!(
+                "goldenfiles would still differ after updating: {}",
+                unstable.join(", ")
+            );
+        }
+    }
+
+    /// Panic if the new goldenfiles are larger in total than the limit set by
+    /// [`Mint::max_total_bytes`], counting writes that bypassed its writers.
+    fn check_total_bytes(&self) {
+        if self.budget.limit.get().is_none() {
+            return;
+        }
+        let measured: u64 = self
+            .files
+            .iter()
+            .filter_map(|goldenfile| fs::metadata(&goldenfile.new).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        self.budget.check(measured.max(self.budget.written.get()));
+    }
+
+    /// Overwrite old goldenfile contents with their new contents.
+    ///
+    /// Called automatically when a Mint goes out of scope and
+    /// `UPDATE_GOLDENFILES=1`.
+    pub fn update_goldenfiles(&self) {
+        self.check_total_bytes();
+        let (mut updated, mut created, mut deleted) = (0, 0, 0);
+        let shared = self.check_shared_updates();
+        for (
+            i,
+            Goldenfile {
+                path: file,
+                old,
+                new,
+                moved_from,
+                preserve_encoding,
+                expect_missing,
+                schema,
+                ..
+            },
+        ) in self.files.iter().enumerate()
+        {
+            if *schema {
+                println!(
+                    "Not updating {:?}: it is a JSON Schema.",
+                    file.to_str().unwrap()
+                );
+                continue;
+            }
+            if *expect_missing {
+                if old.exists() {
+                    println!("Removing {:?}.", file.to_str().unwrap());
+                    fs::remove_file(old).unwrap();
+                    deleted += 1;
+                }
+                continue;
+            }
+            if shared.get(old.as_path()).is_some_and(|&first| first != i) {
+                continue;
+            }
+            if let Some(moved_from) = moved_from {
+                if let Some(parent) = old.parent() {
+                    fs::create_dir_all(parent).unwrap_or_else(|err| {
+                        panic!("Failed to create directory {:?}: {:?}", parent, err)
+                    });
+                }
+                if moved_from.exists() {
+                    println!("Removing {:?}.", moved_from);
+                    fs::remove_file(moved_from).unwrap();
+                }
+            }
+            if self.compare_symlinks {
+                let existed = fs::symlink_metadata(old).is_ok();
+                let target = fs::read_link(new);
+                // Replace symlinks rather than copying through them.
+                if existed && (target.is_ok() || is_symlink(old)) {
+                    fs::remove_file(old).unwrap();
+                }
+                if let Ok(target) = target {
+                    println!("Updating {:?}.", file.to_str().unwrap());
+                    if existed {
+                        updated += 1;
+                    } else {
+                        created += 1;
+                    }
+                    create_symlink(&target, old).unwrap_or_else(|err| {
+                        panic!("Error linking {:?} to {:?}: {:?}", old, target, err)
+                    });
+                    continue;
+                }
+            }
+            let empty = File::open(new).unwrap().metadata().unwrap().len() == 0;
+            if self.create_empty || !empty {
+                println!("Updating {:?}.", file.to_str().unwrap());
+                if old.exists() {
+                    updated += 1;
+                } else {
+                    created += 1;
+                }
+                let is_json = old.extension().is_some_and(|ext| ext == "json");
+                let canonical = (self.canonicalize_json && is_json)
+                    .then(|| canonical_json(new))
+                    .flatten();
+                if *preserve_encoding && old.exists() {
+                    reencode(new, old);
+                } else if let Some(canonical) = canonical {
+                    fs::write(old, canonical)
+                        .unwrap_or_else(|err| panic!("Error writing {:?}: {:?}", old, err));
+                } else {
+                    fs::copy(new, old).unwrap_or_else(|err| {
+                        panic!("Error copying {:?} to {:?}: {:?}", new, old, err)
+                    });
+                }
+            } else if old.exists() {
+                std::fs::remove_file(old).unwrap();
+                deleted += 1;
+            }
+        }
+        for dir in &self.golden_dirs {
+            let counts = self.update_golden_dir(dir);
+            updated += counts.0;
+            created += counts.1;
+            deleted += counts.2;
+        }
+        if let Some(state) = &self.store {
+            state.write_back();
+        }
+        if self.verbose {
+            println!(
+                "goldenfiles: {} updated, {} created, {} deleted",
+                updated, created, deleted
+            );
+        }
+    }
+
+    /// Check that goldenfiles sharing a canonical goldenfile agree on its new
+    /// contents, returning the index of the first to share each one.
+    fn check_shared_updates(&self) -> HashMap<&Path, usize> {
+        let read = |goldenfile: &Goldenfile| {
+            fs::read(&goldenfile.new).unwrap_or_else(|err| {
+                panic!(
+                    "Error reading new contents of {:?} for shared goldenfile {:?}: {:?}",
+                    goldenfile.path, goldenfile.old, err
+                )
+            })
+        };
+        let mut first_sharers: HashMap<&Path, usize> = HashMap::new();
+        for (i, goldenfile) in self.files.iter().enumerate() {
+            if goldenfile.shared.is_none() {
+                continue;
+            }
+            let first = *first_sharers.entry(&goldenfile.old).or_insert(i);
+            let first = &self.files[first];
+            if read(first) != read(goldenfile) {
+                panic!(
+                    "Shared goldenfile {:?} has conflicting new contents from {:?} and {:?}",
+                    goldenfile.old, first.path, goldenfile.path
+                );
+            }
+        }
+        first_sharers
+    }
+
+    /// Register a new goldenfile using a differ inferred from the file extension.
+    ///
+    /// Like [`Mint::new_goldenfile`], a `.goldencfg` sidecar may override the
+    /// differ.
+    ///
+    /// The returned PathBuf references a temporary file, not the goldenfile itself.
+    pub fn register_goldenfile<P: AsRef<Path>>(&mut self, path: P) -> Result<PathBuf> {
+        let abs_path =
+            self.register_goldenfile_comparing(&path, self.infer_differ(path.as_ref()))?;
+        self.files.last_mut().unwrap().inferred = true;
+        Ok(abs_path)
+    }
+
+    /// Register a new goldenfile with the specified diff function.
+    ///
+    /// The returned PathBuf references a temporary file, not the goldenfile itself.
+    pub fn register_goldenfile_with_differ<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        differ: Differ,
+    ) -> Result<PathBuf> {
+        self.register_goldenfile_comparing(path, AnyDiffer::Custom(differ))
+    }
+
+    /// Register a new goldenfile compared with `differ`.
+    fn register_goldenfile_comparing<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        differ: AnyDiffer,
+    ) -> Result<PathBuf> {
+        if !path.as_ref().is_relative() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Path must be relative.",
+            ));
+        }
+
+        let abs_path = self.tempdir.path().to_path_buf().join(path.as_ref());
+        self.files.push(Goldenfile {
+            path: path.as_ref().to_path_buf(),
+            old: self.golden_path(path.as_ref()),
+            new: abs_path.clone(),
+            moved_from: None,
+            differ: Rc::new(differ),
+            binary: has_binary_extension(path.as_ref()),
+            inferred: false,
+            shared: None,
+            preserve_encoding: false,
+            expect_missing: false,
+            lenient: false,
+            regen: None,
+            schema: false,
+        });
+        Ok(abs_path)
+    }
+
+    /// Register a goldenfile that is allowed to be flaky, like
+    /// [`Mint::register_goldenfile`], but whose mismatches print a warning
+    /// instead of failing the test.
+    ///
+    /// The warning names the goldenfile and includes the differ's message
+    /// every time it is checked, and with [`Mint::set_github_annotations`] is
+    /// also a warning annotation, so known-flaky snapshots stay visible until
+    /// they're stabilized. Updating still writes the new contents.
+    ///
+    /// The returned PathBuf references a temporary file, not the goldenfile itself.
+    pub fn register_goldenfile_lenient<P: AsRef<Path>>(&mut self, path: P) -> Result<PathBuf> {
+        let abs_path = self.register_goldenfile(path)?;
+        self.files.last_mut().unwrap().lenient = true;
+        Ok(abs_path)
+    }
+
+    /// Register a goldenfile whose generator is flaky, like
+    /// [`Mint::register_goldenfile`], but rerun `regen` up to `attempts` times
+    /// if the new contents don't match.
+    ///
+    /// This is an escape hatch for nondeterministic output that settles on
+    /// its canonical form when generated again, and can hide real flakiness:
+    /// prefer making the generator deterministic. Each rerun writes to a
+    /// truncated file at the returned path, and checking passes as soon as an
+    /// attempt matches, with a warning naming the attempt. If none do, the
+    /// check fails listing every attempt's message. With `attempts` of 0 it
+    /// behaves like [`Mint::register_goldenfile`]. Updating writes the first
+    /// output without rerunning anything.
+    ///
+    /// The returned PathBuf references a temporary file, not the goldenfile itself.
+    pub fn register_goldenfile_with_regen<P, F>(
+        &mut self,
+        path: P,
+        regen: F,
+        attempts: usize,
+    ) -> Result<PathBuf>
+    where
+        P: AsRef<Path>,
+        F: Fn(&mut File) -> Result<()> + 'static,
+    {
+        let abs_path = self.register_goldenfile(path)?;
+        self.files.last_mut().unwrap().regen = Some(Regen {
+            generate: Box::new(regen),
+            attempts,
+        });
+        Ok(abs_path)
+    }
+
+    /// Register a goldenfile whose golden is a JSON Schema, returning the
+    /// temporary path to write a JSON document to.
+    ///
+    /// Checking validates the new document against the schema as
+    /// [`json_schema_diff`] does, failing with every validation error, so the
+    /// schema acts as a contract rather than a snapshot. Updating leaves the
+    /// schema alone and prints a note instead.
+    ///
+    /// Requires the `json-schema` feature.
+    #[cfg(feature = "json-schema")]
+    pub fn register_schema_goldenfile<P: AsRef<Path>>(&mut self, path: P) -> Result<PathBuf> {
+        let abs_path = self.register_goldenfile_comparing(
+            path,
+            AnyDiffer::Outcome(Box::new(schema_golden_outcome)),
+        )?;
+        self.files.last_mut().unwrap().schema = true;
+        Ok(abs_path)
+    }
+
+    /// Register a goldenfile that should not be produced, returning the
+    /// temporary path it would be written to.
+    ///
+    /// Checking passes if nothing, or only an empty file, is written to the
+    /// returned path. When updating, any stale goldenfile at `path` is removed.
+    pub fn assert_no_goldenfile<P: AsRef<Path>>(&mut self, path: P) -> Result<PathBuf> {
+        let matching = AnyDiffer::Outcome(Box::new(|_, _| DiffOutcome::Match));
+        let abs_path = self.register_goldenfile_comparing(path, matching)?;
+        self.files.last_mut().unwrap().expect_missing = true;
+        Ok(abs_path)
+    }
+
+    /// Move a registered goldenfile from `old_path` to `new_path`, both relative
+    /// to the Mint.
+    ///
+    /// When updating, the new contents are written to `new_path`, overwriting
+    /// any goldenfile already there, and the goldenfile at `old_path` is
+    /// removed. When checking, the new contents are compared with the
+    /// goldenfile at `new_path` if it exists, and otherwise with the one at
+    /// `old_path`, so checks pass before the move has been applied.
+    pub fn rename_goldenfile<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        old_path: P,
+        new_path: Q,
+    ) -> Result<()> {
+        let (old_path, new_path) = (old_path.as_ref(), new_path.as_ref());
+        if !new_path.is_relative() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Path must be relative.",
+            ));
+        }
+        if self
+            .files
+            .iter()
+            .any(|goldenfile| goldenfile.path == new_path)
         {
-            self.update_goldenfiles();
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("A goldenfile is already registered at {:?}.", new_path),
+            ));
+        }
+        let new_old = self.golden_path(new_path);
+        let goldenfile = self
+            .files
+            .iter_mut()
+            .find(|goldenfile| goldenfile.path == old_path)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("No goldenfile is registered at {:?}.", old_path),
+                )
+            })?;
+        goldenfile.path = new_path.to_path_buf();
+        let previous = std::mem::replace(&mut goldenfile.old, new_old);
+        goldenfile.moved_from.get_or_insert(previous);
+        Ok(())
+    }
+
+    /// Register a file the test produces itself, whose golden is stored beside
+    /// it with a `.golden` suffix, e.g. `out/report.txt.golden`.
+    ///
+    /// The produced file is compared in place, using a differ inferred from its
+    /// own extension, and updating copies it over the golden. Paths are used as
+    /// given rather than relative to the Mint. Returns the golden's path.
+    pub fn register_golden_sibling<P: AsRef<Path>>(&mut self, produced_path: P) -> PathBuf {
+        let produced = produced_path.as_ref();
+        let mut golden = produced.as_os_str().to_owned();
+        golden.push(".golden");
+        let golden = PathBuf::from(golden);
+        let differ = self.infer_differ(produced);
+        self.files.push(Goldenfile {
+            path: golden.clone(),
+            old: golden.clone(),
+            new: produced.to_path_buf(),
+            moved_from: None,
+            differ: Rc::new(differ),
+            binary: has_binary_extension(produced),
+            inferred: true,
+            shared: None,
+            preserve_encoding: false,
+            expect_missing: false,
+            lenient: false,
+            regen: None,
+            schema: false,
+        });
+        golden
+    }
+
+    /// Register a new goldenfile whose old contents are stored in `canonical`,
+    /// a goldenfile relative to the Mint that other tests may share.
+    ///
+    /// Deduplicated fixtures can then keep one copy of a large expected output.
+    /// The differ is inferred from the canonical goldenfile's extension. When
+    /// updating, the canonical goldenfile is written once however many
+    /// goldenfiles share it, and sharing goldenfiles with different new
+    /// contents is an error. Failures name the canonical goldenfile.
+    ///
+    /// The returned PathBuf references a temporary file, not the goldenfile itself.
+    pub fn register_shared_golden<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        path: P,
+        canonical: Q,
+    ) -> Result<PathBuf> {
+        let canonical = canonical.as_ref();
+        if !canonical.is_relative() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Path must be relative.",
+            ));
+        }
+        let abs_path = self.register_goldenfile_comparing(path, self.infer_differ(canonical))?;
+        let old = self.golden_path(canonical);
+        let goldenfile = self.files.last_mut().unwrap();
+        goldenfile.old = old;
+        goldenfile.binary = has_binary_extension(canonical);
+        goldenfile.inferred = true;
+        goldenfile.shared = Some(canonical.to_path_buf());
+        Ok(abs_path)
+    }
+
+    /// Register a directory of goldenfiles, returning the temporary directory
+    /// to write the whole tree to, and skipping paths that match any of
+    /// `exclude_globs`.
+    ///
+    /// When the Mint finishes, every file in the tree is compared with the
+    /// file at the same path under `dir`, with a differ inferred from its
+    /// extension, and files present in only one tree are failures. Updating
+    /// copies the tree over `dir` and removes goldenfiles that are no longer
+    /// produced. Like a goldenfile's, the directory's location is computed by
+    /// the resolver set with [`Mint::set_path_resolver`], if any.
+    ///
+    /// Globs match paths relative to `dir`: `*` matches within a component,
+    /// `?` matches one character and `**` matches any number of directories. A
+    /// glob without a `/`, like `*.log`, matches any component, so it also
+    /// skips everything under a matching directory. A path is skipped if it
+    /// matches any glob, so their order doesn't matter. Excluded goldenfiles
+    /// are neither checked nor removed or overwritten.
+    pub fn golden_dir_with_excludes<P: AsRef<Path>, S: AsRef<str>>(
+        &mut self,
+        dir: P,
+        exclude_globs: &[S],
+    ) -> Result<PathBuf> {
+        let dir = dir.as_ref();
+        if !dir.is_relative() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Path must be relative.",
+            ));
+        }
+        if self.store.is_some() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "Golden directories aren't supported with a GoldenStore.",
+            ));
+        }
+        let abs_path = self.tempdir.path().join(dir);
+        fs::create_dir_all(&abs_path)?;
+        self.golden_dirs.push(GoldenDir {
+            path: dir.to_path_buf(),
+            old: self.golden_path(dir),
+            excludes: exclude_globs
+                .iter()
+                .map(|g| g.as_ref().to_string())
+                .collect(),
+        });
+        Ok(abs_path)
+    }
+
+    /// Compare a golden directory with the tree written to its temporary
+    /// directory, returning the failure if they differ. If `report`, also
+    /// print that it changed.
+    fn check_golden_dir(&self, dir: &GoldenDir, report: bool) -> Option<CheckError> {
+        let old_root = &dir.old;
+        let new_root = self.tempdir.path().join(&dir.path);
+        let mut differences = diff_trees_where(
+            old_root,
+            &new_root,
+            &|file| AnyDiffer::Outcome(inferred_differ(file).1),
+            &|file| dir.includes(file),
+        );
+        // A golden directory that doesn't exist yet is empty.
+        differences.retain(|difference| {
+            !matches!(difference, TreeDifference::Io { file, error }
+                if file.as_os_str().is_empty() && error.kind() == ErrorKind::NotFound)
+        });
+        if differences.is_empty() {
+            return None;
+        }
+        if report {
+            report_change(&dir.path, old_root, self.github_annotations);
+        }
+        let mut message = format!("golden directory {:?} differs", dir.path);
+        for difference in differences {
+            let line = match difference {
+                TreeDifference::Io { file, error } => {
+                    let file = dir.path.join(file);
+                    return Some(CheckError::Io { file, error });
+                }
+                TreeDifference::OnlyInA(file) => format!("no longer produced: {:?}", file),
+                TreeDifference::OnlyInB(file) => format!("new file: {:?}", file),
+                TreeDifference::Changed { file, .. } => format!("changed: {:?}", file),
+            };
+            message.push_str("\n  ");
+            message.push_str(&line);
+        }
+        Some(CheckError::Changed {
+            file: dir.path.clone(),
+            message,
+        })
+    }
+
+    /// Copy a golden directory's new tree over its goldenfiles, removing the
+    /// ones that are no longer produced. Returns the number updated, created
+    /// and deleted.
+    fn update_golden_dir(&self, dir: &GoldenDir) -> (usize, usize, usize) {
+        let (mut updated, mut created, mut deleted) = (0, 0, 0);
+        let old_root = &dir.old;
+        let new_root = self.tempdir.path().join(&dir.path);
+        let (mut old_files, mut new_files) = (vec![], vec![]);
+        if old_root.exists() {
+            list_files(old_root, Path::new(""), &mut old_files)
+                .unwrap_or_else(|(file, err)| panic!("Error listing {:?}: {:?}", file, err));
+        }
+        list_files(&new_root, Path::new(""), &mut new_files)
+            .unwrap_or_else(|(file, err)| panic!("Error listing {:?}: {:?}", file, err));
+        for file in old_files.iter().filter(|file| dir.includes(file)) {
+            if !new_files.contains(file) {
+                println!("Removing {:?}.", dir.path.join(file));
+                fs::remove_file(old_root.join(file)).unwrap();
+                deleted += 1;
+            }
+        }
+        for file in new_files.iter().filter(|file| dir.includes(file)) {
+            let (old, new) = (old_root.join(file), new_root.join(file));
+            println!("Updating {:?}.", dir.path.join(file));
+            if old.exists() {
+                updated += 1;
+            } else {
+                created += 1;
+            }
+            if let Some(parent) = old.parent() {
+                fs::create_dir_all(parent).unwrap_or_else(|err| {
+                    panic!("Failed to create directory {:?}: {:?}", parent, err)
+                });
+            }
+            fs::copy(&new, &old)
+                .unwrap_or_else(|err| panic!("Error copying {:?} to {:?}: {:?}", new, old, err));
+        }
+        (updated, created, deleted)
+    }
+}
+
+/// A platform targeted by [`Mint::new_goldenfile_platform`] transforms.
+///
+/// Platforms are detected at compile time with `cfg!`, so they describe the
+/// target the tests were built for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    /// `cfg!(windows)`.
+    Windows,
+    /// `cfg!(unix)`, which includes Linux and macOS.
+    Unix,
+    /// `cfg!(target_os = "linux")`.
+    Linux,
+    /// `cfg!(target_os = "macos")`.
+    MacOs,
+}
+
+impl Platform {
+    /// Whether the tests were compiled for this platform.
+    pub fn is_current(self) -> bool {
+        match self {
+            Platform::Windows => cfg!(windows),
+            Platform::Unix => cfg!(unix),
+            Platform::Linux => cfg!(target_os = "linux"),
+            Platform::MacOs => cfg!(target_os = "macos"),
+        }
+    }
+}
+
+/// A text transform applied by [`Mint::new_goldenfile_platform`] on one
+/// platform.
+pub type PlatformTransform = (Platform, Box<dyn Fn(&str) -> String>);
+
+/// Why [`Mint::try_check_goldenfiles`] failed.
+#[derive(Debug)]
+pub enum CheckError {
+    /// A goldenfile could not be read.
+    Io {
+        /// The goldenfile's path relative to the Mint.
+        file: PathBuf,
+        /// The underlying error.
+        error: Error,
+    },
+    /// A goldenfile's new contents differ from its old contents.
+    Changed {
+        /// The goldenfile's path relative to the Mint.
+        file: PathBuf,
+        /// The differ's panic message.
+        message: String,
+    },
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Io { file, error } => {
+                write!(f, "Error reading goldenfile {:?}: {}", file, error)
+            }
+            CheckError::Changed { file, message } => {
+                write!(f, "goldenfile changed: {:?}: {}", file, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+/// A difference between two directory trees found by [`diff_trees`].
+#[derive(Debug)]
+pub enum TreeDifference {
+    /// A file only present in the first tree.
+    OnlyInA(PathBuf),
+    /// A file only present in the second tree.
+    OnlyInB(PathBuf),
+    /// A file that could not be read.
+    Io {
+        /// The file's path relative to both trees.
+        file: PathBuf,
+        /// The underlying error.
+        error: Error,
+    },
+    /// A file whose contents differ between the trees.
+    Changed {
+        /// The file's path relative to both trees.
+        file: PathBuf,
+        /// The differ's panic message.
+        message: String,
+    },
+}
+
+impl fmt::Display for TreeDifference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeDifference::OnlyInA(file) => write!(f, "only in first tree: {:?}", file),
+            TreeDifference::OnlyInB(file) => write!(f, "only in second tree: {:?}", file),
+            TreeDifference::Io { file, error } => {
+                write!(f, "Error reading {:?}: {}", file, error)
+            }
+            TreeDifference::Changed { file, message } => {
+                write!(f, "file differs: {:?}: {}", file, message)
+            }
+        }
+    }
+}
+
+/// Storage for golden contents, for goldens that don't live in a local
+/// directory. Used by [`Mint::with_store`].
+///
+/// Paths are the goldenfiles' registered paths, relative to the store's root.
+pub trait GoldenStore {
+    /// Read the golden at `path`. A missing golden is an error of kind
+    /// [`ErrorKind::NotFound`].
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    /// Write `contents` to the golden at `path`, replacing it.
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Remove the golden at `path`, for goldenfiles that are no longer
+    /// produced. Unsupported unless implemented.
+    fn remove(&self, path: &Path) -> Result<()> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("Can't remove {:?} from this store.", path),
+        ))
+    }
+}
+
+/// A [`GoldenStore`] keeping goldens in a local directory, as [`Mint::new`]
+/// does.
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    /// Create a store for goldens under `root`.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        FsStore {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl GoldenStore for FsStore {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        fs::read(self.root.join(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)
+    }
+
+    fn remove(&self, path: &Path) -> Result<()> {
+        fs::remove_file(self.root.join(path))
+    }
+}
+
+/// A [`GoldenStore`] keeping each distinct golden once, as a blob named by the
+/// hash of its contents, so identical goldens share storage. Use it with
+/// [`Mint::with_store`].
+///
+/// Blobs live in a `.golden-cas` directory under `root`, with an index mapping
+/// each golden's path to its blob. Writing a golden adds its blob if it's new
+/// and updates the index, and blobs that no golden refers to any more are
+/// removed. Writing fails rather than overwrite a blob whose contents differ
+/// from the golden's, in the unlikely event that the hashes collide.
+///
+/// Stores in one process may share a root, as parallel tests each with their
+/// own Mint do: the index is read afresh for every operation, and changes to
+/// it are serialized.
+///
+/// Requires the `cas` feature.
+#[cfg(feature = "cas")]
+pub struct CasStore {
+    root: PathBuf,
+}
+
+#[cfg(feature = "cas")]
+impl CasStore {
+    /// Create a store for goldens under `root`.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        CasStore {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.root.join(cas::DIR).join("blobs").join(hash)
+    }
+
+    fn load_index(&self) -> Result<BTreeMap<String, String>> {
+        let text = match fs::read_to_string(self.root.join(cas::DIR).join("index")) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        cas::parse_index(&text).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    /// Point `path` at the blob `hash`, or drop it from the index if `None`,
+    /// removing the blob it pointed at if nothing else does. The caller holds
+    /// `CAS_LOCK`.
+    fn set_blob(&self, path: &Path, hash: Option<String>) -> Result<()> {
+        let mut index = self.load_index()?;
+        let key = cas::key(path);
+        let previous = match hash {
+            Some(hash) => index.insert(key, hash),
+            None => index.remove(&key),
+        };
+        let dir = self.root.join(cas::DIR);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("index"), cas::format_index(&index))?;
+        if let Some(previous) = previous.filter(|previous| !index.values().any(|h| h == previous)) {
+            match fs::remove_file(self.blob_path(&previous)) {
+                Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cas")]
+impl GoldenStore for CasStore {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let _guard = CAS_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let hash = self.load_index()?.remove(&cas::key(path)).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("{:?} is not in the index", path),
+            )
+        })?;
+        fs::read(self.blob_path(&hash)).map_err(|err| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Blob {} for {:?} can't be read: {}", hash, path, err),
+            )
+        })
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let _guard = CAS_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let hash = cas::hash(contents);
+        let blob = self.blob_path(&hash);
+        match fs::read(&blob) {
+            Ok(existing) if existing != contents => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Blob {} for {:?} has other contents", hash, path),
+                ))
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                fs::create_dir_all(blob.parent().unwrap())?;
+                fs::write(&blob, contents)?;
+            }
+            Err(err) => return Err(err),
+        }
+        self.set_blob(path, Some(hash))
+    }
+
+    fn remove(&self, path: &Path) -> Result<()> {
+        let _guard = CAS_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        if !self.load_index()?.contains_key(&cas::key(path)) {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{:?} is not in the index", path),
+            ));
+        }
+        self.set_blob(path, None)
+    }
+}
+
+impl StoreState {
+    /// Copy the golden at `path` from the store into the staging directory,
+    /// once, returning where it was staged.
+    fn stage(&self, path: &Path) -> PathBuf {
+        let staged = self.staging.path().join(path);
+        let mut fetched = self.fetched.borrow_mut();
+        if fetched.contains_key(path) {
+            return staged;
+        }
+        let contents = match self.store.read(path) {
+            Ok(contents) => Some(contents),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => panic!("Error reading {:?} from golden store: {:?}", path, err),
+        };
+        if let Some(parent) = staged.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        if let Some(contents) = &contents {
+            fs::write(&staged, contents).unwrap();
+        }
+        fetched.insert(path.to_path_buf(), contents);
+        staged
+    }
+
+    /// Write staged goldenfiles that changed back to the store, and remove
+    /// those that were deleted.
+    fn write_back(&self) {
+        for (path, original) in self.fetched.borrow().iter() {
+            let staged = fs::read(self.staging.path().join(path)).ok();
+            if staged == *original {
+                continue;
+            }
+            let result = match &staged {
+                Some(contents) => self.store.write(path, contents),
+                None => self.store.remove(path),
+            };
+            result.unwrap_or_else(|err| {
+                panic!("Error writing {:?} to golden store: {:?}", path, err)
+            });
+        }
+    }
+}
+
+/// Compare every file in directory tree `a` with the file at the same path in
+/// `b`, returning the differences in path order.
+///
+/// Each pair of files is compared with the differ `differ_for` returns for its
+/// relative path, such as [`get_differ_for_path`], with the file from `a` as
+/// the old file. This compares two generators' outputs without an on-disk
+/// goldenfile. Files present in only one tree are reported rather than
+/// compared, and an unreadable directory is reported as an IO error.
+pub fn diff_trees<P, Q, F>(a: P, b: Q, differ_for: F) -> Vec<TreeDifference>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: Fn(&Path) -> Differ,
+{
+    let differ_for = |file: &Path| AnyDiffer::Custom(differ_for(file));
+    diff_trees_where(a.as_ref(), b.as_ref(), &differ_for, &|_| true)
+}
+
+/// Like [`diff_trees`], but skipping files for which `include` returns false.
+fn diff_trees_where(
+    a: &Path,
+    b: &Path,
+    differ_for: &dyn Fn(&Path) -> AnyDiffer,
+    include: &dyn Fn(&Path) -> bool,
+) -> Vec<TreeDifference> {
+    let mut differences = vec![];
+    let mut files = std::collections::BTreeMap::new();
+    for (root, in_a) in [(a, true), (b, false)] {
+        let mut found = vec![];
+        if let Err((file, error)) = list_files(root, Path::new(""), &mut found) {
+            differences.push(TreeDifference::Io { file, error });
+        }
+        for file in found.into_iter().filter(|file| include(file)) {
+            let entry = files.entry(file).or_insert((false, false));
+            if in_a {
+                entry.0 = true;
+            } else {
+                entry.1 = true;
+            }
+        }
+    }
+    for (file, presence) in files {
+        let difference = match presence {
+            (true, false) => TreeDifference::OnlyInA(file),
+            (false, true) => TreeDifference::OnlyInB(file),
+            _ => {
+                let differ = differ_for(&file);
+                let (old, new) = (a.join(&file), b.join(&file));
+                let Err(failure) = differ.run(&old, &new) else {
+                    continue;
+                };
+                failure.print();
+                match failure {
+                    Failure::Io(error) => TreeDifference::Io {
+                        file,
+                        error: error.error,
+                    },
+                    failure => TreeDifference::Changed {
+                        file,
+                        message: failure.message(),
+                    },
+                }
+            }
+        };
+        differences.push(difference);
+    }
+    differences
+}
+
+/// Add the path of every file under `root.join(dir)`, relative to `root`, to
+/// `files`.
+fn list_files(
+    root: &Path,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> std::result::Result<(), (PathBuf, Error)> {
+    let entries = fs::read_dir(root.join(dir)).map_err(|err| (dir.to_path_buf(), err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| (dir.to_path_buf(), err))?;
+        let path = dir.join(entry.file_name());
+        if entry.path().is_dir() {
+            list_files(root, &path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// How a goldenfile's path is written in a patch: relative to `root`, or else
+/// the current directory, if possible, with `/` separators.
+fn patch_path(root: &Path, path: &Path) -> String {
+    let cwd = env::current_dir().unwrap_or_default();
+    let relative = path
+        .strip_prefix(root)
+        .or_else(|_| path.strip_prefix(&cwd))
+        .unwrap_or(path);
+    let parts: Vec<_> = relative
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_) | Component::ParentDir))
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts.join("/")
+}
+
+/// Convert a goldenfile's differ failure into a [`CheckError`], printing its
+/// message.
+fn check_error(goldenfile: &Goldenfile, failure: Failure) -> CheckError {
+    failure.print();
+    let file = goldenfile.path.clone();
+    match failure {
+        Failure::Io(error) => CheckError::Io {
+            file,
+            error: error.error,
+        },
+        failure => CheckError::Changed {
+            file,
+            message: failure.message(),
+        },
+    }
+}
+
+/// A goldenfile writer implementing [`std::fmt::Write`].
+///
+/// Created by [`Mint::new_goldenfile_fmt_writer`]. Output is flushed to the
+/// temporary file when the writer is dropped or [`FmtWriter::flush`] is called.
+pub struct FmtWriter {
+    inner: BufWriter<File>,
+    budget: Rc<ByteBudget>,
+}
+
+impl FmtWriter {
+    /// Flush buffered output to the temporary file.
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl fmt::Write for FmtWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.budget.spend(s.len());
+        self.inner.write_all(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+/// A goldenfile writer implementing [`std::io::Write`].
+///
+/// Created by [`Mint::new_goldenfile_guard`]. Output is flushed to the
+/// temporary file when the writer is dropped, and the goldenfile is checked
+/// then if [`Mint::set_check_on_drop`] was enabled.
+pub struct GoldenWriter {
+    inner: BufWriter<File>,
+    check: Option<Box<dyn FnOnce()>>,
+    budget: Rc<ByteBudget>,
+}
+
+impl Write for GoldenWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.budget.spend(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Drop for GoldenWriter {
+    fn drop(&mut self) {
+        if let Err(err) = self.inner.flush() {
+            if !thread::panicking() {
+                panic!("Error flushing goldenfile: {}", err);
+            }
+        }
+        if let Some(check) = self.check.take() {
+            if !thread::panicking() {
+                check();
+            }
+        }
+    }
+}
+
+/// Print that a goldenfile changed, and if `annotate`, a GitHub Actions
+/// annotation pointing at its old contents.
+fn report_change(path: &Path, old: &Path, annotate: bool) {
+    eprintln!(
+        "{}: goldenfile changed: {}",
+        "error".bold().red(),
+        path.to_str().unwrap()
+    );
+    if annotate {
+        println!("::error file={}::goldenfile changed", annotation_file(old));
+    }
+}
+
+/// Escape a path for the `file` property of a GitHub Actions workflow
+/// command. Workflow commands are read from stdout.
+fn annotation_file(path: &Path) -> String {
+    path.display()
+        .to_string()
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Append a mismatch's message for the goldenfile at `path` to the file named
+/// by `GOLDENFILE_DIFF_LOG`, if it's set, without color codes.
+fn log_diff(path: &Path, message: &str) {
+    let Some(log) = env::var_os("GOLDENFILE_DIFF_LOG") else {
+        return;
+    };
+    let entry = format!(
+        "==== {} ====\n{}\n\n",
+        path.display(),
+        strip_color_codes(message)
+    );
+    let _guard = DIFF_LOG_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let result = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log)
+        .and_then(|mut file| file.write_all(entry.as_bytes()));
+    if let Err(err) = result {
+        eprintln!(
+            "warning: failed to write goldenfile diff log {:?}: {}",
+            log, err
+        );
+    }
+}
+
+/// Remove ANSI escape sequences like those coloring diffs.
+fn strip_color_codes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.next() == Some('[') {
+                chars.find(|c| ('@'..='~').contains(c));
+            }
         } else {
-            self.check_goldenfiles();
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Print how to update changed goldenfiles, once per failed check.
+fn print_update_hint() {
+    eprintln!("note: run with `UPDATE_GOLDENFILES=1` to update goldenfiles");
+}
+
+/// The canonical form of the JSON file at `path`, for
+/// [`Mint::set_canonicalize_json`], or `None` if it isn't valid JSON.
+fn canonical_json(path: &Path) -> Option<String> {
+    let value = json::parse(&fs::read_to_string(path).ok()?).ok()?;
+    Some(format!("{}\n", value.pretty()))
+}
+
+/// Run a differ, stripping `header` from both files first if given and
+/// neither looks binary.
+fn run_differ(
+    old: &Path,
+    new: &Path,
+    differ: &AnyDiffer,
+    header: Option<&Header>,
+) -> std::result::Result<(), Failure> {
+    match header {
+        Some(header) if !looks_binary(old) && !looks_binary(new) => {
+            let strip = |text| strip_header(text, header);
+            diff_transformed(old, new, differ, &strip, &strip)
+        }
+        _ => differ.run(old, new),
+    }
+}
+
+/// Whether a file contains a NUL byte or isn't valid UTF-8. Missing files
+/// don't.
+fn looks_binary(path: &Path) -> bool {
+    fs::read(path).is_ok_and(|bytes| bytes.contains(&0) || std::str::from_utf8(&bytes).is_err())
+}
+
+/// Write the text of `new` to `old`, in the encoding `old` already has.
+fn reencode(new: &Path, old: &Path) {
+    let read = |path: &Path| {
+        fs::read(path).unwrap_or_else(|err| panic!("Error reading {:?}: {:?}", path, err))
+    };
+    let encoding = bom::Encoding::detect(&read(old));
+    let (_, text) =
+        bom::decode(&read(new)).unwrap_or_else(|err| panic!("{}: {}", new.display(), err));
+    fs::write(old, encoding.encode(&text))
+        .unwrap_or_else(|err| panic!("Error writing {:?}: {:?}", old, err));
+}
+
+/// Compare goldenfiles by symlink target if either is a symlink, returning
+/// whether one was, or the mismatch if their targets differ.
+fn diff_symlinks(path: &Path, old: &Path, new: &Path) -> std::result::Result<bool, String> {
+    let describe = |path: &Path, target: &Option<PathBuf>| match target {
+        Some(target) => format!("a symlink to {:?}", target),
+        None if path.exists() => "a regular file".to_string(),
+        None => "missing".to_string(),
+    };
+    let (old_target, new_target) = (fs::read_link(old).ok(), fs::read_link(new).ok());
+    if old_target.is_none() && new_target.is_none() {
+        return Ok(false);
+    }
+    if old_target != new_target {
+        return Err(format!(
+            "{}: old goldenfile is {} but new goldenfile is {}",
+            path.display(),
+            describe(old, &old_target),
+            describe(new, &new_target)
+        ));
+    }
+    Ok(true)
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn create_symlink(_target: &Path, _link: &Path) -> Result<()> {
+    Err(Error::new(
+        ErrorKind::Unsupported,
+        "Symlinks are not supported on this platform.",
+    ))
+}
+
+/// The differ set by the `.goldencfg` sidecar of the goldenfile at `old`, if it
+/// has one.
+fn sidecar_differ(old: &Path, binary: bool) -> std::result::Result<Option<AnyDiffer>, Failure> {
+    let path = goldencfg::sidecar_path(old);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path).map_err(|error| {
+        Failure::Io(DiffIoError {
+            path: path.clone(),
+            error,
+        })
+    })?;
+    let differ = goldencfg::parse(&text, binary).map_err(|err| {
+        Failure::Changed(format!("{}: invalid goldencfg: {}", path.display(), err))
+    })?;
+    Ok(Some(AnyDiffer::Outcome(differ)))
+}
+
+/// Strip a leading header from text contents.
+fn strip_header(text: Vec<u8>, header: &Header) -> Vec<u8> {
+    let mut lines = text.split_inclusive(|&b| b == b'\n');
+    let stripped = match header {
+        Header::Lines(n) => lines.by_ref().take(*n).map(<[u8]>::len).sum(),
+        Header::UntilMarker(marker) => {
+            let marker = marker.as_bytes();
+            let mut offset = 0;
+            lines
+                .find_map(|line| {
+                    offset += line.len();
+                    line.windows(marker.len().max(1))
+                        .any(|window| window == marker)
+                        .then_some(offset)
+                })
+                .unwrap_or(0)
+        }
+    };
+    text[stripped..].to_vec()
+}
+
+/// Whether a path's extension maps to [`binary_diff`] in [`get_differ_for_path`].
+fn has_binary_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(is_binary_extension)
+}
+
+/// Whether an extension maps to [`binary_diff`] in [`get_differ_for_extension`].
+fn is_binary_extension(ext: &str) -> bool {
+    matches!(ext, "bin" | "exe" | "gz" | "tar" | "zip")
+}
+
+/// The tolerance `.wav` goldenfiles are compared with: one step of a 16-bit
+/// sample.
+#[cfg(feature = "wav")]
+const WAV_SAMPLE_TOLERANCE: f64 = 1.0 / 32768.0;
+
+/// Get the diff function to use for a given file path.
+pub fn get_differ_for_path<P: AsRef<Path>>(_path: P) -> Differ {
+    panic_on_mismatch(inferred_differ(_path.as_ref()).1)
+}
+
+/// Get the diff function to use for files with a given extension, without
+/// its leading `.`, like `"html"`.
+///
+/// This is the differ [`get_differ_for_path`] returns for paths with that
+/// extension. An empty or unknown extension gets [`text_diff`].
+pub fn get_differ_for_extension(ext: &str) -> Differ {
+    panic_on_mismatch(inferred_differ_for_extension(ext).1)
+}
+
+/// The outcome form of the differ [`get_differ_for_path`] returns for `path`,
+/// with its name.
+fn inferred_differ(path: &Path) -> (&'static str, OutcomeDiffer) {
+    inferred_differ_for_extension(path.extension().and_then(|ext| ext.to_str()).unwrap_or(""))
+}
+
+/// The outcome form of the differ [`get_differ_for_extension`] returns for
+/// `ext`, with its name.
+fn inferred_differ_for_extension(ext: &str) -> (&'static str, OutcomeDiffer) {
+    if is_binary_extension(ext) {
+        return ("binary_diff", Box::new(binary_outcome));
+    }
+    match ext {
+        #[cfg(feature = "dot")]
+        "dot" | "gv" => ("dot_diff", Box::new(dot_outcome)),
+        #[cfg(feature = "html")]
+        "htm" | "html" => ("html_diff", Box::new(html_outcome)),
+        #[cfg(feature = "markdown")]
+        "md" => ("markdown_diff", Box::new(markdown_outcome)),
+        "jsonl" | "ndjson" => ("jsonl_diff", Box::new(jsonl_outcome)),
+        #[cfg(feature = "rustfmt")]
+        "rs" => ("rustfmt_diff", Box::new(rustfmt_outcome)),
+        #[cfg(feature = "wav")]
+        "wav" => ("wav_diff", wav_outcome(WAV_SAMPLE_TOLERANCE)),
+        _ => ("text_diff", Box::new(text_outcome)),
+    }
+}
+
+/// Whether `GOLDENFILE_LOG_DIFFERS=1` is set.
+fn log_differs_mode() -> bool {
+    env::var("GOLDENFILE_LOG_DIFFERS").is_ok_and(|var| var == "1")
+}
+
+/// Whether the file at `new` matches the file at `old`, compared with the
+/// differ [`get_differ_for_path`] infers from `old`'s extension.
+///
+/// A mismatch or an unreadable file returns `false` rather than panicking,
+/// and the diff or error is printed to stderr as usual.
+pub fn files_match<P: AsRef<Path>, Q: AsRef<Path>>(old: P, new: Q) -> bool {
+    let (old, new) = (old.as_ref(), new.as_ref());
+    let differ = AnyDiffer::Outcome(inferred_differ(old).1);
+    differ.run(old, new).inspect_err(Failure::print).is_ok()
+}
+
+/// Whether `GOLDENFILE_DRYRUN=1` is set.
+fn dry_run_mode() -> bool {
+    env::var("GOLDENFILE_DRYRUN").is_ok_and(|var| var == "1")
+}
+
+/// Whether `UPDATE_GOLDENFILES=1` is set.
+fn update_mode() -> bool {
+    // For backwards compatibility with 1.4 and below.
+    let legacy_var = env::var("REGENERATE_GOLDENFILES");
+    let update_var = env::var("UPDATE_GOLDENFILES");
+    (legacy_var.is_ok() && legacy_var.unwrap() == "1")
+        || (update_var.is_ok() && update_var.unwrap() == "1")
+}
+
+/// Misspellings of `UPDATE_GOLDENFILES` that are warned about when set to `1`.
+const MISSPELLED_UPDATE_VARS: [&str; 4] = [
+    "UPDATE_GOLDENFILE",
+    "UPDATE_GOLDEN_FILES",
+    "UPDATE_GOLDENS",
+    "UPDATE_GOLDEN",
+];
+
+/// Warn, once per process, if a misspelling of `UPDATE_GOLDENFILES` is set to
+/// `1`, since goldenfiles are then checked rather than updated.
+fn warn_misspelled_update_var() {
+    static WARNED: Once = Once::new();
+    let misspelled = MISSPELLED_UPDATE_VARS
+        .iter()
+        .find(|var| env::var(var).is_ok_and(|value| value == "1"));
+    if let Some(var) = misspelled {
+        WARNED.call_once(|| {
+            eprintln!(
+                "{}: {}=1 is set, but goldenfiles are only updated with UPDATE_GOLDENFILES=1",
+                "warning".bold().yellow(),
+                var
+            );
+        });
+    }
+}
+
+impl Drop for Mint {
+    /// Called when the mint goes out of scope to check or update goldenfiles.
+    fn drop(&mut self) {
+        if thread::panicking() {
+            return;
+        }
+        if dry_run_mode() {
+            self.dry_run_goldenfiles();
+            return;
+        }
+        if update_mode() {
+            if let Some(dir) = env::var_os("GOLDENFILE_PROPOSE_DIR") {
+                self.propose_goldenfiles(dir);
+            } else if let Some(patch) = env::var_os("GOLDENFILE_PATCH") {
+                self.write_patch(patch);
+            } else {
+                self.update_goldenfiles();
+            }
+        } else {
+            warn_misspelled_update_var();
+            let (files, dirs) = self.soft_checked;
+            self.check_goldenfiles_from(files, dirs);
+        }
+        if let Some(cache) = &self.hash_cache {
+            if let Err(err) = cache.save() {
+                eprintln!("warning: failed to save goldenfile hash cache: {}", err);
+            }
         }
     }
 }
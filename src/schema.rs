@@ -0,0 +1,317 @@
+//! A JSON Schema validator for the keywords contract tests commonly use.
+
+use crate::json::{self, Value};
+
+/// Keywords that only annotate a schema, so validation skips them. `format`
+/// is an annotation by default since draft 2019-09.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "$defs",
+    "definitions",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "format",
+    "readOnly",
+    "writeOnly",
+    "deprecated",
+];
+
+/// How deep `$ref`s may nest before the schema is assumed to loop.
+const MAX_REF_DEPTH: usize = 64;
+
+/// Validate `value` against `schema`, returning a message naming the JSON path
+/// of each violation.
+///
+/// Supports `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties`, `minProperties`, `maxProperties`, `items`,
+/// `minItems`, `maxItems`, `uniqueItems`, `minLength`, `maxLength`, `minimum`,
+/// `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`, `allOf`,
+/// `anyOf`, `oneOf`, `not` and `$ref`s within the schema, like
+/// `#/$defs/item`. Other keywords, apart from annotations, are an error
+/// rather than silently passing.
+pub(crate) fn validate(schema: &Value, value: &Value) -> Result<Vec<String>, String> {
+    let mut errors = vec![];
+    Validator { root: schema }.check(schema, value, "$", 0, &mut errors)?;
+    Ok(errors)
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+}
+
+impl<'a> Validator<'a> {
+    fn check(
+        &self,
+        schema: &'a Value,
+        value: &Value,
+        path: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let entries = match schema {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => {
+                errors.push(format!("{}: not allowed by the schema", path));
+                return Ok(());
+            }
+            Value::Object(entries) => entries,
+            _ => return Err(format!("schema for {} is not an object or boolean", path)),
+        };
+        for (keyword, arg) in entries {
+            match keyword.as_str() {
+                "type" => {
+                    let types: Vec<&str> = match arg {
+                        Value::String(name) => vec![name],
+                        Value::Array(names) => names
+                            .iter()
+                            .map(|name| match name {
+                                Value::String(name) => Ok(name.as_str()),
+                                _ => Err("`type` must name types as strings".to_string()),
+                            })
+                            .collect::<Result<_, _>>()?,
+                        _ => return Err("`type` must be a string or an array".to_string()),
+                    };
+                    if !types.iter().any(|name| has_type(value, name)) {
+                        errors.push(format!(
+                            "{}: expected {}, found {}",
+                            path,
+                            types.join(" or "),
+                            value
+                        ));
+                    }
+                }
+                "enum" => {
+                    let Value::Array(allowed) = arg else {
+                        return Err("`enum` must be an array".to_string());
+                    };
+                    if !allowed.contains(value) {
+                        errors.push(format!("{}: {} is not one of {}", path, value, arg));
+                    }
+                }
+                "const" => {
+                    if value != arg {
+                        errors.push(format!("{}: expected {}, found {}", path, arg, value));
+                    }
+                }
+                "properties" => {
+                    let Value::Object(properties) = arg else {
+                        return Err("`properties` must be an object".to_string());
+                    };
+                    for (key, property) in properties {
+                        if let Some(found) = value.get(key) {
+                            self.check(property, found, &json::key_path(path, key), depth, errors)?;
+                        }
+                    }
+                }
+                "required" => {
+                    let Value::Array(keys) = arg else {
+                        return Err("`required` must be an array".to_string());
+                    };
+                    if let Value::Object(_) = value {
+                        for key in keys {
+                            let Value::String(key) = key else {
+                                return Err("`required` must list keys as strings".to_string());
+                            };
+                            if value.get(key).is_none() {
+                                errors
+                                    .push(format!("{}: missing required property {:?}", path, key));
+                            }
+                        }
+                    }
+                }
+                "additionalProperties" => {
+                    if let Value::Object(found) = value {
+                        let declared = schema.get("properties");
+                        for (key, property) in found {
+                            if declared.and_then(|declared| declared.get(key)).is_some() {
+                                continue;
+                            }
+                            let property_path = json::key_path(path, key);
+                            if arg == &Value::Bool(false) {
+                                errors.push(format!("{}: unexpected property", property_path));
+                            } else {
+                                self.check(arg, property, &property_path, depth, errors)?;
+                            }
+                        }
+                    }
+                }
+                "items" => {
+                    if let Value::Array(items) = value {
+                        if let Value::Array(_) = arg {
+                            return Err("`items` as an array of schemas is not supported".into());
+                        }
+                        for (i, item) in items.iter().enumerate() {
+                            self.check(arg, item, &json::index_path(path, i), depth, errors)?;
+                        }
+                    }
+                }
+                "uniqueItems" => {
+                    if let (Value::Bool(true), Value::Array(items)) = (arg, value) {
+                        for (j, item) in items.iter().enumerate() {
+                            if let Some(i) = items[..j].iter().position(|other| other == item) {
+                                errors.push(format!("{}: items {} and {} are equal", path, i, j));
+                                break;
+                            }
+                        }
+                    }
+                }
+                "minItems" | "maxItems" | "minLength" | "maxLength" | "minProperties"
+                | "maxProperties" => {
+                    let limit = count_limit(keyword, arg)?;
+                    let (count, noun) = match (keyword.as_str(), value) {
+                        ("minItems" | "maxItems", Value::Array(items)) => (items.len(), "items"),
+                        ("minLength" | "maxLength", Value::String(text)) => {
+                            (text.chars().count(), "characters")
+                        }
+                        ("minProperties" | "maxProperties", Value::Object(entries)) => {
+                            (entries.len(), "properties")
+                        }
+                        _ => continue,
+                    };
+                    if keyword.starts_with("min") && count < limit {
+                        errors.push(format!(
+                            "{}: has {} {}, fewer than the minimum of {}",
+                            path, count, noun, limit
+                        ));
+                    } else if keyword.starts_with("max") && count > limit {
+                        errors.push(format!(
+                            "{}: has {} {}, more than the maximum of {}",
+                            path, count, noun, limit
+                        ));
+                    }
+                }
+                "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" | "multipleOf" => {
+                    let Some(limit) = number(arg) else {
+                        return Err(format!("`{}` must be a number", keyword));
+                    };
+                    if keyword == "multipleOf" && limit <= 0.0 {
+                        return Err("`multipleOf` must be greater than 0".to_string());
+                    }
+                    let Some(found) = number(value) else {
+                        continue;
+                    };
+                    let (ok, relation) = match keyword.as_str() {
+                        "minimum" => (found >= limit, "less than the minimum of"),
+                        "maximum" => (found <= limit, "more than the maximum of"),
+                        "exclusiveMinimum" => (found > limit, "not greater than"),
+                        "exclusiveMaximum" => (found < limit, "not less than"),
+                        _ => (is_multiple(found, limit), "not a multiple of"),
+                    };
+                    if !ok {
+                        errors.push(format!("{}: {} is {} {}", path, value, relation, arg));
+                    }
+                }
+                "allOf" => {
+                    for subschema in subschemas(keyword, arg)? {
+                        self.check(subschema, value, path, depth, errors)?;
+                    }
+                }
+                "anyOf" | "oneOf" => {
+                    let mut matched = 0;
+                    let subschemas = subschemas(keyword, arg)?;
+                    for subschema in subschemas {
+                        let mut sub_errors = vec![];
+                        self.check(subschema, value, path, depth, &mut sub_errors)?;
+                        matched += usize::from(sub_errors.is_empty());
+                    }
+                    if keyword == "anyOf" && matched == 0 {
+                        errors.push(format!("{}: matches none of the anyOf schemas", path));
+                    } else if keyword == "oneOf" && matched != 1 {
+                        errors.push(format!(
+                            "{}: matches {} of the {} oneOf schemas, not exactly one",
+                            path,
+                            matched,
+                            subschemas.len()
+                        ));
+                    }
+                }
+                "not" => {
+                    let mut sub_errors = vec![];
+                    self.check(arg, value, path, depth, &mut sub_errors)?;
+                    if sub_errors.is_empty() {
+                        errors.push(format!("{}: matches the schema in `not`", path));
+                    }
+                }
+                "$ref" => {
+                    if depth == MAX_REF_DEPTH {
+                        return Err("`$ref`s nest too deeply; does the schema loop?".to_string());
+                    }
+                    let Value::String(reference) = arg else {
+                        return Err("`$ref` must be a string".to_string());
+                    };
+                    let target = self.resolve(reference)?;
+                    self.check(target, value, path, depth + 1, errors)?;
+                }
+                _ if ANNOTATIONS.contains(&keyword.as_str()) => {}
+                _ => return Err(format!("unsupported schema keyword `{}`", keyword)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up a `$ref` like `#/$defs/item` in the root schema.
+    fn resolve(&self, reference: &str) -> Result<&'a Value, String> {
+        let pointer = reference.strip_prefix('#').ok_or_else(|| {
+            format!(
+                "only `$ref`s within the schema are supported: {}",
+                reference
+            )
+        })?;
+        let mut target = self.root;
+        for token in pointer.split('/').skip(1) {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            let next = match target {
+                Value::Object(_) => target.get(&token),
+                Value::Array(items) => token.parse().ok().and_then(|i: usize| items.get(i)),
+                _ => None,
+            };
+            target = next.ok_or_else(|| format!("`$ref` {} not found", reference))?;
+        }
+        Ok(target)
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("number", Value::Number(_))
+        | ("string", Value::String(_))
+        | ("array", Value::Array(_))
+        | ("object", Value::Object(_)) => true,
+        ("integer", _) => number(value).is_some_and(|n| n.fract() == 0.0),
+        _ => false,
+    }
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(token) => token.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Whether `found` is a whole multiple of `limit`, allowing for the rounding
+/// error in decimals like 0.1 that floats can't represent exactly.
+fn is_multiple(found: f64, limit: f64) -> bool {
+    let quotient = found / limit;
+    (quotient - quotient.round()).abs() <= 1e-9 * quotient.abs().max(1.0)
+}
+
+fn count_limit(keyword: &str, arg: &Value) -> Result<usize, String> {
+    number(arg)
+        .filter(|n| *n >= 0.0 && n.fract() == 0.0)
+        .map(|n| n as usize)
+        .ok_or_else(|| format!("`{}` must be a non-negative integer", keyword))
+}
+
+fn subschemas<'a>(keyword: &str, arg: &'a Value) -> Result<&'a [Value], String> {
+    match arg {
+        Value::Array(subschemas) if !subschemas.is_empty() => Ok(subschemas),
+        _ => Err(format!("`{}` must be a non-empty array", keyword)),
+    }
+}
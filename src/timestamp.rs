@@ -0,0 +1,129 @@
+//! Finding and parsing timestamps described by a strftime-like format.
+
+enum Item {
+    Literal(char),
+    /// A fixed-width numeric field, e.g. `%Y`.
+    Field(char, usize),
+    /// `%f`: one to nine fractional-second digits.
+    Fraction,
+}
+
+/// A parsed timestamp format.
+///
+/// Supports `%Y` (4-digit year), `%m`, `%d`, `%H`, `%M`, `%S` (2 digits each),
+/// `%f` (1 to 9 fractional-second digits) and `%%`. Other characters must
+/// match literally. Timestamps are interpreted as UTC.
+pub(crate) struct Format(Vec<Item>);
+
+impl Format {
+    pub(crate) fn parse(format: &str) -> Result<Format, String> {
+        let mut items = vec![];
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                items.push(Item::Literal(c));
+                continue;
+            }
+            items.push(match chars.next() {
+                Some('Y') => Item::Field('Y', 4),
+                Some(c @ ('m' | 'd' | 'H' | 'M' | 'S')) => Item::Field(c, 2),
+                Some('f') => Item::Fraction,
+                Some('%') => Item::Literal('%'),
+                Some(c) => return Err(format!("unsupported specifier %{}", c)),
+                None => return Err("trailing %".to_string()),
+            });
+        }
+        if !items.iter().any(|item| matches!(item, Item::Field(..))) {
+            return Err("format has no date or time fields".to_string());
+        }
+        Ok(Format(items))
+    }
+
+    /// Match a timestamp at the start of `text`, returning its value in seconds
+    /// since the Unix epoch and its length in bytes.
+    pub(crate) fn match_at(&self, text: &str) -> Option<(f64, usize)> {
+        let (mut year, mut month, mut day) = (1970, 1, 1);
+        let (mut hour, mut minute, mut second, mut fraction) = (0, 0, 0, 0.0);
+        let mut pos = 0;
+        for item in &self.0 {
+            let rest = &text[pos..];
+            match item {
+                Item::Literal(c) => {
+                    if !rest.starts_with(*c) {
+                        return None;
+                    }
+                    pos += c.len_utf8();
+                }
+                Item::Field(field, width) => {
+                    let digits = rest.get(..*width)?;
+                    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                        return None;
+                    }
+                    let value: i64 = digits.parse().ok()?;
+                    match field {
+                        'Y' => year = value,
+                        'm' => month = value,
+                        'd' => day = value,
+                        'H' => hour = value,
+                        'M' => minute = value,
+                        _ => second = value,
+                    }
+                    pos += width;
+                }
+                Item::Fraction => {
+                    let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+                    if !(1..=9).contains(&len) {
+                        return None;
+                    }
+                    fraction = format!("0.{}", &rest[..len]).parse().ok()?;
+                    pos += len;
+                }
+            }
+        }
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+        let days = days_from_civil(year, month, day);
+        let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+        Some((seconds as f64 + fraction, pos))
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Replace every timestamp in `text` with `placeholder`, returning the
+/// rewritten text and each timestamp's value and original text in order.
+pub(crate) fn extract<'a>(
+    format: &Format,
+    text: &'a str,
+    placeholder: &str,
+) -> (String, Vec<(f64, &'a str)>) {
+    let mut out = String::new();
+    let mut values = vec![];
+    let mut pos = 0;
+    while pos < text.len() {
+        if let Some((value, len)) = format.match_at(&text[pos..]) {
+            out.push_str(placeholder);
+            values.push((value, &text[pos..pos + len]));
+            pos += len;
+        } else {
+            let c = text[pos..].chars().next().unwrap();
+            out.push(c);
+            pos += c.len_utf8();
+        }
+    }
+    (out, values)
+}
@@ -0,0 +1,385 @@
+//! A small VT100-style terminal emulator used to compare the screens that
+//! terminal output renders to.
+
+use std::fmt;
+
+/// A foreground or background color set by SGR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Color {
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Color::Indexed(i) => write!(f, "{}", i),
+            Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+/// The rendition of a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Attrs {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+    fg: Option<Color>,
+    bg: Option<Color>,
+}
+
+impl fmt::Display for Attrs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = vec![];
+        for (set, name) in [
+            (self.bold, "bold"),
+            (self.dim, "dim"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+            (self.inverse, "inverse"),
+        ] {
+            if set {
+                parts.push(name.to_string());
+            }
+        }
+        if let Some(fg) = self.fg {
+            parts.push(format!("fg {}", fg));
+        }
+        if let Some(bg) = self.bg {
+            parts.push(format!("bg {}", bg));
+        }
+        if parts.is_empty() {
+            write!(f, "plain")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+/// One character position on the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Cell {
+    ch: char,
+    attrs: Attrs,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            ch: ' ',
+            attrs: Attrs::default(),
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} ({})", self.ch, self.attrs)
+    }
+}
+
+/// The screen left after rendering some terminal output.
+pub(crate) struct Screen {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Cell>,
+    row: usize,
+    col: usize,
+    /// Set after writing to the last column, so the next character wraps.
+    pending_wrap: bool,
+    attrs: Attrs,
+    saved: (usize, usize, Attrs),
+}
+
+impl Screen {
+    fn new(cols: usize, rows: usize) -> Self {
+        Screen {
+            cols,
+            rows,
+            cells: vec![Cell::default(); cols * rows],
+            row: 0,
+            col: 0,
+            pending_wrap: false,
+            attrs: Attrs::default(),
+            saved: (0, 0, Attrs::default()),
+        }
+    }
+
+    pub(crate) fn cell(&self, row: usize, col: usize) -> Cell {
+        self.cells[row * self.cols + col]
+    }
+
+    /// The characters of a row, without trailing blanks.
+    pub(crate) fn row_text(&self, row: usize) -> String {
+        let start = row * self.cols;
+        let text: String = self.cells[start..start + self.cols]
+            .iter()
+            .map(|c| c.ch)
+            .collect();
+        text.trim_end().to_string()
+    }
+
+    fn put(&mut self, ch: char) {
+        if self.pending_wrap {
+            self.col = 0;
+            self.line_feed();
+        }
+        let attrs = self.attrs;
+        self.cells[self.row * self.cols + self.col] = Cell { ch, attrs };
+        if self.col + 1 == self.cols {
+            self.pending_wrap = true;
+        } else {
+            self.col += 1;
+        }
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.cols - 1);
+        self.pending_wrap = false;
+    }
+
+    fn line_feed(&mut self) {
+        self.pending_wrap = false;
+        if self.row + 1 == self.rows {
+            self.scroll_up(0, 1);
+        } else {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.pending_wrap = false;
+        if self.row == 0 {
+            self.scroll_down(0, 1);
+        } else {
+            self.row -= 1;
+        }
+    }
+
+    /// Scroll the rows from `top` down by `n` upwards, blanking the bottom.
+    fn scroll_up(&mut self, top: usize, n: usize) {
+        let lines = &mut self.cells[top * self.cols..];
+        let n = (n * self.cols).min(lines.len());
+        lines.rotate_left(n);
+        let len = lines.len();
+        lines[len - n..].fill(Cell::default());
+    }
+
+    /// Scroll the rows from `top` down by `n` downwards, blanking at `top`.
+    fn scroll_down(&mut self, top: usize, n: usize) {
+        let lines = &mut self.cells[top * self.cols..];
+        let n = (n * self.cols).min(lines.len());
+        lines.rotate_right(n);
+        lines[..n].fill(Cell::default());
+    }
+
+    fn erase(&mut self, from: usize, to: usize) {
+        self.cells[from..to].fill(Cell::default());
+    }
+
+    fn erase_display(&mut self, mode: u16) {
+        let cursor = self.row * self.cols + self.col;
+        match mode {
+            0 => self.erase(cursor, self.cells.len()),
+            1 => self.erase(0, cursor + 1),
+            2 | 3 => self.erase(0, self.cells.len()),
+            _ => {}
+        }
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        let start = self.row * self.cols;
+        let cursor = start + self.col;
+        match mode {
+            0 => self.erase(cursor, start + self.cols),
+            1 => self.erase(start, cursor + 1),
+            2 => self.erase(start, start + self.cols),
+            _ => {}
+        }
+    }
+
+    /// Shift the rest of the line right by `n` if `insert`, or left otherwise.
+    fn shift_line(&mut self, n: usize, insert: bool) {
+        let start = self.row * self.cols;
+        let line = &mut self.cells[start + self.col..start + self.cols];
+        let n = n.min(line.len());
+        if insert {
+            line.rotate_right(n);
+            line[..n].fill(Cell::default());
+        } else {
+            line.rotate_left(n);
+            let len = line.len();
+            line[len - n..].fill(Cell::default());
+        }
+    }
+
+    fn sgr(&mut self, params: &[u16]) {
+        let mut params = params.iter().copied();
+        while let Some(p) = params.next() {
+            let a = &mut self.attrs;
+            match p {
+                0 => *a = Attrs::default(),
+                1 => a.bold = true,
+                2 => a.dim = true,
+                3 => a.italic = true,
+                4 => a.underline = true,
+                7 => a.inverse = true,
+                22 => (a.bold, a.dim) = (false, false),
+                23 => a.italic = false,
+                24 => a.underline = false,
+                27 => a.inverse = false,
+                30..=37 => a.fg = Some(Color::Indexed((p - 30) as u8)),
+                39 => a.fg = None,
+                40..=47 => a.bg = Some(Color::Indexed((p - 40) as u8)),
+                49 => a.bg = None,
+                90..=97 => a.fg = Some(Color::Indexed((p - 90 + 8) as u8)),
+                100..=107 => a.bg = Some(Color::Indexed((p - 100 + 8) as u8)),
+                38 | 48 => {
+                    let color = match params.next() {
+                        Some(5) => params.next().map(|i| Color::Indexed(i as u8)),
+                        Some(2) => match (params.next(), params.next(), params.next()) {
+                            (Some(r), Some(g), Some(b)) => {
+                                Some(Color::Rgb(r as u8, g as u8, b as u8))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if p == 38 {
+                        a.fg = color;
+                    } else {
+                        a.bg = color;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn csi(&mut self, params: &[u16], private: bool, action: char) {
+        if private {
+            // Mode changes like hiding the cursor don't affect the cells.
+            return;
+        }
+        let arg = |i: usize| params.get(i).copied().unwrap_or(0);
+        let n = usize::from(arg(0).max(1));
+        let (row, col) = (self.row, self.col);
+        match action {
+            'A' => self.move_to(row.saturating_sub(n), col),
+            'B' | 'e' => self.move_to(row + n, col),
+            'C' | 'a' => self.move_to(row, col + n),
+            'D' => self.move_to(row, col.saturating_sub(n)),
+            'E' => self.move_to(row + n, 0),
+            'F' => self.move_to(row.saturating_sub(n), 0),
+            'G' | '`' => self.move_to(row, n - 1),
+            'd' => self.move_to(n - 1, col),
+            'H' | 'f' => self.move_to(n - 1, usize::from(arg(1).max(1)) - 1),
+            'J' => self.erase_display(arg(0)),
+            'K' => self.erase_line(arg(0)),
+            'X' => {
+                let start = row * self.cols + col;
+                self.erase(start, start + n.min(self.cols - col));
+            }
+            '@' => self.shift_line(n, true),
+            'P' => self.shift_line(n, false),
+            'L' => self.scroll_down(row, n),
+            'M' => self.scroll_up(row, n),
+            'S' => self.scroll_up(0, n),
+            'T' => self.scroll_down(0, n),
+            'm' => self.sgr(if params.is_empty() { &[0] } else { params }),
+            's' => self.saved = (row, col, self.attrs),
+            'u' => {
+                let (row, col, attrs) = self.saved;
+                self.move_to(row, col);
+                self.attrs = attrs;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Render terminal output on a `cols` by `rows` screen.
+///
+/// Handles printable characters with autowrap, carriage return, line feed,
+/// backspace, tab, the common CSI cursor, erase, scroll and SGR sequences, and
+/// cursor save and restore. Other escape sequences, including OSC strings and
+/// private mode changes, are skipped. As on a real terminal, a line feed
+/// doesn't return the cursor to the first column.
+pub(crate) fn render(output: &str, cols: usize, rows: usize) -> Screen {
+    let cols = cols.max(1);
+    let rows = rows.max(1);
+    let mut screen = Screen::new(cols, rows);
+    let mut chars = output.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                Some('[') => {
+                    let mut params = vec![];
+                    let mut current = None;
+                    let mut private = false;
+                    let mut action = None;
+                    for c in chars.by_ref() {
+                        match c {
+                            '0'..='9' => {
+                                let digit = c as u16 - '0' as u16;
+                                current = Some(current.unwrap_or(0u16).saturating_mul(10) + digit);
+                            }
+                            ';' | ':' => params.push(current.take().unwrap_or(0)),
+                            '<'..='?' => private = true,
+                            ' '..='/' => {}
+                            _ => {
+                                action = Some(c);
+                                break;
+                            }
+                        }
+                    }
+                    if let Some(action) = action {
+                        if current.is_some() || !params.is_empty() {
+                            params.push(current.unwrap_or(0));
+                        }
+                        screen.csi(&params, private, action);
+                    }
+                }
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                Some('7') => screen.saved = (screen.row, screen.col, screen.attrs),
+                Some('8') => {
+                    let (row, col, attrs) = screen.saved;
+                    screen.move_to(row, col);
+                    screen.attrs = attrs;
+                }
+                Some('D') => screen.line_feed(),
+                Some('E') => {
+                    screen.col = 0;
+                    screen.line_feed();
+                }
+                Some('M') => screen.reverse_index(),
+                Some('c') => screen = Screen::new(cols, rows),
+                Some('(' | ')' | '*' | '+') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\r' => screen.move_to(screen.row, 0),
+            '\n' | '\x0b' | '\x0c' => screen.line_feed(),
+            '\x08' => screen.move_to(screen.row, screen.col.saturating_sub(1)),
+            '\t' => screen.move_to(screen.row, (screen.col / 8 + 1) * 8),
+            c if c.is_control() => {}
+            c => screen.put(c),
+        }
+    }
+    screen
+}
@@ -0,0 +1,138 @@
+//! A small WAV decoder used to compare audio by its samples.
+
+use std::fmt;
+
+/// How a WAV file's samples are encoded.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Format {
+    channels: u16,
+    sample_rate: u32,
+    bits: u16,
+    float: bool,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} channels, {} Hz, {}-bit {}",
+            self.channels,
+            self.sample_rate,
+            self.bits,
+            if self.float { "float" } else { "PCM" }
+        )
+    }
+}
+
+/// A decoded WAV file.
+pub(crate) struct Wav {
+    pub(crate) format: Format,
+    /// Interleaved samples, scaled to `-1.0..=1.0` for integer formats.
+    pub(crate) samples: Vec<f64>,
+}
+
+impl Wav {
+    pub(crate) fn channels(&self) -> usize {
+        usize::from(self.format.channels)
+    }
+}
+
+const PCM: u16 = 1;
+const FLOAT: u16 = 3;
+const EXTENSIBLE: u16 = 0xFFFE;
+
+/// Decode a RIFF WAVE file with integer PCM or floating point samples.
+///
+/// Chunks other than `fmt ` and `data`, like `LIST` metadata, are skipped.
+pub(crate) fn parse(bytes: &[u8]) -> Result<Wav, String> {
+    if bytes.len() < 12 || &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("not a RIFF WAVE file".to_string());
+    }
+    let mut format = None;
+    let mut data = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let len = u32_at(bytes, pos + 4) as usize;
+        let body = bytes
+            .get(pos + 8..pos + 8 + len)
+            .ok_or_else(|| format!("truncated {:?} chunk", String::from_utf8_lossy(id)))?;
+        match id {
+            b"fmt " => format = Some(parse_format(body)?),
+            b"data" => data = Some(body),
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        pos += 8 + len + len % 2;
+    }
+    let format = format.ok_or("missing fmt chunk")?;
+    let data = data.ok_or("missing data chunk")?;
+
+    let width = usize::from(format.bits / 8);
+    if !data
+        .len()
+        .is_multiple_of(width * usize::from(format.channels))
+    {
+        return Err("data chunk ends partway through a frame".to_string());
+    }
+    let samples = data
+        .chunks_exact(width)
+        .map(|sample| decode_sample(sample, &format))
+        .collect();
+    Ok(Wav { format, samples })
+}
+
+fn parse_format(body: &[u8]) -> Result<Format, String> {
+    if body.len() < 16 {
+        return Err("fmt chunk is too short".to_string());
+    }
+    let mut tag = u16_at(body, 0);
+    if tag == EXTENSIBLE {
+        // The real format is the first two bytes of the subformat GUID.
+        if body.len() < 26 {
+            return Err("extensible fmt chunk is too short".to_string());
+        }
+        tag = u16_at(body, 24);
+    }
+    let format = Format {
+        channels: u16_at(body, 2),
+        sample_rate: u32_at(body, 4),
+        bits: u16_at(body, 14),
+        float: tag == FLOAT,
+    };
+    let supported = match tag {
+        PCM => matches!(format.bits, 8 | 16 | 24 | 32),
+        FLOAT => matches!(format.bits, 32 | 64),
+        _ => return Err(format!("unsupported format tag {:#06x}", tag)),
+    };
+    if !supported {
+        return Err(format!("unsupported {}", format));
+    }
+    if format.channels == 0 {
+        return Err("no channels".to_string());
+    }
+    Ok(format)
+}
+
+fn decode_sample(sample: &[u8], format: &Format) -> f64 {
+    match (format.float, sample.len()) {
+        (true, 4) => f64::from(f32::from_le_bytes(sample.try_into().unwrap())),
+        (true, _) => f64::from_le_bytes(sample.try_into().unwrap()),
+        // 8-bit samples are unsigned, centered on 128.
+        (false, 1) => (f64::from(sample[0]) - 128.0) / 128.0,
+        (false, len) => {
+            // Sign-extend by placing the sample in the top bytes of an i32.
+            let mut bytes = [0; 4];
+            bytes[4 - len..].copy_from_slice(sample);
+            f64::from(i32::from_le_bytes(bytes)) / 2f64.powi(31)
+        }
+    }
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap())
+}
@@ -0,0 +1,417 @@
+//! A minimal XML parser used to compare documents by element structure.
+
+use std::fmt;
+
+/// A normalized XML node.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Node {
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+    Text(String),
+}
+
+impl fmt::Display for Node {
+    /// Describe a node without its children.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Node::Element { name, attrs, .. } => {
+                write!(f, "<{}", name)?;
+                for (key, value) in attrs {
+                    write!(f, " {}={:?}", key, value)?;
+                }
+                write!(f, ">")
+            }
+            Node::Text(text) => write!(f, "{:?}", text),
+        }
+    }
+}
+
+/// An open element's name, attributes and children so far.
+type OpenElement = (String, Vec<(String, String)>, Vec<Node>);
+
+/// Parse an XML document into a list of top-level nodes.
+///
+/// Attributes are sorted by name, entity and character references are decoded,
+/// CDATA sections become text, and text is trimmed, with whitespace-only text
+/// dropped. Comments, processing instructions and doctypes are dropped.
+pub(crate) fn parse(text: &str) -> Result<Vec<Node>, String> {
+    let mut stack: Vec<OpenElement> = vec![(String::new(), vec![], vec![])];
+    let mut rest = text;
+    let line = |rest: &str| text[..text.len() - rest.len()].matches('\n').count() + 1;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after
+                .find("-->")
+                .ok_or_else(|| format!("unterminated comment on line {}", line(rest)))?;
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after
+                .find("]]>")
+                .ok_or_else(|| format!("unterminated CDATA section on line {}", line(rest)))?;
+            push_text(&mut stack.last_mut().unwrap().2, &after[..end]);
+            rest = &after[end + 3..];
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after
+                .find('>')
+                .ok_or_else(|| format!("unterminated end tag on line {}", line(rest)))?;
+            let name = after[..end].trim();
+            if stack.len() == 1 || stack.last().unwrap().0 != name {
+                return Err(format!("unexpected </{}> on line {}", name, line(rest)));
+            }
+            let (name, attrs, children) = stack.pop().unwrap();
+            add_child(&mut stack, name, attrs, children);
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (name, attrs, self_closing, after) =
+                parse_tag(after).map_err(|err| format!("{} on line {}", err, line(rest)))?;
+            rest = after;
+            if self_closing {
+                add_child(&mut stack, name, attrs, vec![]);
+            } else {
+                stack.push((name, attrs, vec![]));
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            push_text(
+                &mut stack.last_mut().unwrap().2,
+                &decode_entities(&rest[..end]),
+            );
+            rest = &rest[end..];
+        }
+    }
+
+    if stack.len() > 1 {
+        return Err(format!("unclosed <{}>", stack.last().unwrap().0));
+    }
+    Ok(stack.pop().unwrap().2)
+}
+
+fn add_child(
+    stack: &mut [OpenElement],
+    name: String,
+    mut attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+) {
+    attrs.sort();
+    stack.last_mut().unwrap().2.push(Node::Element {
+        name,
+        attrs,
+        children,
+    });
+}
+
+fn push_text(children: &mut Vec<Node>, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    match children.last_mut() {
+        Some(Node::Text(previous)) => previous.push_str(text),
+        _ => children.push(Node::Text(text.to_string())),
+    }
+}
+
+/// A start tag's name, attributes, whether it was self-closing and the input
+/// after it.
+type StartTag<'a> = (String, Vec<(String, String)>, bool, &'a str);
+
+/// Parse a start tag after its `<`.
+fn parse_tag(text: &str) -> Result<StartTag<'_>, String> {
+    let is_name_char = |c: char| !c.is_whitespace() && !"/>=".contains(c);
+    let name_end = text.find(|c| !is_name_char(c)).unwrap_or(text.len());
+    if name_end == 0 {
+        return Err("expected an element name after `<`".to_string());
+    }
+    let name = text[..name_end].to_string();
+    let mut rest = &text[name_end..];
+    let mut attrs: Vec<(String, String)> = vec![];
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return Ok((name, attrs, true, after));
+        } else if let Some(after) = rest.strip_prefix('>') {
+            return Ok((name, attrs, false, after));
+        }
+        let key_end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if key_end == 0 {
+            return Err(format!("malformed start tag <{}>", name));
+        }
+        let key = rest[..key_end].to_string();
+        let after = rest[key_end..].trim_start();
+        let after = after
+            .strip_prefix('=')
+            .ok_or_else(|| format!("attribute {} of <{}> has no value", key, name))?
+            .trim_start();
+        let quote = after
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or_else(|| format!("attribute {} of <{}> is not quoted", key, name))?;
+        let body = &after[1..];
+        let end = body
+            .find(quote)
+            .ok_or_else(|| format!("unterminated attribute {} of <{}>", key, name))?;
+        if attrs.iter().any(|(k, _)| *k == key) {
+            return Err(format!("duplicate attribute {} of <{}>", key, name));
+        }
+        attrs.push((key, decode_entities(&body[..end])));
+        rest = &body[end + 1..];
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse().ok()))
+                    .flatten()
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// What a step of a [`Path`] selects.
+#[derive(Debug)]
+enum Test {
+    /// Child elements with this name, or any name if `None`.
+    Element(Option<String>),
+    Text,
+    /// Attributes with this name, or any name if `None`.
+    Attribute(Option<String>),
+}
+
+#[derive(Debug)]
+struct Step {
+    /// Whether the step was written after `//`, matching at any depth.
+    descendant: bool,
+    test: Test,
+    /// Only the nth match among its siblings, counting from 1.
+    position: Option<usize>,
+}
+
+/// A path in a subset of XPath, like `/config/server[2]/@port` or
+/// `//meta/@generatedAt`.
+#[derive(Debug)]
+pub(crate) struct Path(Vec<Step>);
+
+impl Path {
+    /// Parse an absolute path of `/` and `//` separated steps. Steps are
+    /// element names, `*`, `text()` or, last, an attribute as `@name` or
+    /// `@*`, and element and text steps may be followed by a position like
+    /// `[1]`.
+    pub(crate) fn parse(path: &str) -> Result<Path, String> {
+        let mut steps = vec![];
+        let mut rest = path;
+        while !rest.is_empty() {
+            let descendant = rest.starts_with("//");
+            rest = rest
+                .strip_prefix("//")
+                .or_else(|| rest.strip_prefix('/'))
+                .ok_or_else(|| format!("expected `/` before `{}`", rest))?;
+            if steps
+                .last()
+                .is_some_and(|s: &Step| matches!(s.test, Test::Attribute(_)))
+            {
+                return Err("an attribute must be the last step".to_string());
+            }
+            let end = rest.find('/').unwrap_or(rest.len());
+            let (mut step, mut position) = (&rest[..end], None);
+            if let Some(open) = step.find('[') {
+                let index = step[open + 1..]
+                    .strip_suffix(']')
+                    .and_then(|index| index.parse().ok())
+                    .filter(|&index| index > 0)
+                    .ok_or_else(|| format!("invalid position in `{}`", step))?;
+                position = Some(index);
+                step = &step[..open];
+            }
+            let test = match step {
+                "" => return Err("empty step".to_string()),
+                "text()" => Test::Text,
+                "*" => Test::Element(None),
+                "@*" => Test::Attribute(None),
+                _ if step.starts_with('@') => Test::Attribute(Some(step[1..].to_string())),
+                _ => Test::Element(Some(step.to_string())),
+            };
+            if position.is_some() && matches!(test, Test::Attribute(_)) {
+                return Err(format!("attributes can't have a position: `{}`", step));
+            }
+            steps.push(Step {
+                descendant,
+                test,
+                position,
+            });
+            rest = &rest[end..];
+        }
+        if steps.is_empty() {
+            return Err("path must select something below `/`".to_string());
+        }
+        Ok(Path(steps))
+    }
+
+    /// Remove the nodes and attributes the path selects from a document.
+    pub(crate) fn remove_from(&self, nodes: &mut Vec<Node>) {
+        remove_matches(nodes, &mut vec![], &self.0);
+    }
+}
+
+/// Remove what `steps` select relative to an element with `children` and
+/// `attrs`.
+fn remove_matches(children: &mut Vec<Node>, attrs: &mut Vec<(String, String)>, steps: &[Step]) {
+    let Some((step, rest)) = steps.split_first() else {
+        return;
+    };
+    if let Test::Attribute(name) = &step.test {
+        attrs.retain(|(key, _)| name.as_ref().is_some_and(|name| name != key));
+    } else {
+        let mut count = 0;
+        let mut selected = vec![];
+        for (i, child) in children.iter().enumerate() {
+            let matches = match (&step.test, child) {
+                (Test::Element(None), Node::Element { .. }) | (Test::Text, Node::Text(_)) => true,
+                (Test::Element(Some(test)), Node::Element { name, .. }) => name == test,
+                _ => false,
+            };
+            if matches {
+                count += 1;
+                if step.position.is_none_or(|position| position == count) {
+                    selected.push(i);
+                }
+            }
+        }
+        if rest.is_empty() {
+            let mut i = 0;
+            children.retain(|_| {
+                i += 1;
+                !selected.contains(&(i - 1))
+            });
+        } else {
+            for i in selected {
+                if let Node::Element {
+                    attrs, children, ..
+                } = &mut children[i]
+                {
+                    remove_matches(children, attrs, rest);
+                }
+            }
+        }
+    }
+    if step.descendant {
+        for child in children {
+            if let Node::Element {
+                attrs, children, ..
+            } = child
+            {
+                remove_matches(children, attrs, steps);
+            }
+        }
+    }
+}
+
+/// Find the first difference between two node lists, returning its path and
+/// descriptions of the old and new nodes or attribute values there.
+pub(crate) fn first_difference(
+    old: &[Node],
+    new: &[Node],
+    path: &str,
+) -> Option<(String, String, String)> {
+    let describe = |node: Option<&Node>| node.map_or("(nothing)".to_string(), |n| n.to_string());
+    for i in 0..old.len().max(new.len()) {
+        let (o, n) = (old.get(i), new.get(i));
+        let child_path = child_path(
+            old.get(i).or(new.get(i)).unwrap(),
+            &old[..i.min(old.len())],
+            path,
+        );
+        match (o, n) {
+            (
+                Some(Node::Element {
+                    name: old_name,
+                    attrs: old_attrs,
+                    children: old_children,
+                }),
+                Some(Node::Element {
+                    name: new_name,
+                    attrs: new_attrs,
+                    children: new_children,
+                }),
+            ) if old_name == new_name => {
+                if let Some(difference) = attribute_difference(old_attrs, new_attrs, &child_path)
+                    .or_else(|| first_difference(old_children, new_children, &child_path))
+                {
+                    return Some(difference);
+                }
+            }
+            _ if o == n => {}
+            _ => return Some((child_path, describe(o), describe(n))),
+        }
+    }
+    None
+}
+
+fn attribute_difference(
+    old: &[(String, String)],
+    new: &[(String, String)],
+    path: &str,
+) -> Option<(String, String, String)> {
+    let value = |attrs: &[(String, String)], key: &str| {
+        attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map_or("(nothing)".to_string(), |(_, v)| format!("{:?}", v))
+    };
+    let mut keys: Vec<&String> = old.iter().chain(new).map(|(key, _)| key).collect();
+    keys.sort();
+    keys.into_iter().find_map(|key| {
+        let (o, n) = (value(old, key), value(new, key));
+        (o != n).then(|| (format!("{}/@{}", path, key), o, n))
+    })
+}
+
+/// An XPath-like path to `node`, given the siblings that precede it.
+fn child_path(node: &Node, preceding: &[Node], parent: &str) -> String {
+    let same = |other: &&Node| match (node, other) {
+        (Node::Element { name, .. }, Node::Element { name: other, .. }) => name == other,
+        (Node::Text(_), Node::Text(_)) => true,
+        _ => false,
+    };
+    let index = preceding.iter().filter(same).count() + 1;
+    match node {
+        Node::Element { name, .. } => format!("{}/{}[{}]", parent, name, index),
+        Node::Text(_) => format!("{}/text()[{}]", parent, index),
+    }
+}
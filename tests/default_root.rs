@@ -0,0 +1,21 @@
+extern crate goldenfile;
+
+use std::io::Write;
+
+use goldenfile::Mint;
+
+// The default root is process-wide, so this lives in its own test binary.
+#[test]
+fn default_root() {
+    Mint::set_default_root("tests");
+    {
+        let mut mint = Mint::new("goldenfiles");
+        let mut file = mint.new_goldenfile("match1.txt").unwrap();
+        writeln!(file, "Hello world!").unwrap();
+    }
+
+    let absolute = std::env::current_dir().unwrap().join("tests/goldenfiles");
+    let mut mint = Mint::new(absolute);
+    let mut file = mint.new_goldenfile("match2.txt").unwrap();
+    writeln!(file, "foobar").unwrap();
+}
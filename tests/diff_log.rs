@@ -0,0 +1,26 @@
+extern crate goldenfile;
+
+use std::fs;
+
+use goldenfile::Mint;
+
+// The diff log is named by the environment, so this lives in its own test
+// binary.
+#[test]
+fn diff_log() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("diffs.log");
+    std::env::set_var("GOLDENFILE_DIFF_LOG", &log);
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.write_goldenfile("match1.txt", "Hello there!\n")
+        .unwrap();
+    mint.write_goldenfile("match2.txt", "foobar\n").unwrap();
+    mint.write_goldenfile("match3.txt", "bazqux\n").unwrap();
+
+    assert_eq!(mint.soft_check().len(), 2);
+    let log = fs::read_to_string(log).unwrap();
+    assert!(log.starts_with("==== match1.txt ====\n"), "{}", log);
+    assert!(log.contains("\n\n==== match3.txt ====\n"), "{}", log);
+    assert!(!log.contains("match2.txt"), "{}", log);
+    assert!(!log.contains('\x1b'), "{}", log);
+}
@@ -0,0 +1,27 @@
+extern crate goldenfile;
+
+use std::io::Write;
+
+use goldenfile::Mint;
+
+// Diff settings are read from the environment, so this lives in its own test
+// binary.
+#[test]
+#[should_panic(
+    expected = "... (5 identical lines) ...\n line 6\n line 7\n line 8\n line 9\n-line 10\n+line ten\n \
+                line 11\n line 12\n line 13\n line 14\n... (6 identical lines) ..."
+)]
+fn elide_unchanged() {
+    std::env::set_var("GOLDENFILE_ELIDE_UNCHANGED", "4");
+    std::env::set_var("NO_COLOR", "1");
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("long.txt").unwrap();
+
+    for i in 1..=20 {
+        if i == 10 {
+            writeln!(file, "line ten").unwrap();
+        } else {
+            writeln!(file, "line {}", i).unwrap();
+        }
+    }
+}
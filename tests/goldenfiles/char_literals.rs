@@ -0,0 +1,5 @@
+fn quote<'a>(s: &'a str) -> char {
+    let q = '"'; // 2024
+    let e = '\''; // 2024
+    q
+}
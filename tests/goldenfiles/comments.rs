@@ -0,0 +1,5 @@
+// Generated at 2024-01-01.
+fn main() {
+    println!("http://example.com"); // say hi
+    /* version 1 */
+}
@@ -0,0 +1,18 @@
+extern crate goldenfile;
+
+use std::fs;
+
+use goldenfile::Mint;
+
+// Update mode is read from the environment, so this lives in its own test
+// binary.
+#[test]
+#[should_panic(expected = "out.txt: files differ\n  old: old\n  new: new")]
+fn misspelled_update_var() {
+    std::env::set_var("UPDATE_GOLDENFILE", "1");
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("out.txt"), "old\n").unwrap();
+    let mut mint = Mint::new(dir.path());
+
+    mint.write_goldenfile("out.txt", "new\n").unwrap();
+}
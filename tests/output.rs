@@ -0,0 +1,219 @@
+extern crate goldenfile;
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use goldenfile::Mint;
+
+// What a Mint prints can't be captured from within a test, so each test here
+// runs a `_child` test of this binary in a child process and checks its
+// output. Child tests do nothing unless GOLDENFILE_OUTPUT_CHILD is set.
+
+/// Run the child test `name` with `vars` set, returning whether it passed and
+/// its stdout and stderr.
+fn run_child(name: &str, vars: &[(&str, &str)]) -> (bool, String, String) {
+    let output = Command::new(env::current_exe().unwrap())
+        .args([name, "--exact", "--nocapture", "--test-threads=1"])
+        .env("GOLDENFILE_OUTPUT_CHILD", "1")
+        .envs(vars.iter().copied())
+        .output()
+        .unwrap();
+    let text = |bytes: Vec<u8>| String::from_utf8(bytes).unwrap();
+    (
+        output.status.success(),
+        text(output.stdout),
+        text(output.stderr),
+    )
+}
+
+fn is_child() -> bool {
+    env::var_os("GOLDENFILE_OUTPUT_CHILD").is_some()
+}
+
+#[test]
+fn verbose_summary() {
+    let (_, stdout, _) = run_child("verbose_summary_child", &[]);
+    assert!(
+        stdout.contains("goldenfiles: 2 checked, 0 changed\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn verbose_summary_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_verbose(true);
+    let mut file1 = mint.new_goldenfile("match1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("match2.txt").unwrap();
+
+    writeln!(file1, "Hello world!").unwrap();
+    writeln!(file2, "foobar").unwrap();
+}
+
+#[test]
+fn verbose_summary_stops_at_first_change() {
+    let (_, stdout, _) = run_child("verbose_summary_stops_at_first_change_child", &[]);
+    assert!(
+        stdout.contains("goldenfiles: 1 checked, 1 changed\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn verbose_summary_stops_at_first_change_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_verbose(true);
+    let mut file1 = mint.new_goldenfile("text_diff1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("match2.txt").unwrap();
+
+    writeln!(file1, "monkeybrains").unwrap();
+    writeln!(file2, "foobar").unwrap();
+}
+
+#[test]
+fn log_differs() {
+    let (_, _, stderr) = run_child("log_differs_child", &[("GOLDENFILE_LOG_DIFFERS", "1")]);
+    assert!(
+        stderr.contains("goldenfiles: comparing \"binary_match2.bin\" with binary_diff\n"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("goldenfiles: comparing \"match1.txt\" with text_diff\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn log_differs_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile("binary_match2.bin").unwrap();
+    let mut file2 = mint.new_goldenfile("match1.txt").unwrap();
+
+    file1.write_all(b"\x00\x01\x02").unwrap();
+    writeln!(file2, "Hello world!").unwrap();
+}
+
+#[test]
+fn update_hint_once() {
+    let (_, _, stderr) = run_child("update_hint_once_child", &[]);
+    let count = |pattern| stderr.matches(pattern).count();
+    assert_eq!(count("goldenfile changed: text_diff"), 2, "{}", stderr);
+    assert_eq!(
+        count("note: run with `UPDATE_GOLDENFILES=1` to update goldenfiles"),
+        1,
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn update_hint_once_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile("text_diff1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("text_diff2.txt").unwrap();
+
+    writeln!(file1, "monkeybrains").unwrap();
+    writeln!(file2, "monkeybrains").unwrap();
+
+    mint.soft_check();
+    mint.assert_no_soft_failures();
+}
+
+#[test]
+fn github_annotations() {
+    let (_, stdout, _) = run_child("github_annotations_child", &[]);
+    assert!(
+        stdout.contains("::error file=tests/goldenfiles/text_diff1.txt::goldenfile changed\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn github_annotations_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_github_annotations(true);
+    let mut file = mint.new_goldenfile("text_diff1.txt").unwrap();
+
+    writeln!(file, "foobar").unwrap();
+}
+
+#[test]
+fn github_annotations_detected() {
+    let (_, stdout, _) = run_child(
+        "github_annotations_detected_child",
+        &[("GITHUB_ACTIONS", "true")],
+    );
+    assert!(
+        stdout.contains("::error file=tests/goldenfiles/text_diff1.txt::goldenfile changed\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn github_annotations_detected_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("text_diff1.txt").unwrap();
+
+    writeln!(file, "foobar").unwrap();
+}
+
+#[test]
+fn dry_run() {
+    let (passed, stdout, _) = run_child("dry_run_child", &[("GOLDENFILE_DRYRUN", "1")]);
+    assert!(passed, "{}", stdout);
+    assert!(
+        stdout.contains("Would update \"text_diff2.txt\".\n"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("text_diff1.txt"), "{}", stdout);
+    assert_eq!(
+        fs::read_to_string("tests/goldenfiles/text_diff2.txt").unwrap(),
+        "foobar\n"
+    );
+}
+
+#[test]
+fn dry_run_child() {
+    if !is_child() {
+        return;
+    }
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile("text_diff1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("text_diff2.txt").unwrap();
+
+    writeln!(file1, "Hello world!").unwrap();
+    writeln!(file2, "monkeybrains").unwrap();
+
+    assert_eq!(
+        mint.dry_run_goldenfiles(),
+        vec![PathBuf::from("text_diff2.txt")]
+    );
+}
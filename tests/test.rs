@@ -1,9 +1,18 @@
 extern crate goldenfile;
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs::{self, File};
+use std::io;
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
 
-use goldenfile::Mint;
+use goldenfile::differs::*;
+use goldenfile::{CheckError, GoldenStore, Mint, Platform};
 
 #[test]
 fn binary_match() {
@@ -42,7 +51,7 @@ fn binary_size_diff() {
 }
 
 #[test]
-#[should_panic(expected = "Files differ at byte 3")]
+#[should_panic(expected = "Files differ at byte 3: old is 3 bytes, new is 3 bytes")]
 fn binary_content_diff() {
     let mut mint = Mint::new("tests/goldenfiles");
     let mut file = mint.new_goldenfile("binary_content_diff.bin").unwrap();
@@ -71,6 +80,75 @@ fn text_diff() {
     writeln!(file2, "monkeybrains").unwrap();
 }
 
+#[test]
+fn diff_outcomes() {
+    let dir = tempfile::tempdir().unwrap();
+    let new = dir.path().join("new.txt");
+    let golden = Path::new("tests/goldenfiles/match1.txt");
+    fs::write(&new, "Hello world!\n").unwrap();
+    assert!(matches!(text_outcome(golden, &new), DiffOutcome::Match));
+
+    fs::write(&new, "Hello world?\n").unwrap();
+    match text_outcome(golden, &new) {
+        DiffOutcome::Mismatch(message) => assert!(message.contains("files differ")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    match binary_outcome(golden, &new) {
+        DiffOutcome::Mismatch(message) => {
+            assert!(message.ends_with("Files differ at byte 12: old is 13 bytes, new is 13 bytes"))
+        }
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    match binary_outcome(golden, &dir.path().join("missing.bin")) {
+        DiffOutcome::Error(err) => assert!(err.path.ends_with("missing.bin")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+fn factory_outcomes() {
+    let dir = tempfile::tempdir().unwrap();
+    let (old, new) = (dir.path().join("old.json"), dir.path().join("new.json"));
+    fs::write(&old, "{\"id\": 1, \"at\": \"noon\"}").unwrap();
+    fs::write(&new, "{\"id\": 1, \"at\": \"dusk\"}").unwrap();
+    let keys = ["at".to_string()].into_iter().collect();
+    assert!(matches!(
+        json_outcome_ignoring_keys(keys)(&old, &new),
+        DiffOutcome::Match
+    ));
+    match json_outcome(&old, &new) {
+        DiffOutcome::Mismatch(message) => {
+            assert!(message.ends_with("JSON differs at $.at: expected \"noon\", found \"dusk\""))
+        }
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+    match changed_bytes_outcome(0)(&old, &dir.path().join("missing.bin")) {
+        DiffOutcome::Error(err) => assert!(err.path.ends_with("missing.bin")),
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+#[should_panic(expected = "always different")]
+fn panic_on_mismatch_adapter() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = panic_on_mismatch(|_, _| DiffOutcome::Mismatch("always different".to_string()));
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", differ)
+        .unwrap();
+    writeln!(file, "Hello world!").unwrap();
+}
+
+#[test]
+fn temp_prefix() {
+    let mut mint = Mint::with_temp_prefix("tests/goldenfiles", "goldenfile-prefixed-");
+    let path = mint.register_goldenfile("match1.txt").unwrap();
+    fs::write(&path, "Hello world!\n").unwrap();
+
+    let dir = path.parent().unwrap().file_name().unwrap();
+    assert!(dir.to_str().unwrap().starts_with("goldenfile-prefixed-"));
+}
+
 #[test]
 #[should_panic(expected = "Path must be relative")]
 fn absolute_path() {
@@ -111,3 +189,2923 @@ fn nonempty() {
 
     writeln!(file, "Some content").unwrap();
 }
+
+#[test]
+fn code_comment_stripping_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint
+        .new_goldenfile_with_differ(
+            "comments.rs",
+            code_comment_stripping_diff(CommentLanguage::CStyle),
+        )
+        .unwrap();
+    let mut file2 = mint
+        .new_goldenfile_with_differ(
+            "comments.toml",
+            code_comment_stripping_diff(CommentLanguage::Hash),
+        )
+        .unwrap();
+
+    writeln!(file1, "// Generated at 2024-06-30.").unwrap();
+    writeln!(file1, "// By version 2.").unwrap();
+    writeln!(file1, "fn main() {{").unwrap();
+    writeln!(file1, "    println!(\"http://example.com\");").unwrap();
+    writeln!(file1, "    /* version 2").unwrap();
+    writeln!(file1, "       spans lines */").unwrap();
+    writeln!(file1, "}}").unwrap();
+    writeln!(file2, "key = \"value # not a comment\" # trailing").unwrap();
+    writeln!(file2, "other = 'value # not a comment' # trailing").unwrap();
+}
+
+#[test]
+fn code_comment_stripping_char_literals() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ(
+            "char_literals.rs",
+            code_comment_stripping_diff(CommentLanguage::CStyle),
+        )
+        .unwrap();
+
+    writeln!(file, "fn quote<'a>(s: &'a str) -> char {{").unwrap();
+    writeln!(file, "    let q = '\"'; // 2025").unwrap();
+    writeln!(file, "    let e = '\\''; // 2025").unwrap();
+    writeln!(file, "    q").unwrap();
+    writeln!(file, "}}").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "http://example.org")]
+fn code_comment_stripping_diff_code() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ(
+            "comments.rs",
+            code_comment_stripping_diff(CommentLanguage::CStyle),
+        )
+        .unwrap();
+
+    writeln!(file, "// Generated at 2024-01-01.").unwrap();
+    writeln!(file, "fn main() {{").unwrap();
+    writeln!(file, "    println!(\"http://example.org\"); // say hi").unwrap();
+    writeln!(file, "}}").unwrap();
+}
+
+#[test]
+fn addresses_normalized() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("pointers.txt", Box::new(address_normalizing_diff))
+        .unwrap();
+
+    writeln!(
+        file,
+        "Node {{ next: 0x7ffc01a2b3c4, flags: 0x1f, id: 0xDEADBEEF }}"
+    )
+    .unwrap();
+    writeln!(file, "ptr = 0x5612ab34cd50").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "new: Node { next: 0x<ADDR>, flags: 0x2f, id: 0xDEADBEEF }")]
+fn addresses_normalized_data_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("pointers.txt", Box::new(address_normalizing_diff))
+        .unwrap();
+
+    writeln!(
+        file,
+        "Node {{ next: 0x7ffc01a2b3c4, flags: 0x2f, id: 0xDEADBEEF }}"
+    )
+    .unwrap();
+    writeln!(file, "ptr = 0x5612ab34cd50").unwrap();
+}
+
+#[test]
+fn quoted_strings_ignored() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("quoted.conf", Box::new(ignore_quoted_strings_diff))
+        .unwrap();
+
+    writeln!(file, r#"name = "build-5678""#).unwrap();
+    writeln!(file, r#"path = "/tmp/out""#).unwrap();
+    writeln!(file, r#"message = "say \"bye\"""#).unwrap();
+    writeln!(file, "retries = 3").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "new: retries = 4")]
+fn quoted_strings_structure_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("quoted.conf", Box::new(ignore_quoted_strings_diff))
+        .unwrap();
+
+    writeln!(file, r#"name = "build-1234""#).unwrap();
+    writeln!(file, r#"path = "C:\\tmp\\out""#).unwrap();
+    writeln!(file, r#"message = "say \"hi\" to everyone""#).unwrap();
+    writeln!(file, "retries = 4").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "File sizes differ: Old file is 13 bytes, new file is 14 bytes")]
+fn exact_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile_exact("match1.txt").unwrap();
+
+    writeln!(file, "Hello world! ").unwrap();
+}
+
+#[test]
+fn forced_differ_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile_exact("match1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile_text("binary_match2.bin").unwrap();
+
+    writeln!(file1, "Hello world!").unwrap();
+    file2.write_all(b"\x00\x01\x02").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Files differ at byte 3")]
+fn multiple_diffs() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_verbose(true);
+    let mut file1 = mint.new_goldenfile("binary_content_diff.bin").unwrap();
+    let mut file2 = mint.new_goldenfile("text_diff1.txt").unwrap();
+
+    file1.write_all(b"\x00\x01\x02").unwrap();
+    writeln!(file2, "monkeybrains").unwrap();
+}
+
+#[test]
+#[cfg(feature = "markdown")]
+fn markdown_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("doc.md").unwrap();
+
+    writeln!(file, "Title").unwrap();
+    writeln!(file, "=====").unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "Some  paragraph text that is wrapped across").unwrap();
+    writeln!(file, "two lines.").unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "* first item").unwrap();
+    writeln!(file, "* second item").unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "```rust").unwrap();
+    writeln!(file, "fn main() {{}}").unwrap();
+    writeln!(file, "```").unwrap();
+}
+
+#[test]
+#[cfg(feature = "markdown")]
+fn markdown_emphasis_styles() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("emphasis.md").unwrap();
+
+    writeln!(
+        file,
+        "Some _emphasis_, __strong__ text and snake_case_names."
+    )
+    .unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "- run `cargo _test_` with **care**").unwrap();
+}
+
+#[test]
+#[cfg(feature = "markdown")]
+#[should_panic(expected = "Markdown differs at block 1")]
+fn markdown_intraword_underscores() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("emphasis.md").unwrap();
+
+    writeln!(
+        file,
+        "Some *emphasis*, **strong** text and snake*case*names."
+    )
+    .unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "- run `cargo _test_` with **care**").unwrap();
+}
+
+#[test]
+#[cfg(feature = "markdown")]
+#[should_panic(expected = "Markdown differs at block 3")]
+fn markdown_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("doc.md").unwrap();
+
+    writeln!(file, "# Title").unwrap();
+    writeln!(file).unwrap();
+    writeln!(
+        file,
+        "Some paragraph text that is wrapped across two lines."
+    )
+    .unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "1. first item").unwrap();
+}
+
+#[test]
+fn json_subset_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint
+        .new_goldenfile_with_differ("subset.json", json_subset_diff(JsonArrayMatch::Prefix))
+        .unwrap();
+
+    writeln!(
+        file1,
+        r#"{{"owner": {{"id": 7, "name": "x"}}, "tags": ["a", "b", "c"], "name": "widget", "size": 3}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+fn json_subset_contains_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint
+        .new_goldenfile_with_differ("subset.json", json_subset_diff(JsonArrayMatch::Contains))
+        .unwrap();
+
+    writeln!(
+        file1,
+        r#"{{"owner": {{"id": 7}}, "tags": ["c", "b", "a"], "name": "widget"}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "JSON subset not satisfied at $.owner.id: expected 7, found 8")]
+fn json_subset_mismatch() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint
+        .new_goldenfile_with_differ("subset.json", json_subset_diff(JsonArrayMatch::Prefix))
+        .unwrap();
+
+    writeln!(
+        file1,
+        r#"{{"owner": {{"id": 8}}, "tags": ["a", "b"], "name": "widget"}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+fn fmt_writer() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile_fmt_writer("match1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile_fmt_writer("match2.txt").unwrap();
+
+    write!(file1, "Hello ").unwrap();
+    writeln!(file1, "world!").unwrap();
+    file1.flush().unwrap();
+    writeln!(file2, "foobar").unwrap();
+}
+
+#[test]
+fn base64_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_base64("empty", b"", "");
+    mint.assert_golden_base64("bytes", b"\x00\x01\x02\xff", "AAEC/w==");
+    mint.assert_golden_base64("text", b"Hello world!", "SGVsbG8g\nd29ybGQh");
+}
+
+#[test]
+#[should_panic(expected = "bytes: invalid base64 golden: truncated base64")]
+fn base64_truncated() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_base64("bytes", b"\x00\x01\x02", "AAECA");
+}
+
+#[test]
+#[should_panic(expected = "bytes: invalid base64 golden: invalid base64: the final character")]
+fn base64_unused_bits() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_base64("bytes", b"\x00\x01", "AAF=");
+}
+
+#[test]
+#[should_panic(expected = "Base64 golden differs at byte 3: old is 4 bytes, new is 3 bytes")]
+fn base64_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_base64("bytes", b"\x00\x01\x03", "AAEC/w==");
+}
+
+#[test]
+fn inline_str_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_matches_str("greeting", "Hello\nworld\n", "Hello\nworld\n");
+}
+
+#[test]
+#[should_panic(expected = "greeting: files differ\n  old: world\n  new: there")]
+fn inline_str_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_matches_str("greeting", "Hello\nthere\n", "Hello\nworld\n");
+}
+
+#[test]
+fn blocks_unordered_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("blocks.txt", blocks_unordered_diff("\n\n"))
+        .unwrap();
+
+    write!(file, "gamma\n\n\nalpha\nbeta\n\nalpha\nbeta").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "--- only in old ---\nalpha\nbeta\n--- only in new ---\ndelta")]
+fn blocks_unordered_diff_blocks() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("blocks.txt", blocks_unordered_diff("\n\n"))
+        .unwrap();
+
+    write!(file, "gamma\n\nalpha\nbeta\n\ndelta\n").unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "Files differ in 2 region(s), 3 byte(s) total: old is 2 bytes, new is 4 bytes\n  0..1 (1 bytes)\n  2..4 (2 bytes)"
+)]
+fn binary_regions() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("binary_size_diff.bin", Box::new(binary_regions_diff))
+        .unwrap();
+
+    file.write_all(b"\x09\x01\x00\x00").unwrap();
+}
+
+#[test]
+fn binary_edits_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("stream.bin", Box::new(binary_edit_diff))
+        .unwrap();
+
+    file.write_all(b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f")
+        .unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "Files differ by 3 edit(s): old is 16 bytes, new is 17 bytes\n  inserted at old 4 (new 4..6): aabb\n  replaced old 12..13 with new 14..15: 0c -> cc\n  deleted old 15..16 (at new 17): 0f"
+)]
+fn binary_edits_resync_after_insertion() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("stream.bin", Box::new(binary_edit_diff))
+        .unwrap();
+
+    file.write_all(b"\x00\x01\x02\x03\xaa\xbb\x04\x05\x06\x07\x08\x09\x0a\x0b\xcc\x0d\x0e")
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "old is 200000 bytes, new is 200000 bytes")]
+fn binary_edits_unrelated_files() {
+    let noise = |mut seed: u64| -> Vec<u8> {
+        (0..200_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                (seed >> 56) as u8
+            })
+            .collect()
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let (old, new) = (dir.path().join("old.bin"), dir.path().join("new.bin"));
+    fs::write(&old, noise(1)).unwrap();
+    fs::write(&new, noise(2)).unwrap();
+
+    binary_edit_diff(&old, &new);
+}
+
+#[test]
+fn record_multiset_reordered() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("records.bin", record_multiset_diff(4))
+        .unwrap();
+
+    file.write_all(b"\x00\x00\x00\x01\x00\x00\x00\x01\x00\x00\x00\x02")
+        .unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "Records differ: old is 12 bytes, new is 12 bytes\n  only in old: 00000001\n  only in new: 00000003"
+)]
+fn record_multiset_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("records.bin", record_multiset_diff(4))
+        .unwrap();
+
+    file.write_all(b"\x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x03")
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "length 6 is not a multiple of the record size 4")]
+fn record_multiset_partial_record() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("records.bin", record_multiset_diff(4))
+        .unwrap();
+
+    file.write_all(b"\x00\x00\x00\x01\x00\x00").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "monkeybrains")]
+fn try_check_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("text_diff2.txt").unwrap();
+
+    writeln!(file, "monkeybrains").unwrap();
+
+    match mint.try_check_goldenfiles() {
+        Err(CheckError::Changed { file, message }) => {
+            assert_eq!(file, PathBuf::from("text_diff2.txt"));
+            assert!(message.contains("foobar"));
+        }
+        other => panic!("unexpected result: {:?}", other.is_ok()),
+    }
+}
+
+#[test]
+#[should_panic(expected = "Error reading \"tests/goldenfiles/missing.bin\"")]
+fn try_check_io_error() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.new_goldenfile("missing.bin").unwrap();
+
+    assert!(matches!(
+        mint.try_check_goldenfiles(),
+        Err(CheckError::Io { .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "bad.txt\": invalid utf-8")]
+fn try_check_io_message_from_custom_differ() {
+    let dir = tempfile::tempdir().unwrap();
+    let bad = dir.path().join("bad.txt");
+    fs::write(&bad, b"\xff\n").unwrap();
+    let payload =
+        std::panic::catch_unwind(|| goldenfile::differs::text_diff(&bad, &bad)).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap().clone();
+
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ: Differ = Box::new(move |_, _| panic!("{}", message));
+    mint.new_goldenfile_with_differ("match1.txt", differ)
+        .unwrap();
+
+    assert!(matches!(
+        mint.try_check_goldenfiles(),
+        Err(CheckError::Changed { .. })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "invalid utf-8")]
+fn text_diff_invalid_utf8() {
+    let dir = tempfile::tempdir().unwrap();
+    let (old, new) = (dir.path().join("old.txt"), dir.path().join("new.txt"));
+    fs::write(&old, b"\xff\n").unwrap();
+    fs::write(&new, "").unwrap();
+
+    goldenfile::differs::text_diff(&old, &new);
+}
+
+#[test]
+#[cfg(feature = "html")]
+fn html_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("page.html").unwrap();
+
+    writeln!(file, "<HTML><body><!-- generated -->").unwrap();
+    writeln!(file, "<div class='box' id=main><p>Hello &#38;").unwrap();
+    writeln!(file, "  welcome</p><img alt=\"\" src=\"a.png\"/></div>").unwrap();
+    writeln!(file, "<div><p>Second</p></div></body></HTML>").unwrap();
+}
+
+#[test]
+#[cfg(feature = "html")]
+#[should_panic(expected = "HTML differs at /html[1]/body[1]/div[2]/p[1]/text()[1]")]
+fn html_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("page.html").unwrap();
+
+    writeln!(file, "<html><body><div id=\"main\" class=\"box\">").unwrap();
+    writeln!(
+        file,
+        "<p>Hello &amp; welcome</p><img src=\"a.png\" alt=\"\">"
+    )
+    .unwrap();
+    writeln!(file, "</div><div><p>Third</p></div></body></html>").unwrap();
+}
+
+#[test]
+#[cfg(feature = "html")]
+fn html_preformatted_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("preformatted.html").unwrap();
+
+    writeln!(file, "<div>  Some\n  text  </div><pre>").unwrap();
+    writeln!(file, "fn main() {{\n    <b>run</b>();\n}}</pre>").unwrap();
+    writeln!(file, "<textarea>a  &lt;b&gt;\n  c</textarea>").unwrap();
+}
+
+#[test]
+#[cfg(feature = "html")]
+#[should_panic(expected = "HTML differs at /pre[1]/text()[1]")]
+fn html_preformatted_whitespace_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("preformatted.html").unwrap();
+
+    writeln!(file, "<div>Some text</div>").unwrap();
+    writeln!(file, "<pre>fn main() {{ <b>run</b>(); }}</pre>").unwrap();
+    writeln!(file, "<textarea>a  &lt;b&gt;\n  c</textarea>").unwrap();
+}
+
+#[test]
+fn ignore_header() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.ignore_leading_lines(1);
+    let mut file1 = mint.new_goldenfile("licensed.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("binary_match2.bin").unwrap();
+
+    writeln!(file1, "// Copyright 2026 Example Corp.").unwrap();
+    writeln!(file1, "// Licensed under MIT.").unwrap();
+    writeln!(file1, "body line").unwrap();
+    file2.write_all(b"\x00\x01\x02").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "other body")]
+fn ignore_header_until() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.ignore_header_until("Licensed under");
+    let mut file = mint.new_goldenfile("licensed.txt").unwrap();
+
+    writeln!(file, "// Copyright 2026 Example Corp.").unwrap();
+    writeln!(file, "// All rights reserved.").unwrap();
+    writeln!(file, "// Licensed under MIT.").unwrap();
+    writeln!(file, "other body").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "tests/goldenfiles/licensed.txt")]
+fn ignore_header_names_golden() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.ignore_leading_lines(1);
+    let mut file = mint.new_goldenfile("licensed.txt").unwrap();
+
+    writeln!(file, "// Copyright 2026 Example Corp.").unwrap();
+    writeln!(file, "// Licensed under MIT.").unwrap();
+    writeln!(file, "other body").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "tests/goldenfiles/licensed.txt: files differ")]
+fn ignore_header_custom_differ_names_golden() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.ignore_leading_lines(1);
+    let mut file = mint
+        .new_goldenfile_with_differ("licensed.txt", Box::new(goldenfile::differs::text_diff))
+        .unwrap();
+
+    writeln!(file, "// Copyright 2026 Example Corp.").unwrap();
+    writeln!(file, "// Licensed under MIT.").unwrap();
+    writeln!(file, "other body").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Files differ at byte 5")]
+fn ignore_header_binary_differ() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.ignore_leading_lines(1);
+    let mut file = mint
+        .new_goldenfile_with_differ("header.bin", Box::new(binary_diff))
+        .unwrap();
+
+    file.write_all(b"HDR1\x01\x02\x10\x00PAYLOAD-v1").unwrap();
+}
+
+#[test]
+fn platform_transforms() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_platform(
+            "match2.txt",
+            vec![
+                (Platform::Windows, Box::new(|s| s.replace('\\', "/"))),
+                (Platform::Unix, Box::new(|s| s.replace("unix", "foo"))),
+                (Platform::Linux, Box::new(|s| s.replace("linux", "bar"))),
+                (Platform::MacOs, Box::new(|s| s.replace("macos", "bar"))),
+            ],
+        )
+        .unwrap();
+
+    if cfg!(windows) {
+        writeln!(file, "foo\\bar").unwrap();
+    } else if cfg!(target_os = "linux") {
+        writeln!(file, "unixlinux").unwrap();
+    } else if cfg!(target_os = "macos") {
+        writeln!(file, "unixmacos").unwrap();
+    } else {
+        writeln!(file, "foobar").unwrap();
+    }
+}
+
+#[test]
+fn is_updating() {
+    let mint = Mint::new("tests/goldenfiles");
+    let expected = ["UPDATE_GOLDENFILES", "REGENERATE_GOLDENFILES"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|value| value == "1"));
+
+    assert_eq!(mint.is_updating(), expected);
+}
+
+#[test]
+fn timestamp_tolerant_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = timestamp_tolerant_diff("%Y-%m-%dT%H:%M:%SZ", Duration::from_secs(3));
+    let mut file = mint
+        .new_goldenfile_with_differ("timestamps.log", differ)
+        .unwrap();
+
+    writeln!(file, "start 2024-01-02T03:04:07Z").unwrap();
+    writeln!(file, "end 2024-01-02T03:04:10Z").unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "Timestamp 2 differs by 5s, more than the 3s tolerance: old 2024-01-02T03:04:09Z, new 2024-01-02T03:04:14Z"
+)]
+fn timestamp_tolerant_diff_window() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = timestamp_tolerant_diff("%Y-%m-%dT%H:%M:%SZ", Duration::from_secs(3));
+    let mut file = mint
+        .new_goldenfile_with_differ("timestamps.log", differ)
+        .unwrap();
+
+    writeln!(file, "start 2024-01-02T03:04:05Z").unwrap();
+    writeln!(file, "end 2024-01-02T03:04:14Z").unwrap();
+}
+
+#[test]
+fn guard() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_check_on_drop(true);
+    let mut file1 = mint.new_goldenfile_guard("match1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile_guard("match2.txt").unwrap();
+
+    writeln!(file1, "Hello world!").unwrap();
+    writeln!(file2, "foobar").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "match1.txt")]
+fn guard_checks_on_drop() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_check_on_drop(true);
+    let mut file = mint.new_goldenfile_guard("match1.txt").unwrap();
+
+    writeln!(file, "Goodbye world!").unwrap();
+    drop(file);
+    panic!("goldenfile not checked when its writer was dropped");
+}
+
+#[test]
+#[should_panic(expected = "invalid JSON: invalid escape at line 2 column 5")]
+fn json_error_column_in_chars() {
+    let dir = tempfile::tempdir().unwrap();
+    let (old, new) = (dir.path().join("old.json"), dir.path().join("new.json"));
+    fs::write(&old, "[\n  \"\\é\"]\n").unwrap();
+    fs::write(&new, "[]\n").unwrap();
+
+    goldenfile::differs::json_diff(&old, &new);
+}
+
+#[test]
+fn jsonl_key_order() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("records.jsonl").unwrap();
+
+    writeln!(file, r#"{{"name":"alpha","tags":["a","b"],"id":1}}"#).unwrap();
+    writeln!(file, r#"{{"tags":[],"id":2,"name":"beta"}}"#).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "line 1: record differs at $.tags[1]: expected \"b\", found \"c\"")]
+fn jsonl_record_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("records.jsonl").unwrap();
+
+    writeln!(file, r#"{{"id":1,"name":"alpha","tags":["a","c"]}}"#).unwrap();
+    writeln!(file, r#"{{"id":2,"name":"beta","tags":[]}}"#).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "match2.txt: goldenfile was never written to")]
+fn forbid_empty() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.forbid_empty();
+    let mut file1 = mint.new_goldenfile("match1.txt").unwrap();
+    mint.new_goldenfile("match2.txt").unwrap();
+
+    writeln!(file1, "Hello world!").unwrap();
+}
+
+#[test]
+fn json_numbers_by_value() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("numbers.json", Box::new(json_diff))
+        .unwrap();
+
+    writeln!(file, r#"{{"ok": 1.0, "count": 1e2, "ratio": 25E-1}}"#).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "JSON differs at $.count: expected 100, found 100.5")]
+fn json_numbers_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("numbers.json", Box::new(json_diff))
+        .unwrap();
+
+    writeln!(file, r#"{{"count": 100.5, "ratio": 2.5, "ok": 1}}"#).unwrap();
+}
+
+#[test]
+fn transformed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = transformed_diff(
+        Arc::new(|text: &str| text.to_lowercase()),
+        Box::new(goldenfile::differs::text_diff),
+    );
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", differ)
+        .unwrap();
+
+    writeln!(file, "HELLO World!").unwrap();
+}
+
+#[test]
+fn transformed_bytes() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = transformed_bytes_diff(
+        Arc::new(|mut contents| {
+            contents.retain(|&b| b != 0);
+            contents
+        }),
+        Box::new(binary_diff),
+    );
+    let mut file = mint
+        .new_goldenfile_with_differ("binary_match2.bin", differ)
+        .unwrap();
+
+    file.write_all(b"\x01\x00\x00\x02\x00").unwrap();
+}
+
+#[test]
+fn write_goldenfile() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.write_goldenfile("match1.txt", "Hello world!\n")
+        .unwrap();
+    mint.set_atomic_writes(true);
+    mint.write_goldenfile("subdir/file1.txt", "File in subdir\n")
+        .unwrap();
+}
+
+#[test]
+fn json_ignoring_keys() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let keys = ["id", "created_at"].map(String::from).into();
+    let mut file = mint
+        .new_goldenfile_with_differ("volatile.json", json_diff_ignoring_keys(keys))
+        .unwrap();
+
+    writeln!(
+        file,
+        r#"{{"id": 90, "name": "root", "children": [{{"name": "leaf", "id": 91}}]}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "JSON differs at $.children[0].name: expected \"leaf\", found \"branch\""
+)]
+fn json_ignoring_keys_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let keys = ["id", "created_at"].map(String::from).into();
+    let mut file = mint
+        .new_goldenfile_with_differ("volatile.json", json_diff_ignoring_keys(keys))
+        .unwrap();
+
+    writeln!(
+        file,
+        r#"{{"id": 90, "name": "root", "children": [{{"name": "branch", "id": 91}}]}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+fn json_ignoring_paths() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let paths = ["$.id", "$.children[*].id", "$.children[0][\"created_at\"]"];
+    let differ = json_diff_ignoring_paths(paths.map(String::from).into());
+    let mut file = mint
+        .new_goldenfile_with_differ("volatile.json", differ)
+        .unwrap();
+
+    writeln!(
+        file,
+        r#"{{"id": 90, "name": "root", "children": [{{"name": "leaf", "id": 91}}]}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "JSON differs at $.children[0].id: expected 18, found 91")]
+fn json_ignoring_paths_only_at_path() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let paths = ["$.id", "$.children[0].created_at"];
+    let differ = json_diff_ignoring_paths(paths.map(String::from).into());
+    let mut file = mint
+        .new_goldenfile_with_differ("volatile.json", differ)
+        .unwrap();
+
+    writeln!(
+        file,
+        r#"{{"id": 90, "name": "root", "children": [{{"name": "leaf", "id": 91}}]}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "xml")]
+fn xml_ignoring_paths() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = xml_diff_ignoring_paths(vec!["//meta/@generatedAt".to_string()]);
+    let mut file = mint
+        .new_goldenfile_with_differ("config.xml", differ)
+        .unwrap();
+
+    writeln!(
+        file,
+        "<config><meta version='3' generatedAt='2025-06-07T08:09:10Z'/>"
+    )
+    .unwrap();
+    writeln!(
+        file,
+        "<server port=\"8080\" host=\"localhost\"><timeout> 30 </timeout>"
+    )
+    .unwrap();
+    writeln!(file, "</server></config>").unwrap();
+}
+
+#[test]
+#[cfg(feature = "xml")]
+#[should_panic(
+    expected = "XML differs at /config[1]/server[1]/@port: expected \"8080\", found \"9090\""
+)]
+fn xml_ignoring_paths_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = xml_diff_ignoring_paths(vec!["//meta/@generatedAt".to_string()]);
+    let mut file = mint
+        .new_goldenfile_with_differ("config.xml", differ)
+        .unwrap();
+
+    writeln!(
+        file,
+        "<config><meta version='3' generatedAt='2025-06-07T08:09:10Z'/>"
+    )
+    .unwrap();
+    writeln!(
+        file,
+        "<server port=\"9090\" host=\"localhost\"><timeout>30</timeout>"
+    )
+    .unwrap();
+    writeln!(file, "</server></config>").unwrap();
+}
+
+#[test]
+fn golden_reader() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_reader("match1.txt", &mut "Hello world!\n".as_bytes())
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Files differ at byte 3")]
+fn golden_reader_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut reader = b"\x00\x01\x03".as_slice();
+    mint.assert_golden_reader("binary_match2.bin", &mut reader)
+        .unwrap();
+    panic!("goldenfile not checked after reading");
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test]
+fn golden_debug() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_debug("point.txt", &Point { x: 1, y: -2 })
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "point.txt: files differ\n  old:     y: -2,\n  new:     y: 2,")]
+fn golden_debug_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_debug("point.txt", &Point { x: 1, y: 2 })
+        .unwrap();
+    panic!("goldenfile not checked after writing");
+}
+
+fn checked_get(values: &[i32], index: usize) -> i32 {
+    assert!(
+        index < values.len(),
+        "index out of range: {} >= {}",
+        index,
+        values.len()
+    );
+    values[index]
+}
+
+#[test]
+fn golden_panic() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_panic("panic_message.txt", || {
+        checked_get(&[1, 2, 3], 7);
+    })
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "\"panic_message.txt\": expected a panic")]
+fn golden_panic_missing() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_panic("panic_message.txt", || {
+        checked_get(&[1, 2, 3], 2);
+    })
+    .unwrap();
+}
+
+fn is_numeric_id(word: &str) -> bool {
+    word.len() > 1 && word.chars().all(|c| c.is_ascii_digit())
+}
+
+#[test]
+fn sequential_ids() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("ids.txt", sequential_id_diff(is_numeric_id))
+        .unwrap();
+
+    writeln!(file, "order 9001 created by user 15").unwrap();
+    writeln!(file, "order 9001 shipped to user 15").unwrap();
+    writeln!(file, "order 9002 created by user 15").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "order <ID:1> shipped")]
+fn sequential_ids_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("ids.txt", sequential_id_diff(is_numeric_id))
+        .unwrap();
+
+    writeln!(file, "order 9001 created by user 15").unwrap();
+    writeln!(file, "order 9003 shipped to user 15").unwrap();
+    writeln!(file, "order 9002 created by user 15").unwrap();
+}
+
+#[test]
+fn fuzzy_lines() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("ids.txt", fuzzy_line_diff(0.4))
+        .unwrap();
+
+    writeln!(file, "order 4312 created by user 77").unwrap();
+    writeln!(file, "order 4312 shipped to user 78").unwrap();
+    writeln!(file, "order 4313 created by user 77").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "0.6667 of lines changed, more than the 0.4 allowed")]
+fn fuzzy_lines_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("ids.txt", fuzzy_line_diff(0.4))
+        .unwrap();
+
+    writeln!(file, "order 4312 created by user 78").unwrap();
+    writeln!(file, "order 4312 shipped to user 78").unwrap();
+    writeln!(file, "order 4313 created by user 77").unwrap();
+}
+
+#[test]
+fn matrix_within_epsilon() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("grid.txt", matrix_diff(1e-3))
+        .unwrap();
+
+    writeln!(file, "1.0004   2.5 -3").unwrap();
+    writeln!(file, "0.0012 NaN 4000.0").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Element (2, 3) differs by more than 0.001: old 4000, new 4000.1")]
+fn matrix_element_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("grid.txt", matrix_diff(1e-3))
+        .unwrap();
+
+    writeln!(file, "1.0 2.5 -3.0").unwrap();
+    writeln!(file, "0.001 NaN 4000.1").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Matrix shapes differ: old is 2x3, new is 3x2")]
+fn matrix_shape_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("grid.txt", matrix_diff(1e-3))
+        .unwrap();
+
+    writeln!(file, "1.0 2.5\n-3.0 0.001\nNaN 4e3").unwrap();
+}
+
+#[test]
+fn similarity_ratio() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", similarity_ratio_diff(0.9))
+        .unwrap();
+
+    writeln!(file, "Hello world?").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "similarity ratio 0.9231 is below the 0.95 required")]
+fn similarity_ratio_below() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", similarity_ratio_diff(0.95))
+        .unwrap();
+
+    writeln!(file, "Hello world?").unwrap();
+}
+
+#[test]
+#[cfg(feature = "dot")]
+fn dot_reordered() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("graph.dot").unwrap();
+
+    writeln!(file, "// regenerated").unwrap();
+    writeln!(file, "digraph deps {{").unwrap();
+    writeln!(file, "  ui -> core").unwrap();
+    writeln!(file, "  app -> ui [style=\"dashed\"]").unwrap();
+    writeln!(file, "  node [shape=box]").unwrap();
+    writeln!(file, "  app [color=blue label=App]").unwrap();
+    writeln!(file, "  app -> core").unwrap();
+    writeln!(file, "  rankdir=LR").unwrap();
+    writeln!(file, "}}").unwrap();
+}
+
+#[test]
+#[cfg(feature = "dot")]
+#[should_panic(expected = "Graphs differ\n  only in old: ui -> core\n  only in new: core -> ui")]
+fn dot_edge_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("graph.dot").unwrap();
+
+    writeln!(file, "digraph deps {{ rankdir=LR; node [shape=box];").unwrap();
+    writeln!(file, "  app [label=\"App\", color=blue]").unwrap();
+    writeln!(file, "  {{app}} -> core; app -> \"ui\" [style=dashed]").unwrap();
+    writeln!(file, "  core -> ui").unwrap();
+    writeln!(file, "}}").unwrap();
+}
+
+#[test]
+fn golden_sibling_update() {
+    let dir = tempfile::tempdir().unwrap();
+    let produced = dir.path().join("report.txt");
+    fs::write(&produced, "Hello world!\n").unwrap();
+
+    let mut mint = Mint::new("tests/goldenfiles");
+    let golden = mint.register_golden_sibling(&produced);
+    mint.update_goldenfiles();
+
+    assert_eq!(golden, dir.path().join("report.txt.golden"));
+    assert_eq!(fs::read_to_string(&golden).unwrap(), "Hello world!\n");
+}
+
+#[test]
+#[should_panic(expected = "report.txt.golden")]
+fn golden_sibling_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let produced = dir.path().join("report.txt");
+    fs::write(&produced, "Hello world!\n").unwrap();
+    fs::write(dir.path().join("report.txt.golden"), "Goodbye world!\n").unwrap();
+
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.register_golden_sibling(&produced);
+}
+
+#[test]
+fn soft_check() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile("text_diff1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("match2.txt").unwrap();
+    let mut file3 = mint.new_goldenfile("binary_content_diff.bin").unwrap();
+
+    writeln!(file1, "monkeybrains").unwrap();
+    writeln!(file2, "foobar").unwrap();
+    file3.write_all(b"\x00\x01\x02").unwrap();
+
+    let failures: Vec<PathBuf> = mint
+        .soft_check()
+        .iter()
+        .map(|failure| match failure {
+            CheckError::Changed { file, .. } => file.clone(),
+            CheckError::Io { error, .. } => panic!("unexpected IO error: {}", error),
+        })
+        .collect();
+    assert_eq!(
+        failures,
+        [
+            PathBuf::from("text_diff1.txt"),
+            PathBuf::from("binary_content_diff.bin")
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "text_diff2.txt")]
+fn soft_check_then_register() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile("text_diff1.txt").unwrap();
+    writeln!(file1, "monkeybrains").unwrap();
+    assert_eq!(mint.soft_check().len(), 1);
+
+    let mut file2 = mint.new_goldenfile("text_diff2.txt").unwrap();
+    writeln!(file2, "monkeybrains").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "2 goldenfile(s) failed:")]
+fn assert_no_soft_failures() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file1 = mint.new_goldenfile("text_diff1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("text_diff2.txt").unwrap();
+
+    writeln!(file1, "monkeybrains").unwrap();
+    writeln!(file2, "monkeybrains").unwrap();
+
+    mint.soft_check();
+    mint.assert_no_soft_failures();
+}
+
+#[test]
+fn json_jq() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = json_jq_diff(".children[] | .name, (keys | length)");
+    let mut file = mint
+        .new_goldenfile_with_differ("volatile.json", differ)
+        .unwrap();
+
+    writeln!(
+        file,
+        r#"{{"id": 2, "name": "renamed", "children": [{{"name": "leaf", "x": 1, "y": 2}}]}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "jq filter failed: cannot iterate over number")]
+fn json_jq_filter_error() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = json_jq_diff(".children[]");
+    let mut file = mint
+        .new_goldenfile_with_differ("volatile.json", differ)
+        .unwrap();
+
+    writeln!(file, r#"{{"children": 3}}"#).unwrap();
+}
+
+#[test]
+fn text_opts() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = text_diff_opts(TextDiffOptions {
+        trim_trailing_ws: true,
+        tab_width: Some(4),
+        ignore_final_newline: true,
+        ignore_blank_lines: true,
+    });
+    let mut file = mint.new_goldenfile_with_differ("tabs.txt", differ).unwrap();
+
+    write!(file, "fn main() {{  \n\n\tif x {{\n\t\ty(); \t\n\t}}\n}}").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "tabs.txt")]
+fn text_opts_default() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = text_diff_opts(TextDiffOptions::default());
+    let mut file = mint.new_goldenfile_with_differ("tabs.txt", differ).unwrap();
+
+    write!(file, "fn main() {{\n\tif x {{\n\t\ty();\n\t}}\n}}\n").unwrap();
+}
+
+#[test]
+fn rename_goldenfile() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("match1.txt").unwrap();
+    writeln!(file, "Hello world!").unwrap();
+
+    mint.rename_goldenfile("match1.txt", "subdir/moved.txt")
+        .unwrap();
+    assert!(mint.rename_goldenfile("match1.txt", "other.txt").is_err());
+}
+
+#[test]
+fn rename_goldenfile_update() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("rename_from.txt"), "old\n").unwrap();
+
+    let mut mint = Mint::new(dir.path());
+    let mut file = mint.new_goldenfile("rename_from.txt").unwrap();
+    writeln!(file, "new").unwrap();
+    mint.rename_goldenfile("rename_from.txt", "renamed/rename_to.txt")
+        .unwrap();
+    mint.update_goldenfiles();
+
+    assert!(!dir.path().join("rename_from.txt").exists());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("renamed/rename_to.txt")).unwrap(),
+        "new\n"
+    );
+}
+
+#[test]
+fn golden_embedded() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_embedded_str(
+        "match1.txt",
+        "Hello world!\n",
+        include_str!("goldenfiles/match1.txt"),
+    );
+    mint.assert_golden_embedded(
+        "binary_match2.bin",
+        b"\x00\x01\x02",
+        include_bytes!("goldenfiles/binary_match2.bin"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Files differ at byte 3")]
+fn golden_embedded_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_golden_embedded(
+        "binary_match2.bin",
+        b"\x00\x01\x03",
+        include_bytes!("goldenfiles/binary_match2.bin"),
+    );
+}
+
+#[test]
+fn golden_embedded_names_golden() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        mint.assert_golden_embedded_str(
+            "match1.txt",
+            "Hello there!\n",
+            include_str!("goldenfiles/match1.txt"),
+        )
+    }))
+    .unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(
+        message.starts_with("match1.txt: files differ\n"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn uuid_scrub() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = uuid_scrub_diff(Box::new(goldenfile::differs::text_diff), true);
+    let mut file = mint
+        .new_goldenfile_with_differ("uuids.txt", differ)
+        .unwrap();
+
+    writeln!(file, "created 16FD2706-8BAF-433B-82EB-8C7FADA847DA").unwrap();
+    writeln!(
+        file,
+        "linked 16fd2706-8baf-433b-82eb-8c7fada847da to 886313e1-3b8a-5372-9b90-0c9aee199e5d"
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "linked <UUID:1> to <UUID:2>")]
+fn uuid_scrub_numbered_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = uuid_scrub_diff(Box::new(goldenfile::differs::text_diff), true);
+    let mut file = mint
+        .new_goldenfile_with_differ("uuids.txt", differ)
+        .unwrap();
+
+    writeln!(file, "created 16fd2706-8baf-433b-82eb-8c7fada847da").unwrap();
+    writeln!(
+        file,
+        "linked 886313e1-3b8a-5372-9b90-0c9aee199e5d to 16fd2706-8baf-433b-82eb-8c7fada847da"
+    )
+    .unwrap();
+}
+
+#[test]
+fn path_resolver() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_path_resolver(|path| {
+        PathBuf::from("tests/goldenfiles").join(path.to_str().unwrap().replace("::", "/"))
+    });
+    let mut file = mint.new_goldenfile("subdir::file1.txt").unwrap();
+
+    writeln!(file, "File in subdir").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "  old: Hello world!\n  new: Hello World?\n             ^^^^^^")]
+fn text_diff_columns() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("match1.txt").unwrap();
+
+    writeln!(file, "Hello World?").unwrap();
+}
+
+#[test]
+fn goldencfg_epsilon() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("measurements.txt").unwrap();
+
+    writeln!(file, "latency 12.54 ms").unwrap();
+    writeln!(file, "throughput 1200.05 req/s").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Number 1 differs by more than 0.1: old 12.5, new 12.7")]
+fn goldencfg_epsilon_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("measurements.txt").unwrap();
+
+    writeln!(file, "latency 12.7 ms").unwrap();
+    writeln!(file, "throughput 1.2e3 req/s").unwrap();
+}
+
+#[test]
+fn goldencfg_binary_tolerance() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::write(golden.path().join("image.bin"), [10, 20, 30, 40]).unwrap();
+    fs::write(
+        golden.path().join("image.bin.goldencfg"),
+        "max_mean_byte_diff = 1.5\n",
+    )
+    .unwrap();
+    let mut mint = Mint::new(golden.path());
+
+    mint.write_goldenfile("image.bin", [11, 18, 30, 41])
+        .unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "image.bin: 2 bytes differ, more than 1, starting at byte 1: old is 4 bytes, new is 4 bytes"
+)]
+fn goldencfg_binary_tolerance_diff() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::write(golden.path().join("image.bin"), [10, 20, 30, 40]).unwrap();
+    fs::write(
+        golden.path().join("image.bin.goldencfg"),
+        "max_changed_bytes = 1\n",
+    )
+    .unwrap();
+    let mut mint = Mint::new(golden.path());
+
+    mint.write_goldenfile("image.bin", [11, 20, 30, 41])
+        .unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "image.bin: mean byte difference 2.500 is more than 1.5: old is 4 bytes, new is 4 bytes"
+)]
+fn goldencfg_binary_mean_diff() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::write(golden.path().join("image.bin"), [10, 20, 30, 40]).unwrap();
+    fs::write(
+        golden.path().join("image.bin.goldencfg"),
+        "max_mean_byte_diff = 1.5\n",
+    )
+    .unwrap();
+    let mut mint = Mint::new(golden.path());
+
+    mint.write_goldenfile("image.bin", [13, 18, 30, 45])
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "image.bin: File sizes differ: old is 4 bytes, new is 3 bytes")]
+fn goldencfg_binary_size_diff() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::write(golden.path().join("image.bin"), [10, 20, 30, 40]).unwrap();
+    fs::write(
+        golden.path().join("image.bin.goldencfg"),
+        "max_changed_bytes = 1\n",
+    )
+    .unwrap();
+    let mut mint = Mint::new(golden.path());
+
+    mint.write_goldenfile("image.bin", [10, 20, 30]).unwrap();
+}
+
+#[test]
+fn shared_golden() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    for name in ["first.txt", "second.txt"] {
+        let path = mint
+            .register_shared_golden(name, "shared/output.txt")
+            .unwrap();
+        fs::write(path, "A large expected output\nshared by several tests.\n").unwrap();
+    }
+}
+
+#[test]
+#[should_panic(expected = "conflicting new contents")]
+fn shared_golden_conflicting_update() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    for (name, contents) in [("first.txt", "one\n"), ("second.txt", "two\n")] {
+        let path = mint
+            .register_shared_golden(name, "shared/output.txt")
+            .unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    mint.update_goldenfiles();
+}
+
+#[test]
+#[should_panic(expected = "Error reading new contents of \"second.txt\" for shared goldenfile")]
+fn shared_golden_missing_update() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let path = mint
+        .register_shared_golden("first.txt", "shared/output.txt")
+        .unwrap();
+    fs::write(path, "one\n").unwrap();
+    mint.register_shared_golden("second.txt", "shared/output.txt")
+        .unwrap();
+    mint.update_goldenfiles();
+}
+
+#[test]
+fn http_message() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let dropped = ["Date", "x-request-id"].map(String::from).into();
+    let mut file = mint
+        .new_goldenfile_with_differ("response.http", http_message_diff(dropped))
+        .unwrap();
+
+    write!(
+        file,
+        "HTTP/1.1 200 OK\ncontent-length: 16\nX-Request-Id: 2\n\
+         content-type: application/json\n\n{{\"status\": \"ok\"}}"
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "HTTP body differs")]
+fn http_message_body_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let dropped = ["date", "x-request-id"].map(String::from).into();
+    let mut file = mint
+        .new_goldenfile_with_differ("response.http", http_message_diff(dropped))
+        .unwrap();
+
+    write!(
+        file,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 16\r\n\r\n\
+         {{\"status\": \"no\"}}"
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "HTTP headers differ")]
+fn http_message_header_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("response.http", http_message_diff(Default::default()))
+        .unwrap();
+
+    write!(
+        file,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 16\r\n\r\n\
+         {{\"status\": \"ok\"}}"
+    )
+    .unwrap();
+}
+
+#[test]
+fn lockfile_reordered() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("app.lock", Box::new(lockfile_diff))
+        .unwrap();
+
+    write!(
+        file,
+        "version = 3\n\n\
+         [[package]]\nname = \"serde\"\nversion = \"1.0.188\"\n\
+         checksum = \"cf9e0fcba69a370eed61bcf2b728575f726b50b55cba78064753d708ddc7549e\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\n\
+         [[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\"anyhow\", \"serde\"]\n\n\
+         [[package]]\nname = \"anyhow\"\nversion = \"1.0.75\"\n\
+         source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+         checksum = \"a4668cab20f66d8d020e1fbc0ebe47217433c1b6c8f2040faf858554e394ace6\"\n"
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Lockfiles differ\n  \
+                           changed: app 0.1.0 (dependencies)\n  \
+                           updated: serde 1.0.188 -> 1.0.190\n  \
+                           removed: anyhow 1.0.75\n  \
+                           added: thiserror 1.0.75")]
+fn lockfile_package_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let golden = "tests/goldenfiles/app.lock".as_ref();
+    let new = dir.path().join("app.lock");
+    let text = fs::read_to_string(golden).unwrap();
+    fs::write(
+        &new,
+        text.replace("1.0.188", "1.0.190")
+            .replace(" \"anyhow\",\n", "")
+            .replace("name = \"anyhow\"", "name = \"thiserror\""),
+    )
+    .unwrap();
+
+    lockfile_diff(golden, &new);
+}
+
+#[test]
+#[should_panic(expected = "Lockfiles differ\n  changed: anyhow 1.0.75 (checksum, source)")]
+fn lockfile_package_fields_changed() {
+    let dir = tempfile::tempdir().unwrap();
+    let golden = "tests/goldenfiles/app.lock".as_ref();
+    let new = dir.path().join("app.lock");
+    let text = fs::read_to_string(golden).unwrap();
+    fs::write(
+        &new,
+        text.replacen("registry+https", "git+https", 1)
+            .replace("a4668cab", "b4668cab"),
+    )
+    .unwrap();
+
+    lockfile_diff(golden, &new);
+}
+
+#[test]
+fn prepare_dirs() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let paths = ["subdir/file1.txt", "subdir/nested/deep.txt"]
+        .map(|name| mint.register_goldenfile(name).unwrap());
+    mint.prepare_dirs();
+
+    for (path, line) in paths.iter().zip(["File in subdir", "Deeply nested"]) {
+        writeln!(File::create(path).unwrap(), "{}", line).unwrap();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_target_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_compare_symlinks(true);
+    let path = mint.register_goldenfile("link.txt").unwrap();
+    std::os::unix::fs::symlink("match1.txt", path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+#[should_panic(
+    expected = "old goldenfile is a symlink to \"match1.txt\" but new goldenfile is a regular file"
+)]
+fn symlink_regular_file_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_compare_symlinks(true);
+    let mut file = mint.new_goldenfile("link.txt").unwrap();
+    writeln!(file, "Hello world!").unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_update() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("link.txt"), "Hello world!\n").unwrap();
+
+    let mut mint = Mint::new(dir.path());
+    mint.set_compare_symlinks(true);
+    let path = mint.register_goldenfile("link.txt").unwrap();
+    std::os::unix::fs::symlink("target.txt", path).unwrap();
+    mint.update_goldenfiles();
+
+    let link = dir.path().join("link.txt");
+    assert_eq!(fs::read_link(link).unwrap(), PathBuf::from("target.txt"));
+}
+
+#[test]
+#[cfg(feature = "rustfmt")]
+fn rustfmt_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("generated.rs").unwrap();
+
+    writeln!(file, "fn main(){{let x=vec![1,2,3];").unwrap();
+    writeln!(file, "println!(\"{{:?}}\",x);}}").unwrap();
+}
+
+#[test]
+#[cfg(feature = "rustfmt")]
+#[should_panic(expected = "rustfmt failed")]
+fn rustfmt_invalid() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("generated.rs").unwrap();
+
+    writeln!(file, "fn main() {{").unwrap();
+}
+
+/// `text` without the ANSI escape sequences that color it.
+fn strip_colors(text: &str) -> String {
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("\x1b[") {
+        plain.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('m')
+            .map_or(rest.len(), |end| start + end + 1);
+        rest = &rest[end..];
+    }
+    plain.push_str(rest);
+    plain
+}
+
+#[test]
+fn side_by_side_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let (old, new) = (dir.path().join("old.txt"), dir.path().join("new.txt"));
+    fs::write(&old, "Hello world!\n").unwrap();
+    fs::write(&new, "Goodbye world!\nExtra\n").unwrap();
+
+    let payload = std::panic::catch_unwind(|| side_by_side_text_diff(23)(&old, &new)).unwrap_err();
+    let message = strip_colors(payload.downcast_ref::<String>().unwrap());
+    assert!(
+        message.contains("Hello wor… | Goodbye w…\n           > Extra"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn log_lines() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("service.log", log_line_diff("%Y-%m-%dT%H:%M:%SZ"))
+        .unwrap();
+
+    writeln!(file, "2025-06-07T08:09:10Z INFO starting up").unwrap();
+    writeln!(file, "2025-06-07T08:09:11Z  WARN disk almost full").unwrap();
+    writeln!(file, "  free: 1%").unwrap();
+    writeln!(file, "shutting down").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "ERROR disk almost full")]
+fn log_lines_level_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("service.log", log_line_diff("%Y-%m-%dT%H:%M:%SZ"))
+        .unwrap();
+
+    writeln!(file, "2025-06-07T08:09:10Z INFO starting up").unwrap();
+    writeln!(file, "2025-06-07T08:09:11Z ERROR disk almost full").unwrap();
+    writeln!(file, "  free: 1%").unwrap();
+    writeln!(file, "shutting down").unwrap();
+}
+
+#[test]
+fn bom_aware_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile_bom_aware("utf16.txt").unwrap();
+
+    file.write_all(b"\xEF\xBB\xBFhi\n").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "invalid UTF-16BE")]
+fn bom_aware_invalid() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile_bom_aware("utf16.txt").unwrap();
+
+    file.write_all(b"\xFE\xFF\x00h\x00").unwrap();
+}
+
+#[test]
+fn bom_aware_update() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("utf16.txt"), b"\xFE\xFF\x00a").unwrap();
+
+    let mut mint = Mint::new(dir.path());
+    let mut file = mint.new_goldenfile_bom_aware("utf16.txt").unwrap();
+    file.write_all(b"hi").unwrap();
+    mint.update_goldenfiles();
+
+    let golden = fs::read(dir.path().join("utf16.txt")).unwrap();
+    assert_eq!(golden, b"\xFE\xFF\x00h\x00i");
+}
+
+#[test]
+fn strict_line_endings_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", Box::new(strict_line_ending_diff))
+        .unwrap();
+
+    writeln!(file, "Hello world!").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Line endings differ: old uses LF, new uses CRLF")]
+fn strict_line_endings_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", Box::new(strict_line_ending_diff))
+        .unwrap();
+
+    write!(file, "Hello world!\r\n").unwrap();
+}
+
+#[test]
+fn diff_trees() {
+    let (a, b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    for dir in [&a, &b] {
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("same.txt"), "same\n").unwrap();
+    }
+    fs::write(a.path().join("sub/changed.txt"), "old\n").unwrap();
+    fs::write(b.path().join("sub/changed.txt"), "new\n").unwrap();
+    fs::write(a.path().join("only_a.txt"), "").unwrap();
+    fs::write(b.path().join("sub/only_b.bin"), "").unwrap();
+
+    let differences = goldenfile::diff_trees(&a, &b, |path| goldenfile::get_differ_for_path(path));
+    let differences: Vec<String> = differences.iter().map(|d| d.to_string()).collect();
+    assert_eq!(differences.len(), 3);
+    assert_eq!(differences[0], "only in first tree: \"only_a.txt\"");
+    assert!(differences[1].starts_with("file differs: \"sub/changed.txt\""));
+    assert_eq!(differences[2], "only in second tree: \"sub/only_b.bin\"");
+}
+
+#[test]
+fn files_match_inferred() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = dir.path().join("records.jsonl");
+    let text = dir.path().join("match1.txt");
+    fs::write(&text, "Hello world!\n").unwrap();
+    assert!(goldenfile::files_match(
+        "tests/goldenfiles/match1.txt",
+        &text
+    ));
+
+    fs::write(&text, "Hello world?\n").unwrap();
+    assert!(!goldenfile::files_match(
+        "tests/goldenfiles/match1.txt",
+        &text
+    ));
+    fs::write(
+        &records,
+        "{\"name\":\"alpha\",\"id\":1,\"tags\":[\"a\",\"b\"]}\n\
+         {\"name\":\"beta\",\"id\":2,\"tags\":[]}\n",
+    )
+    .unwrap();
+    assert!(goldenfile::files_match(
+        "tests/goldenfiles/records.jsonl",
+        &records
+    ));
+}
+
+#[test]
+fn differ_by_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = dir.path().join("records");
+    fs::write(
+        &records,
+        "{\"id\":1,\"name\":\"alpha\",\"tags\":[\"a\",\"b\"]}\n\
+         {\"id\":2,\"name\":\"beta\",\"tags\":[]}\n",
+    )
+    .unwrap();
+
+    let differ = goldenfile::get_differ_for_extension("jsonl");
+    differ(Path::new("tests/goldenfiles/records.jsonl"), &records);
+}
+
+#[test]
+#[should_panic(expected = "records.jsonl: files differ\n")]
+fn differ_by_unknown_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let records = dir.path().join("records");
+    fs::write(
+        &records,
+        "{\"id\":1,\"name\":\"alpha\",\"tags\":[\"a\",\"b\"]}\n",
+    )
+    .unwrap();
+
+    let differ = goldenfile::get_differ_for_extension("");
+    differ(Path::new("tests/goldenfiles/records.jsonl"), &records);
+}
+
+#[test]
+fn golden_dir_excludes() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let dir = mint
+        .golden_dir_with_excludes("tree", &["*.log", "Cargo.lock"])
+        .unwrap();
+    fs::create_dir(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.txt"), "top\n").unwrap();
+    fs::write(dir.join("sub/b.txt"), "nested\n").unwrap();
+    fs::write(dir.join("sub/run.log"), "run at 10:05\n").unwrap();
+    fs::write(dir.join("Cargo.lock"), "").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "golden directory \"tree\" differs\n  no longer produced: \"sub/b.txt\"")]
+fn golden_dir_missing_file() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let dir = mint.golden_dir_with_excludes("tree", &["*.log"]).unwrap();
+    fs::write(dir.join("a.txt"), "top\n").unwrap();
+}
+
+#[test]
+fn golden_dir_path_resolver() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::create_dir(golden.path().join("out")).unwrap();
+    fs::write(golden.path().join("out/a.txt"), "old\n").unwrap();
+
+    let mut mint = Mint::new("tests/goldenfiles");
+    let root = golden.path().to_path_buf();
+    mint.set_path_resolver(move |path| root.join(path));
+    let dir = mint
+        .golden_dir_with_excludes::<_, &str>("out", &[])
+        .unwrap();
+    fs::write(dir.join("a.txt"), "new\n").unwrap();
+    mint.update_goldenfiles();
+
+    let out = golden.path().join("out");
+    assert_eq!(fs::read_to_string(out.join("a.txt")).unwrap(), "new\n");
+}
+
+#[test]
+fn golden_dir_update() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::create_dir(golden.path().join("out")).unwrap();
+    fs::write(golden.path().join("out/stale.txt"), "stale\n").unwrap();
+    fs::write(golden.path().join("out/keep.log"), "kept\n").unwrap();
+
+    let mut mint = Mint::new(golden.path());
+    let dir = mint.golden_dir_with_excludes("out", &["*.log"]).unwrap();
+    fs::write(dir.join("a.txt"), "new\n").unwrap();
+    fs::write(dir.join("new.log"), "ignored\n").unwrap();
+    mint.update_goldenfiles();
+
+    let out = golden.path().join("out");
+    assert_eq!(fs::read_to_string(out.join("a.txt")).unwrap(), "new\n");
+    assert_eq!(fs::read_to_string(out.join("keep.log")).unwrap(), "kept\n");
+    assert!(!out.join("stale.txt").exists());
+    assert!(!out.join("new.log").exists());
+}
+
+#[test]
+fn write_patch() {
+    let dir = tempfile::tempdir().unwrap();
+    let patch = dir.path().join("update.patch");
+    let mut mint = Mint::new("tests/goldenfiles");
+    let match1 = mint.register_goldenfile("match1.txt").unwrap();
+    let match2 = mint.register_goldenfile("match2.txt").unwrap();
+    let created = mint.register_goldenfile("patch_created.txt").unwrap();
+    fs::write(&match1, "Hello patch!\n").unwrap();
+    fs::write(&match2, "foobar\n").unwrap();
+    fs::write(&created, "new\n").unwrap();
+
+    let patched = mint.write_patch(&patch);
+    assert_eq!(
+        patched,
+        ["match1.txt", "patch_created.txt"].map(PathBuf::from)
+    );
+    assert_eq!(
+        fs::read_to_string(&patch).unwrap(),
+        "diff --git a/match1.txt b/match1.txt\n\
+         --- a/match1.txt\n\
+         +++ b/match1.txt\n\
+         @@ -1 +1 @@\n\
+         -Hello world!\n\
+         +Hello patch!\n\
+         diff --git a/patch_created.txt b/patch_created.txt\n\
+         new file mode 100644\n\
+         --- /dev/null\n\
+         +++ b/patch_created.txt\n\
+         @@ -0,0 +1 @@\n\
+         +new\n"
+    );
+    assert!(!Path::new("tests/goldenfiles/patch_created.txt").exists());
+
+    fs::write(&match1, "Hello world!\n").unwrap();
+    fs::write(&created, "").unwrap();
+}
+
+#[test]
+fn write_patch_relative_to_golden_root() {
+    let golden = tempfile::tempdir().unwrap();
+    fs::create_dir(golden.path().join("sub")).unwrap();
+    fs::write(golden.path().join("sub/out.txt"), "old\n").unwrap();
+    let patch = golden.path().join("update.patch");
+    let mut mint = Mint::new(golden.path());
+    let out = mint.register_goldenfile("sub/out.txt").unwrap();
+    mint.prepare_dirs();
+    fs::write(&out, "new\n").unwrap();
+
+    mint.write_patch(&patch);
+    let text = fs::read_to_string(&patch).unwrap();
+    assert!(text.starts_with(
+        "diff --git a/sub/out.txt b/sub/out.txt\n--- a/sub/out.txt\n+++ b/sub/out.txt\n"
+    ));
+
+    fs::write(&out, "old\n").unwrap();
+}
+
+#[test]
+fn propose_goldenfiles() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut mint = Mint::new("tests/goldenfiles");
+    let match1 = mint.register_goldenfile("match1.txt").unwrap();
+    let match2 = mint.register_goldenfile("match2.txt").unwrap();
+    let mut file = mint.new_goldenfile("subdir/file1.txt").unwrap();
+    writeln!(file, "File in subdir").unwrap();
+    fs::write(&match1, "").unwrap();
+    fs::write(&match2, "Proposed\n").unwrap();
+
+    let proposed = mint.propose_goldenfiles(&dir);
+    let proposed_match1 = dir.path().join("match1.txt");
+    let proposed_match2 = dir.path().join("match2.txt");
+    assert_eq!(proposed, [proposed_match1.clone(), proposed_match2.clone()]);
+    assert_eq!(fs::read_to_string(proposed_match1).unwrap(), "");
+    assert_eq!(fs::read_to_string(proposed_match2).unwrap(), "Proposed\n");
+
+    fs::write(&match1, "Hello world!\n").unwrap();
+    fs::write(&match2, "foobar\n").unwrap();
+}
+
+#[test]
+fn propose_goldenfiles_deletion() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut mint = Mint::new_nonempty("tests/goldenfiles");
+    let match1 = mint.register_goldenfile("match1.txt").unwrap();
+    let missing = mint.register_goldenfile("missing.txt").unwrap();
+    fs::write(&match1, "").unwrap();
+    fs::write(&missing, "").unwrap();
+
+    let proposed = mint.propose_goldenfiles(&dir);
+    assert_eq!(proposed, [dir.path().join("match1.txt.deleted")]);
+    assert_eq!(fs::read_to_string(&proposed[0]).unwrap(), "");
+    assert!(!dir.path().join("match1.txt").exists());
+
+    fs::write(&match1, "Hello world!\n").unwrap();
+}
+
+#[test]
+fn strip_trailing_lines_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = strip_trailing_lines_diff(2, Box::new(goldenfile::differs::text_diff));
+    let mut file = mint
+        .new_goldenfile_with_differ("footer.txt", differ)
+        .unwrap();
+
+    writeln!(file, "Hello world!").unwrap();
+    writeln!(file, "---").unwrap();
+    writeln!(file, "Finished in 1.23s").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "new: Goodbye world!")]
+fn strip_trailing_lines_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = strip_trailing_lines_diff(2, Box::new(goldenfile::differs::text_diff));
+    let mut file = mint
+        .new_goldenfile_with_differ("footer.txt", differ)
+        .unwrap();
+
+    writeln!(file, "Goodbye world!").unwrap();
+    writeln!(file, "---").unwrap();
+    writeln!(file, "Finished in 1.23s").unwrap();
+}
+
+fn machine_values() -> std::collections::HashMap<String, String> {
+    [
+        ("alice", "<USER>"),
+        ("ci-runner-7", "<HOST>"),
+        ("/home/alice/project", "<BUILD>"),
+    ]
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .into()
+}
+
+#[test]
+fn scrubbed_with_map() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = scrubbed_with_map_diff(machine_values(), Box::new(goldenfile::differs::text_diff));
+    let mut file = mint
+        .new_goldenfile_with_differ("build_info.txt", differ)
+        .unwrap();
+
+    writeln!(file, "built by alice on ci-runner-7").unwrap();
+    writeln!(file, "output in /home/alice/project/target").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "new: output in <BUILD>/build")]
+fn scrubbed_with_map_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = scrubbed_with_map_diff(machine_values(), Box::new(goldenfile::differs::text_diff));
+    let mut file = mint
+        .new_goldenfile_with_differ("build_info.txt", differ)
+        .unwrap();
+
+    writeln!(file, "built by alice on ci-runner-7").unwrap();
+    writeln!(file, "output in /home/alice/project/build").unwrap();
+}
+
+fn footer_line_map(line: &str) -> Option<String> {
+    match line.strip_prefix("Finished in ") {
+        Some(_) => Some("Finished in <time>".to_string()),
+        None => (line != "---").then(|| line.to_string()),
+    }
+}
+
+#[test]
+fn line_map_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = line_map_diff(footer_line_map, Box::new(goldenfile::differs::text_diff));
+    let mut file = mint
+        .new_goldenfile_with_differ("footer.txt", differ)
+        .unwrap();
+
+    writeln!(file, "Hello world!").unwrap();
+    writeln!(file, "Finished in 1.23s").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "old: Finished in <time>")]
+fn line_map_changed() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = line_map_diff(footer_line_map, Box::new(goldenfile::differs::text_diff));
+    let mut file = mint
+        .new_goldenfile_with_differ("footer.txt", differ)
+        .unwrap();
+
+    writeln!(file, "Hello world!").unwrap();
+    writeln!(file, "Failed after 1.23s").unwrap();
+}
+
+#[test]
+fn update_idempotent() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("match1.txt").unwrap();
+    writeln!(file, "Hello world!").unwrap();
+
+    mint.verify_update_idempotent();
+}
+
+#[test]
+#[should_panic(expected = "goldenfiles would still differ after updating: match1.txt")]
+fn update_not_idempotent() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    // Only normalizes the new side, so a freshly updated golden still differs.
+    let differ: Differ = Box::new(|old, new| {
+        let old = fs::read_to_string(old).unwrap();
+        let new = fs::read_to_string(new).unwrap();
+        assert_eq!(old, new.to_lowercase());
+    });
+    let mut file = mint
+        .new_goldenfile_with_differ("match1.txt", differ)
+        .unwrap();
+    writeln!(file, "Hello world!").unwrap();
+
+    mint.verify_update_idempotent();
+}
+
+#[test]
+fn no_goldenfile() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.assert_no_goldenfile("not_produced.txt").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "not_produced.txt: expected no goldenfile, but 6 bytes were written")]
+fn no_goldenfile_written() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let path = mint.assert_no_goldenfile("not_produced.txt").unwrap();
+    fs::write(path, "oops!\n").unwrap();
+}
+
+#[test]
+fn no_goldenfile_update() {
+    let dir = tempfile::tempdir().unwrap();
+    let stale = dir.path().join("stale.txt");
+    fs::write(&stale, "stale\n").unwrap();
+
+    let mut mint = Mint::new(dir.path());
+    mint.assert_no_goldenfile("stale.txt").unwrap();
+    mint.update_goldenfiles();
+
+    assert!(!stale.exists());
+}
+
+#[test]
+fn treat_empty_as_equal() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("empty_old_empty_new.bin"), "").unwrap();
+    fs::write(dir.path().join("empty_old_full_new.bin"), "").unwrap();
+    fs::write(dir.path().join("full_old_empty_new.bin"), "old").unwrap();
+
+    let mut mint = Mint::new(dir.path());
+    mint.set_treat_empty_as_equal(true);
+    for (name, contents) in [
+        ("empty_old_empty_new.bin", ""),
+        ("missing_old_empty_new.bin", ""),
+        ("empty_old_full_new.bin", "new"),
+        ("full_old_empty_new.bin", ""),
+    ] {
+        let path = mint.register_goldenfile(name).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    let failed: Vec<String> = mint
+        .soft_check()
+        .iter()
+        .map(|failure| match failure {
+            CheckError::Changed { file, .. } => file.display().to_string(),
+            CheckError::Io { file, .. } => panic!("Error reading {:?}", file),
+        })
+        .collect();
+    assert_eq!(failed, ["empty_old_full_new.bin", "full_old_empty_new.bin"]);
+}
+
+/// A mono 16-bit 8 kHz WAV file with a `LIST` metadata chunk.
+#[cfg(feature = "wav")]
+fn wav_with_metadata(samples: &[i16]) -> Vec<u8> {
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    let mut chunks = b"fmt \x10\0\0\0\x01\0\x01\0\x40\x1f\0\0\x80\x3e\0\0\x02\0\x10\0".to_vec();
+    chunks.extend(b"LIST\x04\0\0\0INFO");
+    chunks.extend(b"data");
+    chunks.extend((data.len() as u32).to_le_bytes());
+    chunks.extend(data);
+    let mut wav = b"RIFF".to_vec();
+    wav.extend((4 + chunks.len() as u32).to_le_bytes());
+    wav.extend(b"WAVE");
+    wav.extend(chunks);
+    wav
+}
+
+#[test]
+#[cfg(feature = "wav")]
+fn wav_match() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("tone.wav").unwrap();
+
+    let samples = [0, 1001, 2000, 999, 0, -1000, -2000, -1000];
+    file.write_all(&wav_with_metadata(&samples)).unwrap();
+}
+
+#[test]
+#[cfg(feature = "wav")]
+#[should_panic(expected = "Sample 2 (frame 2, channel 0) differs by 0.0030")]
+fn wav_sample_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint.new_goldenfile("tone.wav").unwrap();
+
+    let samples = [0, 1000, 2100, 1000, 0, -1000, -2000, -1000];
+    file.write_all(&wav_with_metadata(&samples)).unwrap();
+}
+
+#[test]
+#[cfg(feature = "wav")]
+#[should_panic(expected = "WAV lengths differ: old has 8 frames, new has 2")]
+fn wav_length_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("tone.wav", wav_diff(0.01))
+        .unwrap();
+
+    file.write_all(&wav_with_metadata(&[0, 1000])).unwrap();
+}
+
+#[test]
+fn hash_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("manifest.txt");
+    fs::write(dir.path().join("cached.txt"), "cached\n").unwrap();
+    {
+        let mut mint = Mint::new(dir.path());
+        mint.set_hash_cache(&manifest).unwrap();
+        let mut file = mint.new_goldenfile("cached.txt").unwrap();
+        writeln!(file, "cached").unwrap();
+    }
+    assert_eq!(fs::read_to_string(&manifest).unwrap().lines().count(), 1);
+
+    // The differ would fail, but it is skipped for contents known to match.
+    let mut mint = Mint::new(dir.path());
+    mint.set_hash_cache(&manifest).unwrap();
+    let differ: Differ = Box::new(|_, _| panic!("differ ran"));
+    let mut file = mint
+        .new_goldenfile_with_differ("cached.txt", differ)
+        .unwrap();
+    writeln!(file, "cached").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "differ ran")]
+fn hash_cache_miss() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("manifest.txt");
+    fs::write(dir.path().join("cached.txt"), "cached\n").unwrap();
+    {
+        let mut mint = Mint::new(dir.path());
+        mint.set_hash_cache(&manifest).unwrap();
+        let mut file = mint.new_goldenfile("cached.txt").unwrap();
+        writeln!(file, "cached").unwrap();
+    }
+
+    let mut mint = Mint::new(dir.path());
+    mint.set_hash_cache(&manifest).unwrap();
+    let differ: Differ = Box::new(|_, _| panic!("differ ran"));
+    let mut file = mint
+        .new_goldenfile_with_differ("cached.txt", differ)
+        .unwrap();
+    writeln!(file, "changed").unwrap();
+}
+
+#[test]
+fn trailing_pad() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = binary_diff_ignoring_trailing_pad(8, LogicalLength::EndMarker(b"END".to_vec()));
+    let mut file = mint
+        .new_goldenfile_with_differ("padded.bin", differ)
+        .unwrap();
+
+    file.write_all(b"HEADER\x01\x02\x03END\xff\xff\xff")
+        .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "Files differ at byte 8: old is 15 bytes, new is 12 bytes")]
+fn trailing_pad_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let differ = binary_diff_ignoring_trailing_pad(8, LogicalLength::Bytes(12));
+    let mut file = mint
+        .new_goldenfile_with_differ("padded.bin", differ)
+        .unwrap();
+
+    file.write_all(b"HEADER\x01\x09\x03END").unwrap();
+}
+
+#[test]
+#[cfg(feature = "vt100")]
+fn vt100_same_screen() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("screen.txt", vt100_diff(20, 4))
+        .unwrap();
+
+    write!(
+        file,
+        "ls\r\n\x1b[1m\x1b[32mok\x1b[m\x1b[2Cold\r\nprogress: 10%\rprogress: 100%\r\n"
+    )
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "vt100")]
+#[should_panic(
+    expected = "Screens differ at row 2, column 1: old 'o' (bold, fg 2), new 'o' (plain)"
+)]
+fn vt100_attribute_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("screen.txt", vt100_diff(20, 4))
+        .unwrap();
+
+    write!(file, "ls\r\nok  old\r\nprogress: 100%\r\n").unwrap();
+}
+
+#[test]
+fn lenient_goldenfile_mismatch() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let path = mint.register_goldenfile_lenient("match1.txt").unwrap();
+    fs::write(&path, "Hello flaky world!\n").unwrap();
+
+    assert!(mint.try_check_goldenfiles().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "match2.txt: files differ\n  old: foobar\n  new: Foo baz!")]
+fn lenient_goldenfile_alongside_strict() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let lenient = mint.register_goldenfile_lenient("match1.txt").unwrap();
+    let strict = mint.register_goldenfile("match2.txt").unwrap();
+    fs::write(lenient, "Hello flaky world!\n").unwrap();
+    fs::write(strict, "Foo baz!\n").unwrap();
+}
+
+#[test]
+fn regen_matches_on_retry() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let runs = Rc::new(Cell::new(0));
+    let counted = runs.clone();
+    let regen = move |file: &mut File| {
+        counted.set(counted.get() + 1);
+        let greeting = if counted.get() == 2 {
+            "world"
+        } else {
+            "flaky world"
+        };
+        writeln!(file, "Hello {}!", greeting)
+    };
+    let path = mint
+        .register_goldenfile_with_regen("match1.txt", regen, 3)
+        .unwrap();
+    fs::write(&path, "Hello flaky world!\n").unwrap();
+
+    assert!(mint.try_check_goldenfiles().is_ok());
+    assert_eq!(runs.get(), 2);
+}
+
+#[test]
+#[should_panic(expected = "match1.txt: none of 3 attempts matched\nattempt 1: ")]
+fn regen_reports_every_attempt() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let regen = |file: &mut File| writeln!(file, "Hello flaky world!");
+    let path = mint
+        .register_goldenfile_with_regen("match1.txt", regen, 2)
+        .unwrap();
+    fs::write(&path, "Hello flaky world!\n").unwrap();
+}
+
+#[derive(Clone, Default)]
+struct MemoryStore(Rc<RefCell<HashMap<PathBuf, Vec<u8>>>>);
+
+impl GoldenStore for MemoryStore {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let goldens = self.0.borrow();
+        let golden = goldens.get(path).ok_or(io::ErrorKind::NotFound)?;
+        Ok(golden.clone())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.0
+            .borrow_mut()
+            .insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.0.borrow_mut().remove(path);
+        Ok(())
+    }
+}
+
+#[test]
+#[should_panic(expected = "out.txt: files differ\n  old: stored\n  new: produced")]
+fn golden_store_mismatch() {
+    let store = MemoryStore::default();
+    store.write(Path::new("out.txt"), b"stored\n").unwrap();
+    let mut mint = Mint::with_store(store);
+    let mut file = mint.new_goldenfile("out.txt").unwrap();
+
+    writeln!(file, "produced").unwrap();
+}
+
+#[test]
+fn golden_store_update() {
+    let store = MemoryStore::default();
+    store.write(Path::new("same.txt"), b"same\n").unwrap();
+    store.write(Path::new("gone.txt"), b"stale\n").unwrap();
+    let mut mint = Mint::with_store(store.clone());
+    let mut same = mint.new_goldenfile("same.txt").unwrap();
+    let mut added = mint.new_goldenfile("dir/added.txt").unwrap();
+    mint.assert_no_goldenfile("gone.txt").unwrap();
+    writeln!(same, "same").unwrap();
+    writeln!(added, "added").unwrap();
+    mint.update_goldenfiles();
+    drop(mint);
+
+    let goldens = store.0.borrow();
+    let mut paths: Vec<&PathBuf> = goldens.keys().collect();
+    paths.sort();
+    assert_eq!(paths, [Path::new("dir/added.txt"), Path::new("same.txt")]);
+    assert_eq!(goldens[Path::new("dir/added.txt")], b"added\n");
+}
+
+#[cfg(feature = "cas")]
+fn cas_blobs(root: &Path) -> usize {
+    fs::read_dir(root.join(".golden-cas/blobs"))
+        .unwrap()
+        .count()
+}
+
+#[test]
+#[cfg(feature = "cas")]
+fn cas_store_dedupes_goldens() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut mint = Mint::with_store(goldenfile::CasStore::new(dir.path()));
+    for (path, contents) in [
+        ("a.txt", "shared"),
+        ("sub/b.txt", "shared"),
+        ("c.txt", "own"),
+    ] {
+        let mut file = mint.new_goldenfile(path).unwrap();
+        writeln!(file, "{}", contents).unwrap();
+    }
+    mint.update_goldenfiles();
+    drop(mint);
+    assert_eq!(cas_blobs(dir.path()), 2);
+    let index = fs::read_to_string(dir.path().join(".golden-cas/index")).unwrap();
+    let paths: Vec<&str> = index
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(_, p)| p)
+        .collect();
+    assert_eq!(paths, ["a.txt", "c.txt", "sub/b.txt"]);
+
+    let mut mint = Mint::with_store(goldenfile::CasStore::new(dir.path()));
+    let mut shared = mint.new_goldenfile("sub/b.txt").unwrap();
+    let mut changed = mint.new_goldenfile("c.txt").unwrap();
+    writeln!(shared, "shared").unwrap();
+    writeln!(changed, "changed").unwrap();
+    mint.update_goldenfiles();
+    drop(mint);
+    assert_eq!(cas_blobs(dir.path()), 2);
+    assert_eq!(
+        fs::read_dir(dir.path()).unwrap().count(),
+        1,
+        "only .golden-cas is written"
+    );
+}
+
+#[test]
+#[cfg(feature = "cas")]
+fn cas_store_shared_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = goldenfile::CasStore::new(dir.path());
+    store.write(Path::new("a.txt"), b"a1\n").unwrap();
+    store.write(Path::new("b.txt"), b"b1\n").unwrap();
+    let mut mint_a = Mint::with_store(goldenfile::CasStore::new(dir.path()));
+    let mut mint_b = Mint::with_store(goldenfile::CasStore::new(dir.path()));
+    let mut a = mint_a.new_goldenfile("a.txt").unwrap();
+    let mut b = mint_b.new_goldenfile("b.txt").unwrap();
+    writeln!(a, "a2").unwrap();
+    writeln!(b, "b2").unwrap();
+
+    mint_a.update_goldenfiles();
+    mint_b.update_goldenfiles();
+    drop((mint_a, mint_b));
+    assert_eq!(store.read(Path::new("a.txt")).unwrap(), b"a2\n");
+    assert_eq!(store.read(Path::new("b.txt")).unwrap(), b"b2\n");
+    assert_eq!(cas_blobs(dir.path()), 2);
+}
+
+#[test]
+#[cfg(feature = "cas")]
+#[should_panic(expected = "a.txt: files differ\n  old: old\n  new: new")]
+fn cas_store_mismatch() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = goldenfile::CasStore::new(dir.path());
+    store.write(Path::new("a.txt"), b"old\n").unwrap();
+    let mut mint = Mint::with_store(store);
+    let mut file = mint.new_goldenfile("a.txt").unwrap();
+    writeln!(file, "new").unwrap();
+}
+
+fn header_fields() -> Vec<(String, std::ops::Range<usize>)> {
+    vec![
+        ("magic".to_string(), 0..4),
+        ("version".to_string(), 4..6),
+        ("flags".to_string(), 6..8),
+    ]
+}
+
+#[test]
+fn struct_fields_ignore_payload() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("header.bin", struct_fields_binary_diff(header_fields()))
+        .unwrap();
+
+    file.write_all(b"HDR1\x00\x02\x10\x00PAYLOAD-v2, longer")
+        .unwrap();
+}
+
+#[test]
+#[should_panic(
+    expected = "Fields differ: old is 18 bytes, new is 18 bytes\n  version (bytes 4..6): old 0002, new 0003"
+)]
+fn struct_fields_version_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("header.bin", struct_fields_binary_diff(header_fields()))
+        .unwrap();
+
+    file.write_all(b"HDR1\x00\x03\x10\x00PAYLOAD-v1").unwrap();
+}
+
+#[test]
+fn check_any_candidate_matches() {
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("first.txt"), dir.path().join("second.txt"));
+    fs::write(&first, "Hello other world!\n").unwrap();
+    fs::write(&second, "Hello world!\n").unwrap();
+
+    let mint = Mint::new("tests/goldenfiles");
+    mint.check_any("match1.txt", vec![first, second]);
+}
+
+#[test]
+#[should_panic(expected = "match1.txt: no candidate matches the goldenfile")]
+fn check_any_no_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("first.txt"), dir.path().join("second.txt"));
+    fs::write(&first, "Hello other world!\n").unwrap();
+
+    let mint = Mint::new("tests/goldenfiles");
+    mint.check_any("match1.txt", vec![first, second]);
+}
+
+#[test]
+#[should_panic(expected = "wrote 20 bytes, more than the limit of 16 set by Mint::max_total_bytes")]
+fn max_total_bytes_exceeded() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.max_total_bytes(16);
+    mint.write_goldenfile("match1.txt", "Hello world!\n")
+        .unwrap();
+    let mut writer = mint.new_goldenfile_guard("match2.txt").unwrap();
+
+    writer.write_all(b"foobar\n").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "wrote 20 bytes, more than the limit of 16 set by Mint::max_total_bytes")]
+fn max_total_bytes_exceeded_through_file() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.max_total_bytes(16);
+    let mut file1 = mint.new_goldenfile("match1.txt").unwrap();
+    let mut file2 = mint.new_goldenfile("match2.txt").unwrap();
+
+    writeln!(file1, "Hello world!").unwrap();
+    writeln!(file2, "foobar").unwrap();
+}
+
+#[test]
+fn max_total_bytes_within_limit() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.max_total_bytes(20);
+    mint.write_goldenfile("match1.txt", "Hello world!\n")
+        .unwrap();
+    let mut writer = mint.new_goldenfile_fmt_writer("match2.txt").unwrap();
+
+    writeln!(writer, "foobar").unwrap();
+}
+
+#[test]
+fn max_total_bytes_skips_failed_writes() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.max_total_bytes(20);
+    mint.write_goldenfile("match1.txt", "Hello world!\n")
+        .unwrap();
+    assert!(mint
+        .write_goldenfile("/match1.txt", "Hello world!\n")
+        .is_err());
+    mint.set_atomic_writes(true);
+    assert!(mint
+        .write_goldenfile("/match1.txt", "Hello world!\n")
+        .is_err());
+
+    mint.write_goldenfile("match2.txt", "foobar\n").unwrap();
+}
+
+#[test]
+fn path_separators_normalized() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("paths.txt", Box::new(path_sep_normalizing_diff))
+        .unwrap();
+
+    writeln!(file, "src\\main.rs:3:5: unused variable `x`").unwrap();
+    writeln!(file, "wrote .\\out\\report.json and C:\\logs\\run.log").unwrap();
+    writeln!(file, "{{\"icon\": \"assets\\\\logo.png\"}}").unwrap();
+    writeln!(file, "pattern \\d+\\.\\d+ matched \"tab\\there\\n\"").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "paths.txt: files differ\n  old: pattern")]
+fn path_separators_escapes_kept() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("paths.txt", Box::new(path_sep_normalizing_diff))
+        .unwrap();
+
+    writeln!(file, "src\\main.rs:3:5: unused variable `x`").unwrap();
+    writeln!(file, "wrote .\\out\\report.json and C:\\logs\\run.log").unwrap();
+    writeln!(file, "{{\"icon\": \"assets\\\\logo.png\"}}").unwrap();
+    writeln!(file, "pattern /d+/./d+ matched \"tab/there/n\"").unwrap();
+}
+
+#[test]
+fn golden_value_sorted() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let value = format!(
+        r#"{{"name":"demo","tags":["a","b"],"count":{},"empty":{{}}}}"#,
+        2
+    );
+
+    mint.assert_golden_value("value.json", &value).unwrap();
+}
+
+#[test]
+#[should_panic(expected = "JSON differs at $.count: expected 2, found 3")]
+fn golden_value_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let value = format!(
+        r#"{{"name":"demo","tags":["a","b"],"count":{},"empty":{{}}}}"#,
+        3
+    );
+
+    mint.assert_golden_value("value.json", &value).unwrap();
+}
+
+#[test]
+fn canonicalize_json_infers_json_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.set_canonicalize_json(true);
+    let mut file = mint.new_goldenfile("value.json").unwrap();
+
+    write!(
+        file,
+        r#"{{"tags": ["a", "b"], "name": "demo", "empty": {{}}, "count": 2}}"#
+    )
+    .unwrap();
+}
+
+#[test]
+fn canonicalize_json_on_update() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut mint = Mint::new(dir.path());
+    mint.set_canonicalize_json(true);
+    let mut json = mint
+        .new_goldenfile_with_differ("config.json", Box::new(json_diff))
+        .unwrap();
+    let mut invalid = mint.new_goldenfile("broken.json").unwrap();
+    write!(json, r#"{{"b": [1, {{"d": null, "c": true}}], "a": "x"}}"#).unwrap();
+    write!(invalid, "{{not json").unwrap();
+
+    mint.update_goldenfiles();
+    let canonical = "{\n  \"a\": \"x\",\n  \"b\": [\n    1,\n    {\n      \"c\": true,\n      \"d\": null\n    }\n  ]\n}\n";
+    assert_eq!(
+        fs::read_to_string(dir.path().join("config.json")).unwrap(),
+        canonical
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("broken.json")).unwrap(),
+        "{not json"
+    );
+}
+
+#[test]
+#[cfg(feature = "json-schema")]
+fn schema_golden_valid() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let path = mint.register_schema_goldenfile("user.schema.json").unwrap();
+    fs::write(
+        path,
+        r#"{"id": 7, "name": "Ada", "email": null, "roles": ["member"]}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "json-schema")]
+#[should_panic(expected = "user.schema.json: JSON doesn't match the schema:\n  \
+    $: missing required property \"name\"\n  \
+    $.id: expected integer, found 1.5\n  \
+    $.roles[1]: \"owner\" is not one of [\"admin\",\"member\"]\n  \
+    $.nickname: unexpected property")]
+fn schema_golden_invalid() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let path = mint.register_schema_goldenfile("user.schema.json").unwrap();
+    fs::write(
+        path,
+        r#"{"id": 1.5, "roles": ["member", "owner"], "nickname": "x"}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+#[cfg(feature = "json-schema")]
+fn schema_golden_not_updated() {
+    let dir = tempfile::tempdir().unwrap();
+    let schema = fs::read_to_string("tests/goldenfiles/user.schema.json").unwrap();
+    fs::write(dir.path().join("user.schema.json"), &schema).unwrap();
+    let mut mint = Mint::new(dir.path());
+    let path = mint.register_schema_goldenfile("user.schema.json").unwrap();
+    fs::write(path, r#"{"id": 7, "name": "Ada", "roles": []}"#).unwrap();
+
+    mint.update_goldenfiles();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("user.schema.json")).unwrap(),
+        schema
+    );
+}
+
+/// Validate `json` against `schema` with [`json_schema_diff`].
+#[cfg(feature = "json-schema")]
+fn check_schema(schema: &str, json: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let (schema_path, path) = (
+        dir.path().join("schema.json"),
+        dir.path().join("value.json"),
+    );
+    fs::write(&schema_path, schema).unwrap();
+    fs::write(&path, json).unwrap();
+    json_schema_diff(schema_path)(&path, &path);
+}
+
+#[test]
+#[cfg(feature = "json-schema")]
+fn schema_multiple_of_decimal() {
+    check_schema(r#"{"multipleOf": 0.1}"#, "0.3");
+    check_schema(r#"{"multipleOf": 0.01}"#, "19.99");
+}
+
+#[test]
+#[cfg(feature = "json-schema")]
+#[should_panic(expected = "$: 0.35 is not a multiple of 0.1")]
+fn schema_multiple_of_mismatch() {
+    check_schema(r#"{"multipleOf": 0.1}"#, "0.35");
+}
+
+#[test]
+#[cfg(feature = "json-schema")]
+#[should_panic(expected = "invalid schema: `multipleOf` must be greater than 0")]
+fn schema_multiple_of_zero() {
+    check_schema(r#"{"multipleOf": 0}"#, "3");
+}
+
+#[test]
+fn csv_keyed_reordered() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("inventory.csv", csv_keyed_diff("id"))
+        .unwrap();
+
+    write!(
+        file,
+        "id,name,price\n3,Gizmo,4.50\n1,Widget,9.99\n2,\"Gadget, large\",19.99\n"
+    )
+    .unwrap();
+}
+
+#[test]
+#[should_panic(expected = "CSV rows differ\n  \
+                           removed 3: 3,Gizmo,4.50\n  \
+                           added 4: 4,Doohickey,1.00\n  \
+                           changed 2: price: 19.99 -> 21.00")]
+fn csv_keyed_row_changes() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("inventory.csv", csv_keyed_diff("id"))
+        .unwrap();
+
+    write!(
+        file,
+        "id,name,price\n4,Doohickey,1.00\n2,\"Gadget, large\",21.00\n1,Widget,9.99\n"
+    )
+    .unwrap();
+}
+
+fn final_newline_ignored() -> Differ {
+    text_diff_opts(TextDiffOptions {
+        ignore_final_newline: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn ignore_final_newline_both_ways() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut added = mint
+        .new_goldenfile_with_differ("unterminated.txt", final_newline_ignored())
+        .unwrap();
+    let mut removed = mint
+        .new_goldenfile_with_differ("terminated.txt", final_newline_ignored())
+        .unwrap();
+
+    write!(added, "a\nb\n").unwrap();
+    write!(removed, "a\r\nb").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "unterminated.txt: files differ\n")]
+fn ignore_final_newline_extra_record() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("unterminated.txt", final_newline_ignored())
+        .unwrap();
+
+    write!(file, "a\nb\n\n").unwrap();
+}
+
+#[test]
+fn check_one_goldenfile() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    mint.write_goldenfile("match1.txt", "Hello world!\n")
+        .unwrap();
+    let path = mint.register_goldenfile("match2.txt").unwrap();
+    fs::write(&path, "monkeybrains\n").unwrap();
+
+    assert!(mint.check_one("match1.txt").is_ok());
+    assert!(matches!(
+        mint.check_one("match2.txt"),
+        Err(CheckError::Changed { file, .. }) if file == Path::new("match2.txt")
+    ));
+    assert!(matches!(
+        mint.check_one("unregistered.txt"),
+        Err(CheckError::Io { error, .. }) if error.kind() == std::io::ErrorKind::NotFound
+    ));
+
+    fs::write(&path, "foobar\n").unwrap();
+    assert!(mint.check_one("match2.txt").is_ok());
+}
+
+#[test]
+fn locale_numbers_normalized() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("report.txt", Box::new(locale_numeric_diff))
+        .unwrap();
+
+    writeln!(file, "Total: 1\u{202f}234\u{202f}567,89 EUR").unwrap();
+    writeln!(file, "Items: 12\u{a0}345").unwrap();
+    writeln!(file, "Ratio: 0,75").unwrap();
+    writeln!(file, "Version 1.2.3 from 192.168.1.1").unwrap();
+}
+
+#[test]
+#[should_panic(expected = "report.txt: files differ\n  old: Items: 12345\n  new: Items: 12346")]
+fn locale_numbers_value_diff() {
+    let mut mint = Mint::new("tests/goldenfiles");
+    let mut file = mint
+        .new_goldenfile_with_differ("report.txt", Box::new(locale_numeric_diff))
+        .unwrap();
+
+    writeln!(file, "Total: 1.234.567,89 EUR").unwrap();
+    writeln!(file, "Items: 12'346").unwrap();
+    writeln!(file, "Ratio: 0.75").unwrap();
+    writeln!(file, "Version 1.2.3 from 192.168.1.1").unwrap();
+}